@@ -0,0 +1,263 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use anyhow::anyhow;
+use libasampo::{
+    prelude::*,
+    samplesets::export::{
+        Conversion, ExportJob, ExportJobMessage, RateConversionQuality, WavSampleFormat, WavSpec,
+    },
+};
+
+use crate::{export_cover_image, exported_files, rename_exported_files, savefile, util};
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+/// Enables the JSON troubleshooting log for the session, regardless of the config.
+pub const JSON_LOG_FLAG: &str = "--json-log";
+
+const USAGE: &str = "Usage: asampo-gtk [--json-log] [SAVEFILE]\n       \
+    asampo-gtk --export-set NAME --savefile FILE --target-dir DIR [--format FORMAT]\n\n\
+    Formats: copy (default), wav16/44.1k, wav16/48k";
+
+pub const EXPORT_FORMATS: [(&str, Option<u32>); 3] = [
+    ("copy", None),
+    ("wav16/44.1k", Some(44100)),
+    ("wav16/48k", Some(48000)),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportArgs {
+    pub set_name: String,
+    pub savefile: String,
+    pub target_dir: String,
+    pub sample_rate: Option<u32>,
+}
+
+/// Parse the arguments for a headless export, returning `None` if no export was requested.
+pub fn parse_export_args(args: &[String]) -> Result<Option<ExportArgs>, anyhow::Error> {
+    if !args.iter().any(|arg| arg == "--export-set") {
+        return Ok(None);
+    }
+
+    let mut set_name = None;
+    let mut savefile = None;
+    let mut target_dir = None;
+    let mut sample_rate = None;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or(anyhow!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--export-set" => set_name = Some(value()?),
+            "--savefile" => savefile = Some(value()?),
+            "--target-dir" => target_dir = Some(value()?),
+            "--format" => {
+                let format = value()?;
+
+                sample_rate = EXPORT_FORMATS
+                    .iter()
+                    .find(|(name, _)| *name == format)
+                    .ok_or(anyhow!("Unknown export format: {format}"))?
+                    .1;
+            }
+            _ => return Err(anyhow!("Unexpected argument: {arg}")),
+        }
+    }
+
+    Ok(Some(ExportArgs {
+        set_name: set_name.ok_or(anyhow!("Missing --export-set"))?,
+        savefile: savefile.ok_or(anyhow!("Missing --savefile"))?,
+        target_dir: target_dir.ok_or(anyhow!("Missing --target-dir"))?,
+        sample_rate,
+    }))
+}
+
+/// Parse the arguments for an interactive session, returning the savefile to open, if any.
+pub fn parse_savefile_arg(args: &[String]) -> Result<Option<String>, anyhow::Error> {
+    match args {
+        [] => Ok(None),
+        [arg] if arg.starts_with("--") => Err(anyhow!("Unexpected argument: {arg}")),
+        [savefile] => Ok(Some(savefile.clone())),
+        [_, arg, ..] => Err(anyhow!("Unexpected argument: {arg}")),
+    }
+}
+
+/// Remove every occurrence of `flag` from `args`, returning whether there was one.
+pub fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
+pub fn usage_error(e: anyhow::Error) -> i32 {
+    eprintln!("{e}\n\n{USAGE}");
+    EXIT_USAGE
+}
+
+/// Export a sample set from a savefile without showing a window, printing progress to stdout.
+pub fn run_export(args: ExportArgs) -> i32 {
+    match export(args) {
+        Ok(0) => EXIT_OK,
+        Ok(errors) => {
+            eprintln!("Export finished with {errors} error(s)");
+            EXIT_FAILURE
+        }
+        Err(e) => {
+            eprintln!("Export failed: {e}");
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn export(args: ExportArgs) -> Result<usize, anyhow::Error> {
+    if savefile::is_encrypted(&args.savefile)? {
+        return Err(anyhow!(
+            "Encrypted savefiles can only be exported from the application"
+        ));
+    }
+
+    let model = savefile::Savefile::load(&args.savefile)?;
+
+    let set_uuid = *model
+        .sets
+        .values()
+        .find(|set| set.name() == args.set_name)
+        .ok_or(anyhow!("No sample set named '{}'", args.set_name))?
+        .uuid();
+
+    let set = model.set_for_export(&set_uuid)?;
+
+    let resolved_dir =
+        util::resolve_export_target(&args.target_dir, Some(&args.savefile), set.name())?;
+
+    std::fs::create_dir_all(&resolved_dir)?;
+
+    let num_samples = set.len();
+    let cover_image = model.sets_cover_images.get(&set_uuid).cloned();
+    let sources = model.sources.clone();
+    let files = exported_files(
+        &resolved_dir,
+        &model.export_file_names(&set_uuid, None)?,
+        args.sample_rate.is_some(),
+    );
+
+    let (tx, rx) = std::sync::mpsc::channel::<ExportJobMessage>();
+
+    let job = ExportJob::new(
+        resolved_dir.clone(),
+        args.sample_rate.map(|sample_rate| {
+            Conversion::Wav(
+                WavSpec {
+                    channels: 2,
+                    sample_rate,
+                    bits_per_sample: 16,
+                    sample_format: WavSampleFormat::Int,
+                },
+                Some(RateConversionQuality::High),
+            )
+        }),
+    );
+
+    println!("Exporting '{}' to {resolved_dir}", set.name());
+
+    let worker = std::thread::spawn(move || job.perform(&set, &sources, Some(tx)));
+    let mut errors = 0;
+
+    for message in rx {
+        match message {
+            ExportJobMessage::ItemsCompleted(n) => println!("{n}/{num_samples}"),
+            ExportJobMessage::Error(e) => {
+                eprintln!("Error: {e}");
+                errors += 1;
+            }
+            ExportJobMessage::Finished => (),
+        }
+    }
+
+    worker
+        .join()
+        .map_err(|_| anyhow!("Export thread panicked"))?;
+
+    if let Err(e) = rename_exported_files(&files) {
+        eprintln!("Failed to rename exported files: {e}");
+        errors += 1;
+    }
+
+    if let Some(cover_image) = &cover_image {
+        if let Err(e) = export_cover_image(cover_image, &resolved_dir) {
+            eprintln!("Failed to export cover image: {e}");
+            errors += 1;
+        }
+    }
+
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_export_args() {
+        assert_eq!(parse_export_args(&args(&[])).unwrap(), None);
+
+        assert_eq!(
+            parse_export_args(&args(&[
+                "--export-set",
+                "MyKit",
+                "--savefile",
+                "proj.json",
+                "--target-dir",
+                "out/",
+                "--format",
+                "wav16/44.1k"
+            ]))
+            .unwrap(),
+            Some(ExportArgs {
+                set_name: "MyKit".to_string(),
+                savefile: "proj.json".to_string(),
+                target_dir: "out/".to_string(),
+                sample_rate: Some(44100),
+            })
+        );
+
+        assert!(parse_export_args(&args(&["--export-set", "MyKit"])).is_err());
+        assert!(parse_export_args(&args(&["--export-set", "MyKit", "--format", "mp3"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_savefile_arg() {
+        assert_eq!(parse_savefile_arg(&args(&[])).unwrap(), None);
+
+        assert_eq!(
+            parse_savefile_arg(&args(&["proj.json"])).unwrap(),
+            Some("proj.json".to_string())
+        );
+
+        assert!(parse_savefile_arg(&args(&["--savefile"])).is_err());
+        assert!(parse_savefile_arg(&args(&["a.json", "b.json"])).is_err());
+    }
+
+    #[test]
+    fn test_take_flag() {
+        let mut args = args(&["--json-log", "proj.json"]);
+
+        assert!(take_flag(&mut args, JSON_LOG_FLAG));
+        assert_eq!(args, vec!["proj.json"]);
+        assert!(!take_flag(&mut args, JSON_LOG_FLAG));
+    }
+}
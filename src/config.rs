@@ -2,6 +2,8 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
+use anyhow::anyhow;
+
 use crate::{configfile::ConfigFile, ext::OptionMapExt};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,13 +12,108 @@ pub enum SamplePlaybackBehavior {
     PlayUntilEnd,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SampleFilterMode {
+    #[default]
+    Exact,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ColorScheme {
+    #[default]
+    FollowSystem,
+    Dark,
+    Light,
+}
+
+/// The groups of settings that can be picked when importing settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingsGroup {
+    Audio,
+    Samples,
+    DrumMachine,
+    Appearance,
+    Savefiles,
+    Troubleshooting,
+}
+
+/// Maps the velocity levels of drum machine steps to the amplitudes they are played at.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum VelocityCurve {
+    #[default]
+    Linear,
+    Soft,
+    Hard,
+    Fixed,
+}
+
+impl VelocityCurve {
+    pub fn apply(&self, velocity: f32) -> f32 {
+        match self {
+            VelocityCurve::Linear => velocity,
+            VelocityCurve::Soft => velocity.sqrt(),
+            VelocityCurve::Hard => velocity * velocity,
+            VelocityCurve::Fixed => 1.0,
+        }
+    }
+}
+
+/// Which names to show on the drum machine pads.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum PadLabelSet {
+    #[default]
+    Drums,
+    Notes,
+    Custom,
+}
+
+/// A named set of audio output settings, for quickly switching between audio setups.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioProfile {
+    pub name: String,
+    pub output_samplerate_hz: u32,
+    pub buffer_size_frames: u16,
+    pub sample_rate_conversion_quality: audiothread::Quality,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct AppConfig {
     pub output_samplerate_hz: u32,
     pub buffer_size_frames: u16,
     pub sample_rate_conversion_quality: audiothread::Quality,
     pub config_save_path: String,
     pub sample_playback_behavior: SamplePlaybackBehavior,
+    pub sample_filter_mode: SampleFilterMode,
+    pub watch_sources: bool,
+
+    /// The most samples taken in from each loading source per update, or 0 for no limit.
+    pub source_loading_samples_per_tick: u32,
+    pub stop_preview_on_page_change: bool,
+
+    /// Volume of sample previews in percent.
+    pub preview_volume: u32,
+    pub velocity_curve: VelocityCurve,
+    pub pad_label_set: PadLabelSet,
+
+    /// Names for the drum machine pads when using [`PadLabelSet::Custom`], in pad order.
+    pub custom_pad_labels: Vec<String>,
+    pub samples_list_keep_place: bool,
+    pub samples_list_grouped: bool,
+    pub color_scheme: ColorScheme,
+    pub ui_scale_percent: u16,
+    pub troubleshooting_log: bool,
+    pub profiler_overlay: bool,
+    pub encrypt_savefiles: bool,
+    pub remember_savefile_password: bool,
+    pub reopen_last_workspace: bool,
+
+    /// Whether to list the changes made to the workspace before saving over its savefile.
+    pub review_changes_before_saving: bool,
+    pub recent_savefiles: Vec<String>,
+    pub filter_history: Vec<String>,
+    pub saved_searches: Vec<(String, String)>,
+    pub audio_profiles: Vec<AudioProfile>,
 }
 
 impl Default for AppConfig {
@@ -27,6 +124,28 @@ impl Default for AppConfig {
             sample_rate_conversion_quality: audiothread::Quality::Lowest,
             config_save_path: ConfigFile::default_path(),
             sample_playback_behavior: SamplePlaybackBehavior::PlayUntilEnd,
+            sample_filter_mode: SampleFilterMode::Exact,
+            watch_sources: false,
+            source_loading_samples_per_tick: 2000,
+            stop_preview_on_page_change: false,
+            preview_volume: 100,
+            velocity_curve: VelocityCurve::Linear,
+            pad_label_set: PadLabelSet::Drums,
+            custom_pad_labels: Vec::new(),
+            samples_list_keep_place: true,
+            samples_list_grouped: false,
+            color_scheme: ColorScheme::FollowSystem,
+            ui_scale_percent: 100,
+            troubleshooting_log: false,
+            profiler_overlay: false,
+            encrypt_savefiles: false,
+            remember_savefile_password: false,
+            reopen_last_workspace: false,
+            review_changes_before_saving: false,
+            recent_savefiles: Vec::new(),
+            filter_history: Vec::new(),
+            saved_searches: Vec::new(),
+            audio_profiles: Vec::new(),
         }
     }
 }
@@ -73,6 +192,8 @@ impl AppConfig {
 
     update_with!(plain with_buffer_size, buffer_size_frames, u16);
 
+    update_with!(plain with_preview_volume, preview_volume, u32);
+
     // update_with!(plain with_conversion_quality,
     //     sample_rate_conversion_quality,
     //     audiothread::Quality);
@@ -92,8 +213,342 @@ impl AppConfig {
         sample_playback_behavior,
         SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS,
         "sample playback behavior");
+
+    update_with!(choice with_sample_filter_mode_choice,
+        sample_filter_mode,
+        SAMPLE_FILTER_MODE_OPTIONS,
+        "sample filter mode");
+
+    update_with!(choice with_watch_sources_choice,
+        watch_sources,
+        WATCH_SOURCES_OPTIONS,
+        "source watching");
+
+    update_with!(choice with_source_loading_choice,
+        source_loading_samples_per_tick,
+        SOURCE_LOADING_OPTIONS,
+        "source loading");
+
+    update_with!(choice with_stop_preview_on_page_change_choice,
+        stop_preview_on_page_change,
+        STOP_PREVIEW_ON_PAGE_CHANGE_OPTIONS,
+        "stopping previews on page change");
+
+    update_with!(choice with_samples_list_keep_place_choice,
+        samples_list_keep_place,
+        SAMPLES_LIST_KEEP_PLACE_OPTIONS,
+        "samples list updates");
+
+    update_with!(choice with_velocity_curve_choice,
+        velocity_curve,
+        VELOCITY_CURVE_OPTIONS,
+        "velocity curve");
+
+    update_with!(choice with_pad_label_set_choice,
+        pad_label_set,
+        PAD_LABEL_SET_OPTIONS,
+        "drum pad labels");
+
+    update_with!(choice with_color_scheme_choice,
+        color_scheme,
+        COLOR_SCHEME_OPTIONS,
+        "color scheme");
+
+    update_with!(choice with_ui_scale_choice,
+        ui_scale_percent,
+        UI_SCALE_OPTIONS,
+        "interface scale");
+
+    update_with!(choice with_troubleshooting_log_choice,
+        troubleshooting_log,
+        TROUBLESHOOTING_LOG_OPTIONS,
+        "troubleshooting log");
+
+    update_with!(choice with_profiler_overlay_choice,
+        profiler_overlay,
+        PROFILER_OVERLAY_OPTIONS,
+        "performance overlay");
+
+    update_with!(choice with_savefile_encryption_choice,
+        encrypt_savefiles,
+        SAVEFILE_ENCRYPTION_OPTIONS,
+        "savefile encryption");
+
+    update_with!(choice with_savefile_password_memory_choice,
+        remember_savefile_password,
+        SAVEFILE_PASSWORD_MEMORY_OPTIONS,
+        "savefile password memory");
+
+    update_with!(choice with_reopen_last_workspace_choice,
+        reopen_last_workspace,
+        REOPEN_LAST_WORKSPACE_OPTIONS,
+        "reopening the last workspace");
+
+    update_with!(choice with_review_changes_choice,
+        review_changes_before_saving,
+        REVIEW_CHANGES_OPTIONS,
+        "reviewing changes before saving");
+
+    /// Whether `other` sets up the audio output differently, so that the audiothread has to be
+    /// respawned to apply it.
+    pub fn audio_output_differs(&self, other: &AppConfig) -> bool {
+        self.output_samplerate_hz != other.output_samplerate_hz
+            || self.buffer_size_frames != other.buffer_size_frames
+            || self.sample_rate_conversion_quality != other.sample_rate_conversion_quality
+    }
+
+    /// The names to show on the drum machine pads, in pad order. Pads left without a name
+    /// by the custom labels use their drum names.
+    pub fn pad_labels(&self) -> Vec<String> {
+        let labels: &[&str] = match self.pad_label_set {
+            PadLabelSet::Drums | PadLabelSet::Custom => &DRUM_PAD_LABELS,
+            PadLabelSet::Notes => &NOTE_PAD_LABELS,
+        };
+
+        labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| match self.pad_label_set {
+                PadLabelSet::Custom => self
+                    .custom_pad_labels
+                    .get(i)
+                    .filter(|custom| !custom.is_empty())
+                    .cloned()
+                    .unwrap_or(label.to_string()),
+                _ => label.to_string(),
+            })
+            .collect()
+    }
+
+    /// Use `labels` as the custom pad names, and switch to them.
+    pub fn with_custom_pad_labels(self, labels: Vec<String>) -> AppConfig {
+        AppConfig {
+            pad_label_set: PadLabelSet::Custom,
+            custom_pad_labels: labels.into_iter().take(NUM_PADS).collect(),
+            ..self
+        }
+    }
+
+    /// Check that all settings have values that can be presented in the settings page.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        OUTPUT_SAMPLE_RATE_OPTIONS
+            .key_for(&self.output_samplerate_hz)
+            .ok_or(anyhow!(
+                "Unsupported output sample rate: {}",
+                self.output_samplerate_hz
+            ))?;
+
+        SAMPLE_RATE_CONVERSION_QUALITY_OPTIONS
+            .key_for(&self.sample_rate_conversion_quality)
+            .ok_or(anyhow!("Unsupported sample rate conversion quality"))?;
+
+        SOURCE_LOADING_OPTIONS
+            .key_for(&self.source_loading_samples_per_tick)
+            .ok_or(anyhow!(
+                "Unsupported source loading batch size: {}",
+                self.source_loading_samples_per_tick
+            ))?;
+
+        UI_SCALE_OPTIONS
+            .key_for(&self.ui_scale_percent)
+            .ok_or(anyhow!(
+                "Unsupported interface scale: {}%",
+                self.ui_scale_percent
+            ))?;
+
+        if !(64..=8192).contains(&self.buffer_size_frames) {
+            return Err(anyhow!(
+                "Unsupported buffer size: {}",
+                self.buffer_size_frames
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Take the settings in `groups` from `imported`. Settings that only make sense on this
+    /// machine (config save path and recently used savefiles) are never imported.
+    pub fn with_imported_settings(
+        self,
+        imported: AppConfig,
+        groups: &[SettingsGroup],
+    ) -> AppConfig {
+        groups.iter().fold(self, |config, group| match group {
+            SettingsGroup::Audio => AppConfig {
+                output_samplerate_hz: imported.output_samplerate_hz,
+                buffer_size_frames: imported.buffer_size_frames,
+                sample_rate_conversion_quality: imported.sample_rate_conversion_quality,
+                preview_volume: imported.preview_volume,
+                audio_profiles: imported.audio_profiles.clone(),
+                ..config
+            },
+
+            SettingsGroup::Samples => AppConfig {
+                sample_playback_behavior: imported.sample_playback_behavior.clone(),
+                sample_filter_mode: imported.sample_filter_mode.clone(),
+                watch_sources: imported.watch_sources,
+                source_loading_samples_per_tick: imported.source_loading_samples_per_tick,
+                stop_preview_on_page_change: imported.stop_preview_on_page_change,
+                samples_list_keep_place: imported.samples_list_keep_place,
+                samples_list_grouped: imported.samples_list_grouped,
+                filter_history: imported.filter_history.clone(),
+                saved_searches: imported.saved_searches.clone(),
+                ..config
+            },
+
+            SettingsGroup::DrumMachine => AppConfig {
+                velocity_curve: imported.velocity_curve.clone(),
+                pad_label_set: imported.pad_label_set.clone(),
+                custom_pad_labels: imported.custom_pad_labels.clone(),
+                ..config
+            },
+
+            SettingsGroup::Appearance => AppConfig {
+                color_scheme: imported.color_scheme.clone(),
+                ui_scale_percent: imported.ui_scale_percent,
+                ..config
+            },
+
+            SettingsGroup::Savefiles => AppConfig {
+                encrypt_savefiles: imported.encrypt_savefiles,
+                remember_savefile_password: imported.remember_savefile_password,
+                reopen_last_workspace: imported.reopen_last_workspace,
+                review_changes_before_saving: imported.review_changes_before_saving,
+                ..config
+            },
+
+            SettingsGroup::Troubleshooting => AppConfig {
+                troubleshooting_log: imported.troubleshooting_log,
+                profiler_overlay: imported.profiler_overlay,
+                ..config
+            },
+        })
+    }
+
+    /// Move `filename` to the top of the list of recently used savefiles.
+    pub fn with_recent_savefile(self, filename: &str) -> AppConfig {
+        let mut recent_savefiles = vec![filename.to_string()];
+
+        recent_savefiles.extend(
+            self.recent_savefiles
+                .into_iter()
+                .filter(|recent| recent != filename)
+                .take(MAX_RECENT_SAVEFILES - 1),
+        );
+
+        AppConfig {
+            recent_savefiles,
+            ..self
+        }
+    }
+
+    /// Move `query` to the top of the list of recently used sample filters.
+    pub fn with_filter_history_entry(self, query: &str) -> AppConfig {
+        let mut filter_history = vec![query.to_string()];
+
+        filter_history.extend(
+            self.filter_history
+                .into_iter()
+                .filter(|recent| recent != query)
+                .take(MAX_FILTER_HISTORY - 1),
+        );
+
+        AppConfig {
+            filter_history,
+            ..self
+        }
+    }
+
+    /// Save `query` under `name`, replacing any saved search with the same name.
+    pub fn with_saved_search(self, name: &str, query: &str) -> AppConfig {
+        let mut saved_searches = self.saved_searches;
+
+        match saved_searches.iter_mut().find(|(saved, _)| saved == name) {
+            Some(saved) => saved.1 = query.to_string(),
+            None => saved_searches.push((name.to_string(), query.to_string())),
+        }
+
+        AppConfig {
+            saved_searches,
+            ..self
+        }
+    }
+
+    /// The audio profile matching the current audio output settings, if any.
+    pub fn active_audio_profile(&self) -> Option<&AudioProfile> {
+        self.audio_profiles.iter().find(|profile| {
+            profile.output_samplerate_hz == self.output_samplerate_hz
+                && profile.buffer_size_frames == self.buffer_size_frames
+                && profile.sample_rate_conversion_quality == self.sample_rate_conversion_quality
+        })
+    }
+
+    /// Save the current audio output settings as the profile `name`, replacing any profile
+    /// with the same name.
+    pub fn with_audio_profile_saved(self, name: &str) -> AppConfig {
+        let profile = AudioProfile {
+            name: name.to_string(),
+            output_samplerate_hz: self.output_samplerate_hz,
+            buffer_size_frames: self.buffer_size_frames,
+            sample_rate_conversion_quality: self.sample_rate_conversion_quality,
+        };
+
+        let mut audio_profiles = self.audio_profiles.clone();
+
+        match audio_profiles.iter_mut().find(|saved| saved.name == name) {
+            Some(saved) => *saved = profile,
+            None => audio_profiles.push(profile),
+        }
+
+        AppConfig {
+            audio_profiles,
+            ..self
+        }
+    }
+
+    /// Switch to the audio output settings of the profile `name`.
+    pub fn with_audio_profile(self, name: &str) -> Result<AppConfig, anyhow::Error> {
+        let profile = self
+            .audio_profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+            .ok_or(anyhow!("No audio profile named '{name}'"))?;
+
+        Ok(AppConfig {
+            output_samplerate_hz: profile.output_samplerate_hz,
+            buffer_size_frames: profile.buffer_size_frames,
+            sample_rate_conversion_quality: profile.sample_rate_conversion_quality,
+            ..self
+        })
+    }
+
+    pub fn without_audio_profile(self, name: &str) -> AppConfig {
+        AppConfig {
+            audio_profiles: self
+                .audio_profiles
+                .into_iter()
+                .filter(|profile| profile.name != name)
+                .collect(),
+            ..self
+        }
+    }
+
+    pub fn without_saved_search(self, name: &str) -> AppConfig {
+        AppConfig {
+            saved_searches: self
+                .saved_searches
+                .into_iter()
+                .filter(|(saved, _)| saved != name)
+                .collect(),
+            ..self
+        }
+    }
 }
 
+pub const MAX_RECENT_SAVEFILES: usize = 8;
+pub const MAX_FILTER_HISTORY: usize = 10;
+
 pub const OUTPUT_SAMPLE_RATE_OPTIONS: [(&str, u32); 4] = [
     ("44.1 kHz", 44100),
     ("48 kHz", 48000),
@@ -118,3 +573,186 @@ pub const SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS: [(&str, SamplePlaybackBehavior); 2]
         SamplePlaybackBehavior::PlayUntilEnd,
     ),
 ];
+
+pub const WORKSPACE_SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS: [(&str, Option<SamplePlaybackBehavior>); 3] = [
+    ("Use global setting", None),
+    (
+        "Play only most recently selected sample",
+        Some(SamplePlaybackBehavior::PlaySingleSample),
+    ),
+    (
+        "Let each sample play to completion",
+        Some(SamplePlaybackBehavior::PlayUntilEnd),
+    ),
+];
+
+pub const SAMPLE_FILTER_MODE_OPTIONS: [(&str, SampleFilterMode); 2] = [
+    ("Match all words exactly", SampleFilterMode::Exact),
+    ("Fuzzy, best matches first", SampleFilterMode::Fuzzy),
+];
+
+pub const WATCH_SOURCES_OPTIONS: [(&str, bool); 2] =
+    [("Off", false), ("Rescan when files change", true)];
+
+pub const SOURCE_LOADING_OPTIONS: [(&str, u32); 4] = [
+    ("Smoothest (500 samples at a time)", 500),
+    ("Balanced (2000 samples at a time)", 2000),
+    ("Faster (10000 samples at a time)", 10000),
+    ("Unthrottled", 0),
+];
+
+pub const STOP_PREVIEW_ON_PAGE_CHANGE_OPTIONS: [(&str, bool); 2] = [
+    ("Keep playing sample previews", false),
+    ("Stop sample previews", true),
+];
+
+pub const SAMPLES_LIST_KEEP_PLACE_OPTIONS: [(&str, bool); 2] = [
+    ("Keep scroll position and selection", true),
+    ("Rebuild the list", false),
+];
+
+pub const VELOCITY_CURVE_OPTIONS: [(&str, VelocityCurve); 4] = [
+    ("Linear", VelocityCurve::Linear),
+    ("Soft", VelocityCurve::Soft),
+    ("Hard", VelocityCurve::Hard),
+    ("Fixed, always full velocity", VelocityCurve::Fixed),
+];
+
+pub const PAD_LABEL_SET_OPTIONS: [(&str, PadLabelSet); 3] = [
+    ("Drum names", PadLabelSet::Drums),
+    ("Note names", PadLabelSet::Notes),
+    ("Custom", PadLabelSet::Custom),
+];
+
+/// The drum machine has a pad for each drum label of a sample set, so the number of pads is
+/// fixed by the labels rather than by the pad label set.
+pub const NUM_PADS: usize = 16;
+
+pub const DRUM_PAD_LABELS: [&str; NUM_PADS] = [
+    "RS", "CP", "CH", "OH", "CR", "RD", "SH", "P1", "BD", "SD", "LT", "MT", "HT", "P2", "P3", "P4",
+];
+
+/// Chromatic from the lower left pad, so that the bottom row holds the lowest notes.
+pub const NOTE_PAD_LABELS: [&str; NUM_PADS] = [
+    "G#3", "A3", "A#3", "B3", "C4", "C#4", "D4", "D#4", "C3", "C#3", "D3", "D#3", "E3", "F3",
+    "F#3", "G3",
+];
+
+pub const COLOR_SCHEME_OPTIONS: [(&str, ColorScheme); 3] = [
+    ("Follow system", ColorScheme::FollowSystem),
+    ("Dark", ColorScheme::Dark),
+    ("Light", ColorScheme::Light),
+];
+
+pub const UI_SCALE_OPTIONS: [(&str, u16); 5] = [
+    ("80%", 80),
+    ("90%", 90),
+    ("100%", 100),
+    ("125%", 125),
+    ("150%", 150),
+];
+
+pub const TROUBLESHOOTING_LOG_OPTIONS: [(&str, bool); 2] =
+    [("Off", false), ("Write JSON log file", true)];
+
+pub const PROFILER_OVERLAY_OPTIONS: [(&str, bool); 2] =
+    [("Off", false), ("Show message handling times", true)];
+
+pub const SAVEFILE_ENCRYPTION_OPTIONS: [(&str, bool); 2] =
+    [("Off", false), ("Password protected", true)];
+
+pub const SAVEFILE_PASSWORD_MEMORY_OPTIONS: [(&str, bool); 2] = [
+    ("Ask every time", false),
+    ("Remember for this session", true),
+];
+
+pub const REOPEN_LAST_WORKSPACE_OPTIONS: [(&str, bool); 2] = [
+    ("Start with an empty workspace", false),
+    ("Reopen the last workspace", true),
+];
+
+pub const REVIEW_CHANGES_OPTIONS: [(&str, bool); 2] =
+    [("Save right away", false), ("List the changes first", true)];
+
+pub const SETTINGS_GROUP_OPTIONS: [(&str, SettingsGroup); 6] = [
+    ("Audio output and audio profiles", SettingsGroup::Audio),
+    ("Samples, sources and searches", SettingsGroup::Samples),
+    ("Drum machine", SettingsGroup::DrumMachine),
+    ("Appearance", SettingsGroup::Appearance),
+    ("Savefiles and workspaces", SettingsGroup::Savefiles),
+    ("Troubleshooting", SettingsGroup::Troubleshooting),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_imported_settings() {
+        let config = AppConfig::default().with_recent_savefile("/home/me/workspace.asa");
+
+        let imported = AppConfig {
+            config_save_path: "/elsewhere/config.toml".to_string(),
+            output_samplerate_hz: 96000,
+            buffer_size_frames: 256,
+            preview_volume: 50,
+            velocity_curve: VelocityCurve::Hard,
+            color_scheme: ColorScheme::Dark,
+            troubleshooting_log: true,
+            ..AppConfig::default()
+        }
+        .with_recent_savefile("/elsewhere/workspace.asa");
+
+        let merged = config.clone().with_imported_settings(
+            imported.clone(),
+            &[SettingsGroup::Audio, SettingsGroup::Appearance],
+        );
+
+        assert_eq!(merged.output_samplerate_hz, 96000);
+        assert_eq!(merged.buffer_size_frames, 256);
+        assert_eq!(merged.preview_volume, 50);
+        assert_eq!(merged.color_scheme, ColorScheme::Dark);
+
+        assert_eq!(merged.velocity_curve, config.velocity_curve);
+        assert_eq!(merged.troubleshooting_log, config.troubleshooting_log);
+        assert_eq!(merged.config_save_path, config.config_save_path);
+        assert_eq!(merged.recent_savefiles, config.recent_savefiles);
+
+        assert_eq!(config.clone().with_imported_settings(imported, &[]), config);
+    }
+
+    #[test]
+    fn test_validate_rejects_imported_out_of_range_values() {
+        assert!(AppConfig::default().validate().is_ok());
+
+        for imported in [
+            AppConfig {
+                output_samplerate_hz: 12345,
+                ..AppConfig::default()
+            },
+            AppConfig {
+                buffer_size_frames: 16,
+                ..AppConfig::default()
+            },
+            AppConfig {
+                buffer_size_frames: 16384,
+                ..AppConfig::default()
+            },
+            AppConfig {
+                ui_scale_percent: 500,
+                ..AppConfig::default()
+            },
+            AppConfig {
+                source_loading_samples_per_tick: 7,
+                ..AppConfig::default()
+            },
+        ] {
+            assert!(imported.validate().is_err());
+
+            let merged = AppConfig::default()
+                .with_imported_settings(imported, &SETTINGS_GROUP_OPTIONS.map(|(_, group)| group));
+
+            assert!(merged.validate().is_err());
+        }
+    }
+}
@@ -6,7 +6,10 @@ use std::{io::Write, path::Path};
 
 use serde::{Deserialize, Serialize};
 
-use crate::config::{AppConfig, SamplePlaybackBehavior};
+use crate::config::{
+    AppConfig, AudioProfile, ColorScheme, PadLabelSet, SampleFilterMode, SamplePlaybackBehavior,
+    VelocityCurve,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AudioOutput {
@@ -29,6 +32,84 @@ pub enum PlaybackBehaviorSerde {
     PlayUntilEnd,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "crate::config::SampleFilterMode")]
+pub enum SampleFilterModeSerde {
+    Exact,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "crate::config::ColorScheme")]
+pub enum ColorSchemeSerde {
+    FollowSystem,
+    Dark,
+    Light,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "crate::config::VelocityCurve")]
+pub enum VelocityCurveSerde {
+    Linear,
+    Soft,
+    Hard,
+    Fixed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "crate::config::PadLabelSet")]
+pub enum PadLabelSetSerde {
+    Drums,
+    Notes,
+    Custom,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioProfileV1 {
+    name: String,
+    output_samplerate_hz: u32,
+    buffer_size_samples: u16,
+
+    #[serde(with = "QualitySerde")]
+    sample_rate_conversion_quality: audiothread::Quality,
+}
+
+impl AudioProfileV1 {
+    fn into_audio_profile(self) -> AudioProfile {
+        AudioProfile {
+            name: self.name,
+            output_samplerate_hz: self.output_samplerate_hz,
+            buffer_size_frames: self.buffer_size_samples,
+            sample_rate_conversion_quality: self.sample_rate_conversion_quality,
+        }
+    }
+
+    fn from_audio_profile(profile: &AudioProfile) -> AudioProfileV1 {
+        AudioProfileV1 {
+            name: profile.name.clone(),
+            output_samplerate_hz: profile.output_samplerate_hz,
+            buffer_size_samples: profile.buffer_size_frames,
+            sample_rate_conversion_quality: profile.sample_rate_conversion_quality,
+        }
+    }
+}
+
+fn default_samples_list_keep_place() -> bool {
+    true
+}
+
+fn default_source_loading_samples_per_tick() -> u32 {
+    2000
+}
+
+fn default_preview_volume() -> u32 {
+    100
+}
+
+fn default_ui_scale_percent() -> u16 {
+    100
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigFileV1 {
     audio_output: AudioOutput,
@@ -42,6 +123,72 @@ pub struct ConfigFileV1 {
 
     #[serde(with = "PlaybackBehaviorSerde")]
     sample_playback_behavior: SamplePlaybackBehavior,
+
+    #[serde(default, with = "SampleFilterModeSerde")]
+    sample_filter_mode: SampleFilterMode,
+
+    #[serde(default)]
+    watch_sources: bool,
+
+    #[serde(default = "default_source_loading_samples_per_tick")]
+    source_loading_samples_per_tick: u32,
+
+    #[serde(default)]
+    stop_preview_on_page_change: bool,
+
+    #[serde(default = "default_preview_volume")]
+    preview_volume: u32,
+
+    #[serde(default = "default_samples_list_keep_place")]
+    samples_list_keep_place: bool,
+
+    #[serde(default)]
+    samples_list_grouped: bool,
+
+    #[serde(default, with = "VelocityCurveSerde")]
+    velocity_curve: VelocityCurve,
+
+    #[serde(default, with = "PadLabelSetSerde")]
+    pad_label_set: PadLabelSet,
+
+    #[serde(default)]
+    custom_pad_labels: Vec<String>,
+
+    #[serde(default, with = "ColorSchemeSerde")]
+    color_scheme: ColorScheme,
+
+    #[serde(default = "default_ui_scale_percent")]
+    ui_scale_percent: u16,
+
+    #[serde(default)]
+    troubleshooting_log: bool,
+
+    #[serde(default)]
+    profiler_overlay: bool,
+
+    #[serde(default)]
+    encrypt_savefiles: bool,
+
+    #[serde(default)]
+    remember_savefile_password: bool,
+
+    #[serde(default)]
+    reopen_last_workspace: bool,
+
+    #[serde(default)]
+    review_changes_before_saving: bool,
+
+    #[serde(default)]
+    recent_savefiles: Vec<String>,
+
+    #[serde(default)]
+    filter_history: Vec<String>,
+
+    #[serde(default)]
+    saved_searches: Vec<(String, String)>,
+
+    #[serde(default)]
+    audio_profiles: Vec<AudioProfileV1>,
 }
 
 impl ConfigFileV1 {
@@ -52,6 +199,32 @@ impl ConfigFileV1 {
             sample_rate_conversion_quality: self.sample_rate_conversion_quality,
             config_save_path: self.config_save_path,
             sample_playback_behavior: self.sample_playback_behavior,
+            sample_filter_mode: self.sample_filter_mode,
+            watch_sources: self.watch_sources,
+            source_loading_samples_per_tick: self.source_loading_samples_per_tick,
+            stop_preview_on_page_change: self.stop_preview_on_page_change,
+            preview_volume: self.preview_volume,
+            velocity_curve: self.velocity_curve,
+            pad_label_set: self.pad_label_set,
+            custom_pad_labels: self.custom_pad_labels,
+            samples_list_keep_place: self.samples_list_keep_place,
+            samples_list_grouped: self.samples_list_grouped,
+            color_scheme: self.color_scheme,
+            ui_scale_percent: self.ui_scale_percent,
+            troubleshooting_log: self.troubleshooting_log,
+            profiler_overlay: self.profiler_overlay,
+            encrypt_savefiles: self.encrypt_savefiles,
+            remember_savefile_password: self.remember_savefile_password,
+            reopen_last_workspace: self.reopen_last_workspace,
+            review_changes_before_saving: self.review_changes_before_saving,
+            recent_savefiles: self.recent_savefiles,
+            filter_history: self.filter_history,
+            saved_searches: self.saved_searches,
+            audio_profiles: self
+                .audio_profiles
+                .into_iter()
+                .map(AudioProfileV1::into_audio_profile)
+                .collect(),
         }
     }
 
@@ -63,6 +236,32 @@ impl ConfigFileV1 {
             sample_rate_conversion_quality: config.sample_rate_conversion_quality,
             config_save_path: config.config_save_path.clone(),
             sample_playback_behavior: config.sample_playback_behavior.clone(),
+            sample_filter_mode: config.sample_filter_mode.clone(),
+            watch_sources: config.watch_sources,
+            source_loading_samples_per_tick: config.source_loading_samples_per_tick,
+            stop_preview_on_page_change: config.stop_preview_on_page_change,
+            preview_volume: config.preview_volume,
+            velocity_curve: config.velocity_curve.clone(),
+            pad_label_set: config.pad_label_set.clone(),
+            custom_pad_labels: config.custom_pad_labels.clone(),
+            samples_list_keep_place: config.samples_list_keep_place,
+            samples_list_grouped: config.samples_list_grouped,
+            color_scheme: config.color_scheme.clone(),
+            ui_scale_percent: config.ui_scale_percent,
+            troubleshooting_log: config.troubleshooting_log,
+            profiler_overlay: config.profiler_overlay,
+            encrypt_savefiles: config.encrypt_savefiles,
+            remember_savefile_password: config.remember_savefile_password,
+            reopen_last_workspace: config.reopen_last_workspace,
+            review_changes_before_saving: config.review_changes_before_saving,
+            recent_savefiles: config.recent_savefiles.clone(),
+            filter_history: config.filter_history.clone(),
+            saved_searches: config.saved_searches.clone(),
+            audio_profiles: config
+                .audio_profiles
+                .iter()
+                .map(AudioProfileV1::from_audio_profile)
+                .collect(),
         }
     }
 }
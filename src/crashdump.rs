@@ -0,0 +1,144 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::VecDeque,
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{model::AppModel, savefile::LoadJobMessage, AppMessage};
+
+const RECENT_MESSAGES_MAX: usize = 50;
+const MESSAGE_SUMMARY_MAX_CHARS: usize = 200;
+
+static RECENT_MESSAGES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+thread_local! {
+    // per thread, so that a panic in a worker thread isn't mistaken for one in update_model
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Remember the details of panics, including their location, for inclusion in state dumps.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        LAST_PANIC.set(Some(info.to_string()));
+
+        default_hook(info);
+    }));
+}
+
+/// Remember a message about to be handled, keeping only the most recent ones. Frequent
+/// messages such as timer ticks are skipped, and secrets are redacted by the `Debug` output
+/// of the messages carrying them.
+pub fn record_message(message: &AppMessage) {
+    if message.is_frequent() {
+        return;
+    }
+
+    let summary = match message {
+        AppMessage::LoadJobMessage(LoadJobMessage::Finished(_)) => {
+            "LoadJobMessage(Finished(..))".to_string()
+        }
+
+        _ => format!("{message:?}")
+            .chars()
+            .take(MESSAGE_SUMMARY_MAX_CHARS)
+            .collect(),
+    };
+
+    if let Ok(mut recent) = RECENT_MESSAGES.lock() {
+        if recent.len() == RECENT_MESSAGES_MAX {
+            recent.pop_front();
+        }
+
+        recent.push_back(summary);
+    }
+}
+
+/// A description of a caught panic, preferring the details recorded by the panic hook.
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(details) = LAST_PANIC.take() {
+        return details;
+    }
+
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or("unknown panic".to_string())
+}
+
+pub fn dump_dir() -> String {
+    dirs::data_local_dir()
+        .expect("System should have a common data dir")
+        .join("asampo")
+        .join("crashdumps")
+        .to_str()
+        .expect("Should be able to construct the crash dump dir path")
+        .to_string()
+}
+
+/// Write a state dump with the panic, the recently handled messages and a summary of the
+/// model the panicking message was handled with. Returns the path of the dump.
+pub fn write(model: &AppModel, panic_message: &str) -> Result<String, anyhow::Error> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_millis())
+        .unwrap_or_default();
+
+    std::fs::create_dir_all(dump_dir())?;
+
+    let path = Path::new(&dump_dir())
+        .join(format!("asampo-{timestamp}.txt"))
+        .to_string_lossy()
+        .to_string();
+
+    let mut file = std::fs::File::create(&path)?;
+
+    writeln!(file, "Asampo {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(file, "\nPanic:\n{panic_message}")?;
+    writeln!(file, "\nModel:\n{}", model_summary(model))?;
+    writeln!(file, "\nRecent messages, oldest first:")?;
+
+    if let Ok(recent) = RECENT_MESSAGES.lock() {
+        for message in recent.iter() {
+            writeln!(file, "{message}")?;
+        }
+    }
+
+    Ok(path)
+}
+
+fn model_summary(model: &AppModel) -> String {
+    let samples = model
+        .samples
+        .try_borrow()
+        .map(|samples| samples.len().to_string())
+        .unwrap_or("(borrowed)".to_string());
+
+    [
+        format!("savefile: {:?}", model.savefile),
+        format!("sources: {}", model.sources.len()),
+        format!("sources loading: {}", model.sources_loading.len()),
+        format!("sources rescanning: {}", model.sources_rescanning.len()),
+        format!("samples: {samples}"),
+        format!(
+            "selected samples: {}",
+            model.samplelist_selected_samples.len()
+        ),
+        format!("sets: {}", model.sets.len()),
+        format!("selected set: {:?}", model.sets_selected_set),
+        format!("export state: {:?}", model.sets_export_state),
+        format!("drum machine tempo: {}", model.drum_machine.tempo),
+        format!("viewflags: {:?}", model.viewflags),
+    ]
+    .join("\n")
+}
@@ -0,0 +1,60 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::{collections::HashSet, path::Path, sync::mpsc};
+
+use notify::{event::ModifyKind, EventKind, RecursiveMode, Watcher};
+
+/// Watches the folders of a source for files being added, removed or renamed.
+pub struct SourceWatcher {
+    _watcher: notify::RecommendedWatcher,
+    rx: mpsc::Receiver<()>,
+}
+
+impl std::fmt::Debug for SourceWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourceWatcher").finish_non_exhaustive()
+    }
+}
+
+impl SourceWatcher {
+    /// Watch the folders containing the given files. Folders are watched non-recursively,
+    /// so a new subfolder is only picked up once the source has been listed again.
+    pub fn new<'a>(files: impl IntoIterator<Item = &'a str>) -> Result<Self, anyhow::Error> {
+        let (tx, rx) = mpsc::channel::<()>();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    if matches!(
+                        event.kind,
+                        EventKind::Create(_)
+                            | EventKind::Remove(_)
+                            | EventKind::Modify(ModifyKind::Name(_))
+                    ) {
+                        let _ = tx.send(());
+                    }
+                }
+            })?;
+
+        let dirs = files
+            .into_iter()
+            .filter_map(|file| Path::new(file).parent())
+            .collect::<HashSet<&Path>>();
+
+        for dir in dirs {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(SourceWatcher {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// Whether any files were added, removed or renamed since the last call.
+    pub fn changed(&self) -> bool {
+        self.rx.try_iter().count() > 0
+    }
+}
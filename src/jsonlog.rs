@@ -0,0 +1,164 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::{
+    fs::File,
+    io::Write,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use regex::Regex;
+
+/// Setting this environment variable enables the JSON log regardless of the config.
+pub const ENV_VAR: &str = "ASAMPO_JSON_LOG";
+
+/// Once the log grows past this size it is moved aside to `<path>.1` and started over, so
+/// that at most twice this much disk space is used.
+const MAX_LOG_SIZE: u64 = 10 * 1024 * 1024;
+
+struct LogFile {
+    file: File,
+    path: String,
+    size: u64,
+}
+
+static LOG_FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+
+/// Set by [`force_enabled`], e.g from the command line, to keep the log enabled regardless
+/// of the config.
+static FORCED: AtomicBool = AtomicBool::new(false);
+
+pub fn default_path() -> String {
+    dirs::data_local_dir()
+        .expect("System should have a common data dir")
+        .join("asampo")
+        .join("asampo-log.jsonl")
+        .to_str()
+        .expect("Should be able to construct the JSON log path")
+        .to_string()
+}
+
+/// Enable the log for the rest of the session, regardless of the config.
+pub fn force_enabled() -> Result<(), anyhow::Error> {
+    FORCED.store(true, Ordering::Relaxed);
+    set_enabled(true)
+}
+
+pub fn set_enabled(enabled: bool) -> Result<(), anyhow::Error> {
+    let enabled = enabled || FORCED.load(Ordering::Relaxed) || std::env::var_os(ENV_VAR).is_some();
+    let mut log_file = LOG_FILE
+        .lock()
+        .map_err(|_| anyhow!("JSON log lock poisoned"))?;
+
+    match (enabled, log_file.is_some()) {
+        (true, false) => {
+            let path = default_path();
+
+            if let Some(dir) = Path::new(&path).parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+
+            *log_file = Some(open(path.clone())?);
+
+            log::log!(log::Level::Info, "Writing JSON log to {path}");
+        }
+
+        (false, true) => *log_file = None,
+        _ => (),
+    }
+
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    LOG_FILE.lock().is_ok_and(|log_file| log_file.is_some())
+}
+
+/// Record the handling of a message, given its debug representation. The message kind and
+/// any entity uuids mentioned in the message are extracted into separate fields.
+pub fn record_message(message: &str, model_time: Duration, view_time: Duration, ok: bool) {
+    static UUID_RE: OnceLock<Regex> = OnceLock::new();
+
+    let uuid_re = UUID_RE.get_or_init(|| {
+        Regex::new(r"[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}").unwrap()
+    });
+
+    let kind = message_kind(message);
+
+    let mut uuids = uuid_re
+        .find_iter(message)
+        .map(|m| m.as_str())
+        .collect::<Vec<_>>();
+
+    uuids.dedup();
+
+    write_entry(serde_json::json!({
+        "time_ms": SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_millis() as u64)
+            .unwrap_or_default(),
+        "kind": kind,
+        "uuids": uuids,
+        "update_model_us": model_time.as_micros() as u64,
+        "update_view_us": view_time.as_micros() as u64,
+        "ok": ok,
+    }));
+}
+
+/// The name of the message variant, given the debug representation of a message.
+pub fn message_kind(message: &str) -> &str {
+    message
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+}
+
+fn open(path: String) -> Result<LogFile, anyhow::Error> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+
+    let size = file.metadata()?.len();
+
+    Ok(LogFile { file, path, size })
+}
+
+/// Move a full log aside, replacing the previously moved one, and start a new log.
+fn rotate(log_file: &mut Option<LogFile>) -> Result<(), anyhow::Error> {
+    if let Some(LogFile { path, .. }) = log_file.take() {
+        std::fs::rename(&path, format!("{path}.1"))?;
+        *log_file = Some(open(path)?);
+    }
+
+    Ok(())
+}
+
+fn write_entry(entry: serde_json::Value) {
+    if let Ok(mut log_file) = LOG_FILE.lock() {
+        if log_file
+            .as_ref()
+            .is_some_and(|log_file| log_file.size >= MAX_LOG_SIZE)
+        {
+            if let Err(e) = rotate(&mut log_file) {
+                log::log!(log::Level::Error, "Failed to rotate JSON log: {e}");
+            }
+        }
+
+        if let Some(log_file) = log_file.as_mut() {
+            let line = format!("{entry}\n");
+
+            match log_file.file.write_all(line.as_bytes()) {
+                Ok(_) => log_file.size += line.len() as u64,
+                Err(e) => log::log!(log::Level::Error, "Failed to write JSON log: {e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,448 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::anyhow;
+use libasampo::samplesets::DrumkitLabel;
+use regex::Regex;
+
+/// A drum kit read from an SFZ instrument or a Hydrogen `drumkit.xml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedKit {
+    pub name: String,
+
+    /// Paths of the samples of the kit, in the order the kit lists them.
+    pub samples: Vec<String>,
+
+    /// The drum machine label of each sample, from the note the kit plays it on.
+    pub labels: Vec<Option<DrumkitLabel>>,
+}
+
+impl ImportedKit {
+    /// The innermost directory containing all the samples of the kit.
+    pub fn sample_dir(&self) -> Option<String> {
+        let mut dirs = self
+            .samples
+            .iter()
+            .filter_map(|sample| Path::new(sample).parent());
+
+        let mut common = dirs.next()?.to_path_buf();
+
+        for dir in dirs {
+            while !dir.starts_with(&common) {
+                common = common.parent()?.to_path_buf();
+            }
+        }
+
+        common.to_str().map(String::from)
+    }
+
+    /// The file extensions of the samples of the kit, lowercase and without duplicates.
+    pub fn extensions(&self) -> Vec<String> {
+        let mut exts = Vec::<String>::new();
+
+        for ext in self
+            .samples
+            .iter()
+            .filter_map(|sample| Path::new(sample).extension())
+            .filter_map(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+        {
+            if !exts.contains(&ext) {
+                exts.push(ext);
+            }
+        }
+
+        exts
+    }
+
+    /// Add a sample path, without `.` and `..` components, unless already present. A
+    /// sample listed more than once keeps the first label found for it.
+    fn push_sample(&mut self, path: PathBuf, label: Option<DrumkitLabel>) {
+        let mut normalized = PathBuf::new();
+
+        for component in path.components() {
+            match component {
+                Component::CurDir => (),
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                _ => normalized.push(component),
+            }
+        }
+
+        if let Some(path) = normalized.to_str() {
+            match self.samples.iter().position(|sample| sample == path) {
+                Some(i) => self.labels[i] = self.labels[i].or(label),
+                None => {
+                    self.samples.push(path.to_string());
+                    self.labels.push(label);
+                }
+            }
+        }
+    }
+}
+
+/// Read a kit from an `.sfz` file or a Hydrogen `drumkit.xml`, resolving the sample paths
+/// against the directory of the file.
+pub fn load(filename: &str) -> Result<ImportedKit, anyhow::Error> {
+    let path = Path::new(filename);
+
+    let dir = path
+        .parent()
+        .ok_or(anyhow!("Kit file has no parent directory"))?;
+
+    let default_name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Imported kit");
+
+    let text = std::fs::read_to_string(path)?;
+
+    let kit = match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .as_deref()
+    {
+        Some("sfz") => parse_sfz(&text, dir, default_name),
+        Some("xml") => parse_hydrogen(&text, dir, default_name),
+        _ => return Err(anyhow!("Unsupported kit file: {filename}")),
+    };
+
+    if kit.samples.is_empty() {
+        Err(anyhow!("No samples found in {filename}"))
+    } else {
+        Ok(kit)
+    }
+}
+
+fn parse_sfz(text: &str, dir: &Path, name: &str) -> ImportedKit {
+    let token = Regex::new(r"<(\w+)>|(\w+)=").unwrap();
+
+    let mut kit = ImportedKit {
+        name: name.to_string(),
+        samples: Vec::new(),
+        labels: Vec::new(),
+    };
+
+    let mut default_path = String::new();
+    let mut group_note: Option<u8> = None;
+    let mut region: Option<SfzRegion> = None;
+
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap_or_default();
+
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let matches = token.captures_iter(line).collect::<Vec<_>>();
+
+        // values may contain spaces, so each one runs until the next header or opcode
+        for (i, caps) in matches.iter().enumerate() {
+            if let Some(header) = caps.get(1) {
+                if let Some(region) = region.take() {
+                    region.add_to(&mut kit, dir, group_note);
+                }
+
+                match header.as_str() {
+                    "region" => region = Some(SfzRegion::default()),
+                    "group" => group_note = None,
+                    _ => (),
+                }
+
+                continue;
+            }
+
+            let end = matches
+                .get(i + 1)
+                .map(|next| next.get(0).unwrap().start())
+                .unwrap_or(line.len());
+
+            let value = line[caps.get(0).unwrap().end()..end]
+                .trim()
+                .replace('\\', "/");
+
+            match (&caps[2], region.as_mut()) {
+                ("default_path", _) => default_path = value,
+
+                ("sample", Some(region)) if !value.starts_with('*') => {
+                    region.sample = Some(format!("{default_path}{value}"))
+                }
+
+                ("key", Some(region)) => region.key = sfz_note(&value),
+                ("pitch_keycenter", Some(region)) => region.keycenter = sfz_note(&value),
+                ("lokey", Some(region)) => region.lokey = sfz_note(&value),
+
+                ("key" | "pitch_keycenter" | "lokey", None) => {
+                    group_note = sfz_note(&value).or(group_note)
+                }
+
+                _ => (),
+            }
+        }
+    }
+
+    if let Some(region) = region.take() {
+        region.add_to(&mut kit, dir, group_note);
+    }
+
+    kit
+}
+
+/// A `<region>` of an SFZ instrument, with the opcodes that tell which note plays it.
+#[derive(Default)]
+struct SfzRegion {
+    sample: Option<String>,
+    key: Option<u8>,
+    keycenter: Option<u8>,
+    lokey: Option<u8>,
+}
+
+impl SfzRegion {
+    fn add_to(self, kit: &mut ImportedKit, dir: &Path, group_note: Option<u8>) {
+        if let Some(sample) = self.sample {
+            let note = self.key.or(self.keycenter).or(self.lokey).or(group_note);
+            kit.push_sample(dir.join(sample), note.and_then(gm_drum_label));
+        }
+    }
+}
+
+/// An SFZ note, given either as a MIDI note number or as a note name such as `c#4`, where
+/// `c4` is middle C (60).
+fn sfz_note(value: &str) -> Option<u8> {
+    if let Ok(note) = value.parse::<u8>() {
+        return (note < 128).then_some(note);
+    }
+
+    let value = value.to_lowercase();
+    let mut chars = value.chars();
+
+    let pitch_class: i32 = match chars.next()? {
+        'c' => 0,
+        'd' => 2,
+        'e' => 4,
+        'f' => 5,
+        'g' => 7,
+        'a' => 9,
+        'b' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+
+    let (accidental, octave) = match rest.chars().next()? {
+        '#' => (1, &rest[1..]),
+        'b' if rest.len() > 1 => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+
+    let note = 12 * (octave.parse::<i32>().ok()? + 1) + pitch_class + accidental;
+
+    u8::try_from(note).ok().filter(|note| *note < 128)
+}
+
+/// The drum machine label of a note of the General MIDI percussion map, if it has one.
+fn gm_drum_label(note: u8) -> Option<DrumkitLabel> {
+    match note {
+        35 | 36 => Some(DrumkitLabel::BassDrum),
+        37 => Some(DrumkitLabel::RimShot),
+        38 | 40 => Some(DrumkitLabel::SnareDrum),
+        39 => Some(DrumkitLabel::Clap),
+        41 | 43 => Some(DrumkitLabel::LowTom),
+        42 | 44 => Some(DrumkitLabel::ClosedHihat),
+        45 | 47 => Some(DrumkitLabel::MidTom),
+        46 => Some(DrumkitLabel::OpenHihat),
+        48 | 50 => Some(DrumkitLabel::HighTom),
+        49 | 52 | 55 | 57 => Some(DrumkitLabel::CrashCymbal),
+        51 | 53 | 59 => Some(DrumkitLabel::RideCymbal),
+        69 | 70 | 82 => Some(DrumkitLabel::Shaker),
+        _ => None,
+    }
+}
+
+/// The drum machine label of a Hydrogen instrument, guessed from its name.
+fn drum_label_by_name(name: &str) -> Option<DrumkitLabel> {
+    let name = name.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+
+    if has(&["kick", "bass drum", "bassdrum"]) {
+        Some(DrumkitLabel::BassDrum)
+    } else if has(&["rim", "stick"]) {
+        Some(DrumkitLabel::RimShot)
+    } else if has(&["snare"]) {
+        Some(DrumkitLabel::SnareDrum)
+    } else if has(&["clap"]) {
+        Some(DrumkitLabel::Clap)
+    } else if has(&["open"]) && has(&["hat", "hh"]) {
+        Some(DrumkitLabel::OpenHihat)
+    } else if has(&["hat", "hh"]) {
+        Some(DrumkitLabel::ClosedHihat)
+    } else if has(&["crash"]) {
+        Some(DrumkitLabel::CrashCymbal)
+    } else if has(&["ride"]) {
+        Some(DrumkitLabel::RideCymbal)
+    } else if has(&["shaker", "maracas", "cabasa"]) {
+        Some(DrumkitLabel::Shaker)
+    } else if has(&["floor", "low tom"]) {
+        Some(DrumkitLabel::LowTom)
+    } else if has(&["high tom", "hi tom"]) {
+        Some(DrumkitLabel::HighTom)
+    } else if has(&["tom"]) {
+        Some(DrumkitLabel::MidTom)
+    } else {
+        None
+    }
+}
+
+fn parse_hydrogen(text: &str, dir: &Path, default_name: &str) -> ImportedKit {
+    let name = Regex::new(r"<name>\s*([^<]*?)\s*</name>").unwrap();
+    let instrument = Regex::new(r"(?s)<instrument>(.*?)</instrument>").unwrap();
+    let midi_note = Regex::new(r"<midiOutNote>\s*(\d+)\s*</midiOutNote>").unwrap();
+    let filename = Regex::new(r"<filename>\s*([^<]*?)\s*</filename>").unwrap();
+
+    let mut kit = ImportedKit {
+        name: name
+            .captures(text)
+            .map(|caps| unescape_xml(&caps[1]))
+            .filter(|name| !name.is_empty())
+            .unwrap_or(default_name.to_string()),
+        samples: Vec::new(),
+        labels: Vec::new(),
+    };
+
+    for caps in instrument.captures_iter(text) {
+        let instrument = &caps[1];
+
+        // Hydrogen numbers the MIDI notes of new instruments in order, so the name says more
+        let label = name
+            .captures(instrument)
+            .and_then(|caps| drum_label_by_name(&unescape_xml(&caps[1])))
+            .or_else(|| {
+                midi_note
+                    .captures(instrument)
+                    .and_then(|caps| caps[1].parse::<u8>().ok())
+                    .and_then(gm_drum_label)
+            });
+
+        for caps in filename.captures_iter(instrument) {
+            kit.push_sample(dir.join(unescape_xml(&caps[1])), label);
+        }
+    }
+
+    kit
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sfz() {
+        let kit = parse_sfz(
+            "// My Kit\n\
+            <control> default_path=samples\\\n\
+            <group> loop_mode=one_shot\n\
+            <region> sample=kick 1.wav key=36\n\
+            <region>sample=../snare.wav key=38 // the snare\n\
+            <region> sample=kick 1.wav key=35\n\
+            <region> sample=*sine key=60\n",
+            Path::new("/kits/mykit"),
+            "mykit",
+        );
+
+        assert_eq!(kit.name, "mykit");
+        assert_eq!(
+            kit.samples,
+            vec!["/kits/mykit/samples/kick 1.wav", "/kits/mykit/snare.wav"]
+        );
+        assert_eq!(
+            kit.labels,
+            vec![Some(DrumkitLabel::BassDrum), Some(DrumkitLabel::SnareDrum)]
+        );
+        assert_eq!(kit.sample_dir(), Some("/kits/mykit".to_string()));
+        assert_eq!(kit.extensions(), vec!["wav"]);
+
+        let kit = parse_sfz(
+            "<group> key=42
+            <region> sample=hh1.wav
+            <region> sample=hh2.wav
+            <group>
+            <region> sample=ride.wav lokey=c#3 hikey=d#3 pitch_keycenter=d#3
+            <region> sample=bell.wav key=c6
+",
+            Path::new("/kits/hats"),
+            "hats",
+        );
+
+        assert_eq!(
+            kit.labels,
+            vec![
+                Some(DrumkitLabel::ClosedHihat),
+                Some(DrumkitLabel::ClosedHihat),
+                Some(DrumkitLabel::RideCymbal),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sfz_note() {
+        assert_eq!(sfz_note("36"), Some(36));
+        assert_eq!(sfz_note("c4"), Some(60));
+        assert_eq!(sfz_note("C#2"), Some(37));
+        assert_eq!(sfz_note("eb2"), Some(39));
+        assert_eq!(sfz_note("c-1"), Some(0));
+        assert_eq!(sfz_note("200"), None);
+        assert_eq!(sfz_note("h2"), None);
+    }
+
+    #[test]
+    fn test_parse_hydrogen() {
+        let kit = parse_hydrogen(
+            "<drumkit_info>\n\
+              <name>Rock &amp; Roll</name>\n\
+              <instrumentList>\n\
+                <instrument><name>Kick</name><layer><filename>kick.flac</filename></layer></instrument>\n\
+                <instrument><name>Snare</name><layer><filename>snare.WAV</filename></layer></instrument>\n\
+                <instrument><name>Thing</name><midiOutNote>46</midiOutNote><layer><filename>thing.wav</filename></layer></instrument>\n\
+                <instrument><name>Cowbell</name><midiOutNote>56</midiOutNote><layer><filename>cowbell.wav</filename></layer></instrument>\n\
+              </instrumentList>\n\
+            </drumkit_info>",
+            Path::new("/kits/rock"),
+            "drumkit",
+        );
+
+        assert_eq!(kit.name, "Rock & Roll");
+        assert_eq!(
+            kit.samples,
+            vec![
+                "/kits/rock/kick.flac",
+                "/kits/rock/snare.WAV",
+                "/kits/rock/thing.wav",
+                "/kits/rock/cowbell.wav"
+            ]
+        );
+        assert_eq!(
+            kit.labels,
+            vec![
+                Some(DrumkitLabel::BassDrum),
+                Some(DrumkitLabel::SnareDrum),
+                Some(DrumkitLabel::OpenHihat),
+                None
+            ]
+        );
+        assert_eq!(kit.extensions(), vec!["flac", "wav"]);
+    }
+}
@@ -0,0 +1,114 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::path::Path;
+
+use uuid::Uuid;
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("unknown host".to_string())
+}
+
+/// Location of the lock file for a savefile, next to the savefile so that instances on other
+/// machines sharing the folder see it too.
+pub fn lock_path(savefile: &str) -> String {
+    let path = Path::new(savefile);
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    path.with_file_name(format!(".{name}.lock"))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// If the savefile is held open by anyone other than `owner`, such as another window or
+/// another instance, a description of the holder.
+pub fn held_elsewhere(savefile: &str, owner: &Uuid) -> Option<String> {
+    let contents = std::fs::read_to_string(lock_path(savefile)).ok()?;
+    let mut lines = contents.lines();
+
+    match lines.next() {
+        Some(id) if id == owner.to_string() => None,
+        _ => Some(lines.next().unwrap_or("another instance").to_string()),
+    }
+}
+
+/// Mark the savefile as held open by `owner`, taking over any existing lock.
+pub fn acquire(savefile: &str, owner: &Uuid) -> Result<(), anyhow::Error> {
+    std::fs::write(
+        lock_path(savefile),
+        format!(
+            "{}\n{} (process {})\n",
+            owner,
+            hostname(),
+            std::process::id()
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Remove the lock `owner` has on the savefile, unless someone else has since taken it over.
+pub fn release(savefile: &str, owner: &Uuid) {
+    let path = lock_path(savefile);
+
+    if Path::new(&path).exists() && held_elsewhere(savefile, owner).is_none() {
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::log!(log::Level::Error, "Failed to remove lock file: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_acquire_release() {
+        let tmpdir = tempfile::tempdir().expect("Should be able to create temporary dir");
+
+        let savefile = tmpdir
+            .path()
+            .join("workspace.json")
+            .to_str()
+            .expect("Temporary dir should have UTF-8 path")
+            .to_string();
+
+        let owner = Uuid::new_v4();
+        let other_window = Uuid::new_v4();
+
+        assert_eq!(held_elsewhere(&savefile, &owner), None);
+
+        std::fs::write(
+            lock_path(&savefile),
+            "someone-else\nelsewhere (process 1)\n",
+        )
+        .unwrap();
+        assert_eq!(
+            held_elsewhere(&savefile, &owner),
+            Some("elsewhere (process 1)".to_string())
+        );
+
+        release(&savefile, &owner);
+        assert!(Path::new(&lock_path(&savefile)).exists());
+
+        acquire(&savefile, &owner).unwrap();
+        assert_eq!(held_elsewhere(&savefile, &owner), None);
+        assert!(held_elsewhere(&savefile, &other_window).is_some());
+
+        release(&savefile, &other_window);
+        assert!(Path::new(&lock_path(&savefile)).exists());
+
+        release(&savefile, &owner);
+        assert!(!Path::new(&lock_path(&savefile)).exists());
+    }
+}
@@ -2,22 +2,32 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
+mod cli;
 mod config;
 mod configfile;
+mod crashdump;
 
 #[macro_use]
 mod ext;
 
+mod fswatch;
+mod jsonlog;
+mod kitimport;
+mod lockfile;
 mod model;
 mod savefile;
 mod testutils;
+mod timers;
 mod util;
 mod view;
 
 use std::{
-    cell::Cell,
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
     io::BufReader,
-    path::Path,
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::mpsc,
     time::{Duration, Instant},
@@ -26,12 +36,15 @@ use std::{
 use anyhow::anyhow;
 use audiothread::{AudioSpec, NonZeroNumFrames};
 use ext::ClonedHashMapExt;
-use model::{DrumMachineModel, ExportState, ModelOps};
+use model::{
+    metronome_click_wav, output_test_wav, DrumMachineModel, ExportState, ModelOps,
+    DEFAULT_VELOCITY_LEVEL, VELOCITY_LEVELS,
+};
 use uuid::Uuid;
 
 use gtk::{
     gdk::Display,
-    gio::ApplicationFlags,
+    gio::{ActionEntry, ApplicationFlags},
     glib::{clone, ExitCode},
     prelude::*,
     Application, DialogError,
@@ -48,21 +61,39 @@ use libasampo::{
 };
 
 use crate::{
-    config::AppConfig,
+    config::{
+        AppConfig, SamplePlaybackBehavior, SettingsGroup, VelocityCurve,
+        WORKSPACE_SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS,
+    },
     configfile::ConfigFile,
-    ext::WithModel,
-    model::{AppModel, AppModelOps, AppModelPtr, ViewFlags, ViewModelOps, ViewValues},
+    ext::{OptionMapExt, WithModel},
+    model::{
+        AppModel, AppModelOps, AppModelPtr, CompareSlot, KitImport, LoadInProgress, SampleListId,
+        SaveInProgress, SavefileLockConflict, SavefileReview, ViewFlags, ViewModelOps, ViewValues,
+    },
+    savefile::{LoadJobMessage, NewerFormatError, SaveJobMessage},
     util::gtk_find_child_by_builder_id,
     view::{
         dialogs,
-        menus::build_actions,
-        samples::{setup_samples_page, update_samples_sidebar, SampleListEntry},
+        menus::{build_actions, update_audio_profiles_menu, update_recent_savefiles_menu},
+        profiler::{self, update_profiler_overlay},
+        samples::{
+            add_to_set_popover, history_popover, open_sample_folder, reveal_sample,
+            searches_popover, setup_samples_page, update_samples_compare,
+            update_samples_list_grouping, update_samples_sidebar,
+        },
         sequences::{
             setup_sequences_page, update_drum_machine_view, LABELS as DRUM_MACHINE_VIEW_LABELS,
         },
         sets::{setup_sets_page, update_samplesets_detail, update_samplesets_list, LabellingKind},
-        settings::setup_settings_page,
-        sources::{setup_sources_page, update_sources_list},
+        settings::{
+            appearance_changed, apply_appearance, setup_settings_page, update_settings_view,
+            update_workspace_settings,
+        },
+        sources::{
+            setup_sources_page, update_sources_list, update_sources_loading_progress,
+            update_sources_overlap,
+        },
         AsampoView,
     },
 };
@@ -76,6 +107,9 @@ use crate::testutils::savefile_for_test::Savefile;
 #[derive(Debug)]
 enum ErrorWithEffect {
     AlertDialog { text: String, detail: String },
+    Toast { text: String },
+    PanicDump { text: String, path: String },
+    AudioDisconnected,
 }
 
 impl std::fmt::Display for ErrorWithEffect {
@@ -84,6 +118,13 @@ impl std::fmt::Display for ErrorWithEffect {
             ErrorWithEffect::AlertDialog { text, detail } => {
                 f.write_str(&format!("{}: {}", text, detail))
             }
+            ErrorWithEffect::Toast { text } => f.write_str(text),
+            ErrorWithEffect::PanicDump { text, path } => {
+                f.write_str(&format!("{} (state dump written to {})", text, path))
+            }
+            ErrorWithEffect::AudioDisconnected => {
+                f.write_str("Send error on audio thread control channel")
+            }
         }
     }
 }
@@ -93,7 +134,55 @@ impl std::error::Error for ErrorWithEffect {}
 #[derive(Debug, Clone)]
 enum InputDialogContext {
     AddToSampleset,
+    RenameSample,
+    SaveSearch,
     CreateSampleSet,
+    RenameSampleSet,
+    SavefilePasswordForSave,
+    SavefilePasswordForLoad,
+    CustomPadLabels,
+    SaveAudioProfile,
+}
+
+impl InputDialogContext {
+    fn is_secret(&self) -> bool {
+        matches!(
+            self,
+            InputDialogContext::SavefilePasswordForSave
+                | InputDialogContext::SavefilePasswordForLoad
+        )
+    }
+
+    /// Whether the input has to be typed twice, for secrets that can't be recovered if
+    /// mistyped.
+    fn needs_confirmation(&self) -> bool {
+        matches!(self, InputDialogContext::SavefilePasswordForSave)
+    }
+}
+
+/// Text submitted in an input dialog. Secret text is left out of the `Debug` output, so that
+/// passwords don't end up in logs or crash dumps.
+struct DialogText {
+    text: String,
+    secret: bool,
+}
+
+impl DialogText {
+    fn new(context: &InputDialogContext, text: String) -> Self {
+        DialogText {
+            text,
+            secret: context.is_secret(),
+        }
+    }
+}
+
+impl std::fmt::Debug for DialogText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.secret {
+            true => f.write_str("<redacted>"),
+            false => write!(f, "{:?}", self.text),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -105,10 +194,33 @@ enum SelectFolderDialogContext {
 #[derive(Debug)]
 enum AppMessage {
     TimerTick,
+    ShowToast(String),
+    ToastsShown,
     SettingsOutputSampleRateChanged(String),
     SettingsBufferSizeChanged(u16),
     SettingsSampleRateConversionQualityChanged(String),
     SettingsSamplePlaybackBehaviorChanged(String),
+    SettingsWorkspaceSamplePlaybackBehaviorChanged(String),
+    SettingsSampleFilterModeChanged(String),
+    SettingsWatchSourcesChanged(String),
+    SettingsSourceLoadingChanged(String),
+    SettingsSamplesListKeepPlaceChanged(String),
+    SettingsStopPreviewOnPageChangeChanged(String),
+    SettingsVelocityCurveChanged(String),
+    SettingsPadLabelsChanged(String),
+    SettingsPadLabelsEditClicked,
+    SettingsColorSchemeChanged(String),
+    SettingsUiScaleChanged(String),
+    SettingsTroubleshootingLogChanged(String),
+    SettingsProfilerOverlayChanged(String),
+    SettingsSavefileEncryptionChanged(String),
+    SettingsSavefilePasswordMemoryChanged(String),
+    SettingsReopenLastWorkspaceChanged(String),
+    SettingsReviewChangesChanged(String),
+    SettingsTestOutputClicked,
+    AudioProfileSelected(String),
+    AudioProfileSaveClicked,
+    AudioProfileDeleteClicked,
     AddFilesystemSourceNameChanged(String),
     AddFilesystemSourcePathChanged(String),
     AddFilesystemSourcePathBrowseClicked,
@@ -116,41 +228,116 @@ enum AppMessage {
     AddFilesystemSourcePathBrowseError(gtk::glib::Error),
     AddFilesystemSourceExtensionsChanged(String),
     AddFilesystemSourceClicked,
-    SampleListSampleSelected(u32),
+    SourcePreviewDialogOpened,
+    SourcePreviewFileClicked(String),
+    SampleListSampleSelected(SampleListId, u32),
+    SampleListMultipleSelected(SampleListId, Vec<u32>),
     SampleSetSampleSelected(Sample),
-    SamplesFilterChanged(String),
+    SamplesFilterChanged(SampleListId, String),
+    SamplesFilterActivated(SampleListId),
+    SamplesSearchesClicked,
+    SamplesSearchesPopoverOpened,
+    SamplesHistoryClicked,
+    SamplesHistoryPopoverOpened,
+    SamplesHistoryEntryClicked(Sample),
+    SamplesSaveSearchClicked,
+    SavedSearchRemoved(String),
+    SamplesSplitViewToggled(bool),
+    SamplesGroupBySourceToggled(bool),
+    SamplesFavoritesOnlyToggled(bool),
+    SampleFavoriteToggled(Sample, bool),
+    SampleSidebarFavoriteToggled(bool),
+    SampleCompareAssignClicked(CompareSlot),
+    SampleCompareSwitchClicked,
+    SamplesPreviewPitchChanged(i32),
+    SampleSidebarRenameClicked,
     SampleSidebarAddToSetClicked,
+    SampleSidebarAddToSetPopoverOpened,
+    SampleSidebarAddToSetChosen(Uuid),
+    SampleSidebarAddToNewSetClicked,
     SampleSidebarAddToMostRecentlyUsedSetClicked,
     SourceEnabled(Uuid),
     SourceDisabled(Uuid),
     SourceDeleteClicked(Uuid),
+    SourceRescanClicked(Uuid),
+    SourceFilesChanged(Uuid),
+    SourcesDedupToggled(bool),
     SourceLoadingMessage(Uuid, Vec<Result<Sample, libasampo::errors::Error>>),
     SourceLoadingDisconnected(Uuid),
     LoadFromSavefile(String),
     SaveToSavefile(String),
+    SavefileReviewDialogOpened,
+    SavefileReviewAccepted,
+    SavefileReviewDeclined,
+    ExportSettings(String),
+    ImportSettings(String),
+    SettingsImportDialogOpened,
+    SettingsImportAccepted(Vec<SettingsGroup>),
+    SettingsImportDeclined,
+    ConfigShared(AppConfig),
+    AutosaveHandedOver,
+    ImportKit(String),
+    RecoveryRestoreAccepted,
+    RecoveryRestoreDeclined,
+    SavefileLockedDialogOpened,
+    SavefileLockTakeoverAccepted,
+    SavefileLockTakeoverDeclined,
     DialogError(gtk::glib::Error),
     AddSampleSetClicked,
     InputDialogOpened(InputDialogContext),
-    InputDialogSubmitted(InputDialogContext, String),
+    InputDialogSubmitted(InputDialogContext, DialogText),
     InputDialogCanceled(InputDialogContext),
     SelectFolderDialogOpened(SelectFolderDialogContext),
     SampleSetSelected(Uuid),
+    SampleSetDuplicateClicked(Uuid),
+    SampleSetRenameClicked(Uuid),
+    SampleSetExportClicked(Uuid),
     SampleSetLabellingKindChanged(LabellingKind),
     SampleSetDetailsExportClicked,
+    SampleSetCoverBrowseClicked,
+    SampleSetCoverBrowseOpened,
+    SampleSetCoverBrowseSubmitted(String),
+    SampleSetCoverBrowseError(gtk::glib::Error),
+    SampleSetCoverClearClicked,
+    SampleSetMemberMoved(usize, isize),
+    SampleSetMemberExportToggled(Sample, bool),
+    SampleSetMemberFindClicked(Sample),
+    SampleSetMemberPlayClicked(Sample),
+    SampleRevealed,
+    SampleOpenFolderClicked,
+    SampleFolderOpened,
     ExportDialogOpened(dialogs::ExportDialogView),
     ExportDialogClosed,
     ExportTargetDirectoryChanged(String),
+    ExportFilenameTemplateChanged(String),
     ExportTargetDirectoryBrowseClicked,
     ExportTargetDirectoryBrowseSubmitted(String),
     ExportTargetDirectoryBrowseError(gtk::glib::Error),
     PerformExportClicked,
+    QuickExportRequested,
     PlainCopyExportSelected,
+    SfzExportSelected,
     ConversionExportSelected,
     ExportJobMessage(libasampo::samplesets::export::ExportJobMessage),
+    FindDuplicatesRequested,
+    DuplicatesJobFinished(Vec<Vec<Sample>>),
+    DuplicatesJobDisconnected,
+    DuplicatesDialogOpened,
+    DuplicatesHideToggled(bool),
     ExportJobDisconnected,
+    SaveJobMessage(SaveJobMessage),
+    SaveJobDisconnected,
+    LoadJobMessage(LoadJobMessage),
+    LoadJobDisconnected,
+    ExportErrorsDialogOpened,
     StopAllSoundButtonClicked,
+    PageChanged(String),
+    PreviewMuteToggled(bool),
+    PreviewVolumeChanged(u32),
     DrumMachineTempoChanged(u16),
     DrumMachineSwingChanged(u32),
+    DrumMachineVolumeChanged(u32),
+    DrumMachineMetronomeToggled(bool),
     DrumMachinePlayClicked,
     DrumMachineStopClicked,
     DrumMachineBackClicked,
@@ -161,26 +348,86 @@ enum AppMessage {
     DrumMachinePadClicked(usize),
     DrumMachinePartClicked(usize),
     DrumMachineStepClicked(usize),
+    DrumMachineStepVelocityCycled(usize),
     DrumMachinePlaybackEvent(DrumkitSequenceEvent),
 }
 
+impl AppMessage {
+    /// Whether the message is sent many times a second, e.g by the timers, and would drown
+    /// out the other messages in logs.
+    fn is_frequent(&self) -> bool {
+        matches!(
+            self,
+            AppMessage::TimerTick
+                | AppMessage::SourceLoadingMessage(..)
+                | AppMessage::DrumMachinePlaybackEvent(..)
+        )
+    }
+}
+
 fn update(model_ptr: AppModelPtr, view: &AsampoView, message: AppMessage) {
-    match message {
-        AppMessage::TimerTick => (),
-        AppMessage::SourceLoadingMessage(..) => (),
-        AppMessage::DrumMachinePlaybackEvent(..) => (),
-        _ => log::log!(log::Level::Debug, "{message:?}"),
+    let frequent = message.is_frequent();
+
+    if !frequent {
+        log::log!(log::Level::Debug, "{message:?}");
     }
 
-    let old_model = model_ptr.take().unwrap();
+    crashdump::record_message(&message);
 
-    match update_model(old_model.clone(), message) {
+    let shares_config = !matches!(message, AppMessage::ConfigShared(_));
+    let old_model = model_ptr.take().unwrap();
+    let samples_snapshot = SamplesSnapshot::take(&old_model, frequent);
+    let profiling = profiler::is_enabled(&old_model);
+    let json_logging = !frequent && jsonlog::is_enabled();
+    let message_debug = (profiling || json_logging).then(|| format!("{message:?}"));
+    let started = Instant::now();
+
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        update_model(old_model.clone(), message)
+    }))
+    .unwrap_or_else(|panic| Err(panic_error(&old_model, &*panic)));
+
+    match result {
         Ok(new_model) => {
+            let model_time = started.elapsed();
+
+            let config_changed = old_model.config != new_model.config;
+
             model_ptr.set(Some(new_model.clone()));
             update_view(model_ptr.clone(), old_model, new_model.clone(), view);
+
+            if let Some(message_debug) = message_debug {
+                let view_time = started.elapsed() - model_time;
+
+                if json_logging {
+                    jsonlog::record_message(&message_debug, model_time, view_time, true);
+                }
+
+                if profiling {
+                    profiler::record(
+                        view,
+                        jsonlog::message_kind(&message_debug),
+                        model_time,
+                        view_time,
+                    );
+                }
+            }
+
+            if let Some(config) = new_model
+                .config
+                .as_ref()
+                .filter(|_| shares_config && config_changed)
+            {
+                share_config(&model_ptr, config);
+            }
         }
 
         Err(e) => {
+            if let Some(message_debug) = message_debug.filter(|_| json_logging) {
+                jsonlog::record_message(&message_debug, started.elapsed(), Duration::ZERO, false);
+            }
+
+            samples_snapshot.restore(&old_model);
             model_ptr.set(Some(old_model));
             log::log!(log::Level::Error, "{}", e.to_string());
 
@@ -191,286 +438,1728 @@ fn update(model_ptr: AppModelPtr, view: &AsampoView, message: AppMessage) {
                     ErrorWithEffect::AlertDialog { text, detail } => {
                         dialogs::alert(model_ptr.clone(), view, &text, &detail)
                     }
+                    ErrorWithEffect::Toast { text } => dialogs::toast(view, &text),
+                    ErrorWithEffect::PanicDump { text, path } => {
+                        dialogs::panic_dump(view, &text, &path)
+                    }
+                    ErrorWithEffect::AudioDisconnected => model_ptr.with_model(|model| AppModel {
+                        audio_send_failed: true,
+                        ..model
+                    }),
                 }
             }
         }
     }
 }
 
-fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow::Error> {
-    match message {
-        AppMessage::TimerTick => {
-            if !model.sources_loading.is_empty() {
-                model.populate_samples_listmodel();
-            }
-
-            if model
-                .config_save_timeout
-                .is_some_and(|t| t <= Instant::now())
-            {
-                let config = model
-                    .config
-                    .as_ref()
-                    .expect("There should be an active config");
-
-                log::log!(
-                    log::Level::Info,
-                    "Saving config to {:?}",
-                    config.config_save_path
-                );
-                ConfigFile::save(config, &config.config_save_path)?;
-
-                log::log!(log::Level::Info, "Respawning audiothread with new config");
+/// Write a state dump for a panic caught while handling a message, turning the panic into an
+/// error so that the previous model is kept.
+/// The samples list as it was before a message was handled. The list is shared between
+/// models rather than copied, so it is put back by hand when handling a message fails, or
+/// panics, after changing it.
+enum SamplesSnapshot {
+    /// The frequent messages only ever add samples, so their length is all there is to
+    /// remember, which keeps them cheap.
+    Len(usize),
+    Full(Vec<Sample>),
+}
 
-                let had_dks_render_thread = model.drum_machine.render_thread_tx.is_some();
+impl SamplesSnapshot {
+    fn take(model: &AppModel, frequent: bool) -> SamplesSnapshot {
+        let samples = model.samples.borrow();
 
-                if let Some(control_tx) = &model.drum_machine.render_thread_tx {
-                    match control_tx.send(drumkit_render_thread::Message::Shutdown) {
-                        Ok(_) => (),
-                        Err(e) => {
-                            log::log!(
-                                log::Level::Error,
-                                "Error shutting down drumkit sequence render thread: {e}"
-                            );
-                        }
-                    }
-                }
+        match frequent {
+            true => SamplesSnapshot::Len(samples.len()),
+            false => SamplesSnapshot::Full(samples.clone()),
+        }
+    }
 
-                // TODO: sequence the shutdown in some other way to avoid sleeping the main thread
-                // give drum machine thread some time to shut down gracefully
-                std::thread::sleep(Duration::from_millis(250));
+    fn restore(self, model: &AppModel) {
+        let changed = {
+            let mut samples = model.samples.borrow_mut();
 
-                if let Some(prev_tx) = model.audiothread_tx {
-                    match prev_tx.send(audiothread::Message::Shutdown) {
-                        Ok(_) => {
-                            // give audiothread some time to shut down gracefully
-                            std::thread::sleep(Duration::from_millis(10))
-                        }
-                        Err(e) => {
-                            log::log!(log::Level::Error, "Error shutting down audiothread: {e}")
-                        }
-                    }
+            match self {
+                SamplesSnapshot::Len(len) => {
+                    let changed = samples.len() != len;
+                    samples.truncate(len);
+                    changed
                 }
 
-                let (audiothread_tx, audiothread_rx) = mpsc::channel::<audiothread::Message>();
-
-                let _audiothread_handle = Some(Rc::new(audiothread::spawn(
-                    audiothread_rx,
-                    Some(
-                        audiothread::Opts::default()
-                            .with_name("asampo")
-                            .with_spec(AudioSpec::new(config.output_samplerate_hz, 2)?)
-                            .with_conversion_quality(config.sample_rate_conversion_quality)
-                            .with_buffer_size((config.buffer_size_frames as usize).try_into()?),
-                    ),
-                )));
-
-                let drum_machine = if had_dks_render_thread {
-                    DrumMachineModel::new_with_render_thread(audiothread_tx.clone())
-                } else {
-                    DrumMachineModel::new(None, None)
-                };
-
-                Ok(AppModel {
-                    audiothread_tx: Some(audiothread_tx.clone()),
-                    _audiothread_handle,
-                    drum_machine,
-                    ..model
+                SamplesSnapshot::Full(snapshot) => {
+                    let changed = *samples != snapshot;
+                    *samples = snapshot;
+                    changed
                 }
-                .clear_config_save_timeout())
-            } else {
-                Ok(model)
             }
-        }
-
-        AppMessage::SettingsOutputSampleRateChanged(choice) => {
-            let new_config = model
-                .config
-                .clone()
-                .ok_or(anyhow!("There should be an active config"))?
-                .with_samplerate_choice(choice);
+        };
 
-            Ok(model
-                .set_latency_approx_label_by_config(&new_config)
-                .set_config(new_config)
-                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        if changed {
+            model.populate_samples_listmodel();
         }
+    }
+}
 
-        AppMessage::SettingsBufferSizeChanged(samples) => {
-            let new_config = model
-                .config
-                .clone()
-                .ok_or(anyhow!("There should be an active config"))?
-                .with_buffer_size(samples);
+fn panic_error(model: &AppModel, panic: &(dyn Any + Send)) -> anyhow::Error {
+    let text = crashdump::panic_message(panic);
 
-            Ok(model
-                .set_latency_approx_label_by_config(&new_config)
-                .set_config(new_config)
-                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
-        }
+    match crashdump::write(model, &text) {
+        Ok(path) => anyhow::Error::new(ErrorWithEffect::PanicDump { text, path }),
+        Err(e) => anyhow::Error::new(ErrorWithEffect::AlertDialog {
+            text: "Internal error".to_string(),
+            detail: format!("{text}\n\nFailed to write state dump: {e}"),
+        }),
+    }
+}
 
-        AppMessage::SettingsSampleRateConversionQualityChanged(choice) => {
-            let new_config = model
-                .config
-                .clone()
-                .ok_or(anyhow!("There should be an active config"))?
-                .with_conversion_quality_choice(choice);
+/// An error for a failed send to the audiothread, which gets the audiothread respawned.
+fn audiothread_send_error<T>(_: mpsc::SendError<T>) -> anyhow::Error {
+    anyhow::Error::new(ErrorWithEffect::AudioDisconnected)
+}
 
-            Ok(model
-                .set_config(new_config)
-                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
-        }
+/// An error that is shown to the user as a toast rather than only being logged.
+fn toast_error(text: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(ErrorWithEffect::Toast { text: text.into() })
+}
 
-        AppMessage::SettingsSamplePlaybackBehaviorChanged(choice) => {
-            let new_config = model
-                .config
-                .clone()
-                .ok_or(anyhow!("There should be an active config"))?
-                .with_sample_playback_behavior_choice(choice);
+fn savefile_load_error(e: anyhow::Error) -> anyhow::Error {
+    let text = if e.is::<NewerFormatError>() {
+        "Savefile was written by a newer version of Asampo"
+    } else {
+        "Error loading savefile"
+    };
+
+    anyhow::Error::new(ErrorWithEffect::AlertDialog {
+        text: text.to_string(),
+        detail: e.to_string(),
+    })
+}
 
-            Ok(model
-                .set_config(new_config)
-                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
-        }
+fn load_savefile_into_model(
+    model: AppModel,
+    loaded_app_model: AppModel,
+) -> Result<AppModel, anyhow::Error> {
+    let model = AppModel {
+        viewvalues: ViewValues {
+            sources_sample_count: loaded_app_model.sources.keys().map(|k| (*k, 0)).collect(),
+            ..model.viewvalues
+        },
+        sources: loaded_app_model.sources,
+        sources_order: loaded_app_model.sources_order,
+        sets: loaded_app_model.sets,
+        sets_order: loaded_app_model.sets_order,
+        sets_cover_images: loaded_app_model.sets_cover_images,
+        sets_sample_order: loaded_app_model.sets_sample_order,
+        sets_export_excluded: loaded_app_model.sets_export_excluded,
+        sets_favorites: loaded_app_model.sets_favorites,
+        sample_playback_behavior_override: loaded_app_model.sample_playback_behavior_override,
+        samples_display_names: loaded_app_model.samples_display_names,
+        ..model
+    };
+
+    model.samples.borrow_mut().clear();
+    model.populate_samples_listmodel();
+
+    Ok(AppModel {
+        sources_rescanning: HashMap::new(),
+        sources_watching: HashMap::new(),
+        sources_rescan_due: HashMap::new(),
+        sources_loading: model
+            .sources
+            .iter()
+            .filter_map(|(uuid, source)| match source.is_enabled() {
+                true => {
+                    let (tx, rx) =
+                        std::sync::mpsc::channel::<Result<Sample, libasampo::errors::Error>>();
+                    std::thread::spawn(clone!(@strong source => move || {
+                        source.list_async(tx);
+                    }));
+
+                    Some((*uuid, Rc::new(rx)))
+                }
 
-        AppMessage::AddFilesystemSourceNameChanged(text) => Ok(model
-            .set_sources_add_fs_name_entry(text)
-            .validate_sources_add_fs_fields()),
+                false => None,
+            })
+            .collect(),
+        ..model
+    })
+}
 
-        AppMessage::AddFilesystemSourcePathChanged(text) => Ok(model
-            .set_sources_add_fs_path_entry(text)
-            .validate_sources_add_fs_fields()),
+const SOURCE_PREVIEW_DEFAULT_EXTENSIONS: [&str; 3] = ["wav", "flac", "ogg"];
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// wait this long before the first retry of a lost audiothread, doubling it for each retry
+const AUDIO_RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+const AUDIO_RECONNECT_MAX_ATTEMPTS: u32 = 5;
+
+// wait for changes to settle before rescanning a watched source
+const SOURCE_RESCAN_DELAY: Duration = Duration::from_secs(2);
+
+/// Rescan the watched sources whose files last changed at least `SOURCE_RESCAN_DELAY` ago
+/// and that are not already loading. Each rescanned source is taken off the list of sources
+/// due for a rescan, also when starting the rescan fails.
+fn rescan_changed_sources(model: AppModel) -> AppModel {
+    let due = model
+        .sources_rescan_due
+        .iter()
+        .filter(|(uuid, t)| **t <= Instant::now() && !model.sources_loading.contains_key(uuid))
+        .map(|(uuid, _)| *uuid)
+        .collect::<Vec<Uuid>>();
+
+    due.iter().fold(model, |model, uuid| {
+        let model = AppModel {
+            sources_rescan_due: model
+                .sources_rescan_due
+                .clone_and_remove(uuid)
+                .unwrap_or_default(),
+            ..model
+        };
 
-        AppMessage::AddFilesystemSourcePathBrowseClicked => {
-            Ok(model.signal_sources_add_fs_begin_browse())
+        match model.clone().begin_source_rescan(uuid) {
+            Ok(model) => model,
+            Err(e) => {
+                log::log!(log::Level::Error, "{e}");
+                model
+            }
         }
+    })
+}
 
-        AppMessage::AddFilesystemSourcePathBrowseSubmitted(text) => Ok(match (
-            model.viewvalues.sources_add_fs_name_entry.is_empty(),
-            Path::new(&text).file_name(),
-        ) {
-            (true, Some(filename)) => model
-                .set_sources_add_fs_name_entry(
-                    filename
-                        .to_str()
-                        .ok_or(anyhow!("Path contains invalid UTF-8"))?,
-                )
-                .set_sources_add_fs_path_entry(text),
+/// Write the workspace to the recovery file if it has unsaved changes that have not already
+/// been written there. Workspaces that are to be encrypted are never written in plain text,
+/// so autosave is skipped for those.
+fn autosave_if_due(model: AppModel) -> AppModel {
+    if !model.autosave || model.autosave_deadline.is_some_and(|t| t > Instant::now()) {
+        return model;
+    }
+
+    let model = AppModel {
+        autosave_deadline: Some(Instant::now() + AUTOSAVE_INTERVAL),
+        ..model
+    };
+
+    if !model.modified()
+        || !model.modified_since_autosave()
+        || model
+            .config
+            .as_ref()
+            .is_some_and(|config| config.encrypt_savefiles)
+    {
+        return model;
+    }
 
-            _ => model.set_sources_add_fs_path_entry(text),
+    let recovery_path = savefile::recovery_path();
+    log::log!(log::Level::Debug, "Autosaving to {recovery_path}");
+
+    match Savefile::save(&model, &recovery_path) {
+        Ok(_) => model.mark_autosaved(),
+        Err(e) => {
+            log::log!(log::Level::Error, "Autosave failed: {e}");
+            model
         }
-        .validate_sources_add_fs_fields()),
+    }
+}
 
-        AppMessage::AddFilesystemSourcePathBrowseError(error) => {
-            log::log!(log::Level::Debug, "Error browsing for folder: {error:?}");
+fn remove_recovery_file() {
+    let recovery_path = savefile::recovery_path();
 
-            Ok(model)
+    if Path::new(&recovery_path).exists() {
+        if let Err(e) = std::fs::remove_file(&recovery_path) {
+            log::log!(log::Level::Error, "Failed to remove recovery file: {e}");
         }
+    }
+}
 
-        AppMessage::AddFilesystemSourceExtensionsChanged(text) => Ok(model
-            .set_sources_add_fs_extensions_entry(text)
-            .validate_sources_add_fs_fields()),
+/// Hold off opening or saving `filename` while another instance holds its lock file, and ask
+/// whether to take the lock over.
+fn with_savefile_lock_conflict(
+    model: AppModel,
+    filename: String,
+    held_by: String,
+    saving: bool,
+) -> AppModel {
+    log::log!(log::Level::Warn, "{filename} is locked by {held_by}");
+
+    AppModel {
+        viewflags: ViewFlags {
+            savefile_locked_show_dialog: true,
+            ..model.viewflags
+        },
+        savefile_lock_conflict: Some(SavefileLockConflict {
+            filename,
+            held_by,
+            saving,
+        }),
+        ..model
+    }
+}
 
-        AppMessage::AddFilesystemSourceClicked => Ok(model
-            .commit_file_system_source()?
-            .tap(AppModel::populate_samples_listmodel)),
+/// Make `filename` the savefile of a model that was just saved to or loaded from it, and move
+/// it to the top of the recently used savefiles. The lock file of the previous savefile, if
+/// any, is released and `filename` is locked instead.
+fn with_active_savefile(model: AppModel, filename: String) -> AppModel {
+    if let Some(previous) = model.savefile.as_ref().filter(|f| **f != filename) {
+        lockfile::release(previous, &model.window_id);
+    }
 
-        AppMessage::SourceLoadingMessage(uuid, messages) => {
-            let mut samples = model.samples.borrow_mut();
-            let len_before = samples.len();
+    if let Err(e) = lockfile::acquire(&filename, &model.window_id) {
+        log::log!(log::Level::Error, "Failed to lock savefile: {e}");
+    }
 
-            for message in messages {
-                match message {
-                    Ok(sample) => {
-                        samples.push(sample);
-                    }
+    let model = match model.config.clone() {
+        Some(config) => {
+            let config = config.with_recent_savefile(&filename);
 
-                    Err(e) => log::log!(log::Level::Error, "Error loading source: {e}"),
-                }
+            if let Err(e) = ConfigFile::save(&config, &config.config_save_path) {
+                log::log!(log::Level::Error, "Failed to save config: {e}");
             }
 
-            let added = samples.len() - len_before;
-            drop(samples);
-
-            model.source_sample_count_add(uuid, added)
+            model.set_config(config)
         }
 
-        AppMessage::SourceLoadingDisconnected(uuid) => {
-            model.populate_samples_listmodel();
+        None => model,
+    };
 
-            Ok(AppModel {
-                sources_loading: model.sources_loading.clone_and_remove(&uuid)?,
-                ..model
-            })
-        }
+    AppModel {
+        savefile: Some(filename),
+        ..model
+    }
+    .mark_saved()
+}
 
-        AppMessage::SampleListSampleSelected(index) => {
-            let item = model.viewvalues.samples_listview_model.item(index);
+/// Start loading `filename` on a worker thread, decrypting it if a password is given. The
+/// window is kept insensitive until the loaded workspace has replaced the current one.
+fn begin_load(
+    model: AppModel,
+    filename: String,
+    password: Option<String>,
+    recovery: bool,
+) -> Result<AppModel, anyhow::Error> {
+    if model.savefile_loading.is_some() {
+        return Err(anyhow!("A savefile is already being loaded"));
+    }
 
-            match item
-                .and_dynamic_cast_ref::<SampleListEntry>()
-                .map(|x| &x.value)
-            {
-                Some(sample) => {
-                    let stream = model
-                        .sources
-                        .get(
-                            sample
-                                .borrow()
-                                .source_uuid()
-                                .ok_or(anyhow!("Sample missing source uuid"))?,
-                        )
-                        .ok_or(anyhow!("Failed to get source for sample"))?
-                        .stream(&sample.borrow())?;
+    let (tx, rx) = mpsc::channel::<LoadJobMessage>();
+    Savefile::load_in_background(&filename, password.clone(), tx);
 
-                    model
-                        .audiothread_tx
-                        .as_ref()
-                        .unwrap()
-                        .send(audiothread::Message::PlaySymphoniaSource(
-                            audiothread::SymphoniaSource::from_buf_reader(BufReader::new(stream))?,
-                        ))
-                        .map_err(|_| anyhow!("Send error on audio thread control channel"))?;
+    Ok(AppModel {
+        viewflags: ViewFlags {
+            view_sensitive: false,
+            ..model.viewflags
+        },
+        savefile_loading: Some(LoadInProgress {
+            filename,
+            password,
+            stage: "Opening",
+            recovery,
+        }),
+        load_job_rx: Some(Rc::new(rx)),
+        ..model
+    })
+}
 
-                    Ok(AppModel {
-                        samplelist_selected_sample: Some(sample.borrow().clone()),
-                        ..model
-                    })
-                }
-                None => Err(anyhow!("Could not obtain clicked sample (this is a bug)")),
-            }
+/// Save the workspace to `filename`, first asking for a password if savefiles are encrypted
+/// and no password is remembered.
+fn save_to(model: AppModel, filename: String) -> Result<AppModel, anyhow::Error> {
+    let encrypt = model
+        .config
+        .as_ref()
+        .is_some_and(|config| config.encrypt_savefiles);
+
+    match (encrypt, model.savefile_password.clone()) {
+        (false, _) => {
+            log::log!(log::Level::Info, "Saving to {filename}");
+            begin_save(model, filename, None)
         }
 
-        AppMessage::SamplesFilterChanged(text) => Ok(AppModel {
-            viewvalues: ViewValues {
-                samples_list_filter: text,
-                ..model.viewvalues
-            },
-            ..model
+        (true, Some(password)) => {
+            log::log!(log::Level::Info, "Saving encrypted to {filename}");
+            begin_save(model, filename, Some(password))
         }
-        .tap(AppModel::populate_samples_listmodel)),
 
-        AppMessage::SampleSidebarAddToSetClicked => Ok(AppModel {
+        (true, None) => Ok(AppModel {
             viewflags: ViewFlags {
-                samples_sidebar_add_to_set_show_dialog: true,
+                savefile_save_password_show_dialog: true,
                 ..model.viewflags
             },
+            savefile_pending: Some(filename),
             ..model
         }),
+    }
+}
 
-        AppMessage::SampleSidebarAddToMostRecentlyUsedSetClicked => {
-            let mru_uuid = model
-                .sets_most_recently_used_uuid
-                .ok_or(anyhow!("No sample set recently added to"))?;
+/// Start saving the workspace to `filename` on a worker thread, encrypted if a password is
+/// given. The savefile becomes the active one once saving has finished.
+fn begin_save(
+    model: AppModel,
+    filename: String,
+    password: Option<String>,
+) -> Result<AppModel, anyhow::Error> {
+    if model.savefile_saving.is_some() {
+        return Err(anyhow!("A save is already in progress"));
+    }
 
-            model::util::add_selected_sample_to_sampleset_by_uuid(model, &mru_uuid)
-        }
+    let (tx, rx) = mpsc::channel::<SaveJobMessage>();
+    Savefile::save_in_background(&model, &filename, password, tx)?;
 
-        AppMessage::SourceEnabled(uuid) => {
-            let source = model
-                .sources
-                .get(&uuid)
-                .ok_or(anyhow!("Source not found"))?;
+    Ok(AppModel {
+        savefile_saving: Some(SaveInProgress {
+            filename,
+            snapshot: model.snapshot(),
+            stage: "Saving",
+        }),
+        save_job_rx: Some(Rc::new(rx)),
+        ..model
+    })
+}
+
+/// Copy a sample set cover image into an export directory as `cover.<ext>`.
+fn export_cover_image(cover_image: &str, target_dir: &str) -> Result<(), anyhow::Error> {
+    let cover_path = Path::new(cover_image);
+    let mut target_path = Path::new(target_dir).join("cover");
+
+    if let Some(ext) = cover_path.extension() {
+        target_path.set_extension(ext);
+    }
+
+    std::fs::copy(cover_path, target_path)?;
+    Ok(())
+}
+
+/// The paths of the files an export writes for the members of a sample set, paired with the
+/// paths they are to be renamed to, from the file names and export stems paired by
+/// [`AppModel::export_file_names`]. Converted files are written as WAV files.
+fn exported_files(
+    target_dir: &str,
+    file_names: &[(String, String)],
+    converted: bool,
+) -> Vec<(PathBuf, PathBuf)> {
+    file_names
+        .iter()
+        .map(|(file_name, export_stem)| {
+            let written = match converted {
+                true => Path::new(file_name).with_extension("wav"),
+                false => PathBuf::from(file_name),
+            };
+
+            // the export stem may contain dots, so append the extension rather than set it
+            let export_name = match written.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) => format!("{export_stem}.{ext}"),
+                None => export_stem.to_string(),
+            };
+
+            (
+                Path::new(target_dir).join(written),
+                Path::new(target_dir).join(export_name),
+            )
+        })
+        .collect()
+}
+
+/// Rename exported files as paired by [`exported_files`], and return the paths of those that
+/// were written, in the order given. Files whose new name is taken are left as they are.
+fn rename_exported_files(files: &[(PathBuf, PathBuf)]) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let mut result = Vec::new();
+
+    for (written, target) in files {
+        if !written.exists() {
+            continue;
+        }
+
+        if written == target {
+            result.push(written.clone());
+        } else if target.exists() {
+            log::log!(
+                log::Level::Warn,
+                "Not renaming {written:?} to {target:?}, which already exists"
+            );
+            result.push(written.clone());
+        } else {
+            if let Some(dir) = target.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+
+            std::fs::rename(written, target)?;
+            result.push(target.clone());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Write an SFZ instrument for exported WAV `files` into the export directory, named after
+/// the exported set. The files are mapped to keys in the order given.
+fn export_sfz_instrument(
+    set_name: &str,
+    target_dir: &str,
+    files: &[PathBuf],
+) -> Result<(), anyhow::Error> {
+    let files = files
+        .iter()
+        .filter_map(|path| path.strip_prefix(target_dir).ok()?.to_str())
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    std::fs::write(
+        Path::new(target_dir).join(format!("{}.sfz", set_name.replace(['/', '\\'], "_"))),
+        util::sfz_instrument(set_name, &files),
+    )?;
+
+    Ok(())
+}
+
+/// Parse the file name template of the export dialog, where no template keeps the names of
+/// the exported samples.
+fn export_filename_template(text: &str) -> Result<Option<model::FilenameTemplate>, anyhow::Error> {
+    match text.trim() {
+        "" => Ok(None),
+        text => model::FilenameTemplate::parse(text).map(Some),
+    }
+}
+
+/// Export the selected sample set in the background, remembering the settings used so that
+/// the export can be repeated with a quick export.
+fn perform_export(
+    model: AppModel,
+    target_dir: String,
+    kind: Option<model::ExportKind>,
+    template: Option<model::FilenameTemplate>,
+) -> Result<AppModel, anyhow::Error> {
+    use libasampo::samplesets::export::{RateConversionQuality, WavSampleFormat, WavSpec};
+
+    let set_uuid = model
+        .sets_selected_set
+        .ok_or(anyhow!("No sample set selected"))?;
+
+    let sampleset = model.set_for_export(&set_uuid)?;
+
+    let file_names = model.export_file_names(&set_uuid, template.as_ref())?;
+
+    let num_samples = sampleset.len();
+    let cover_image = model.sets_cover_images.get(&set_uuid).cloned();
+
+    let resolved_dir =
+        util::resolve_export_target(&target_dir, model.savefile.as_deref(), sampleset.name())
+            .map_err(|e| {
+                anyhow::Error::new(ErrorWithEffect::AlertDialog {
+                    text: "Error exporting sample set".to_string(),
+                    detail: e.to_string(),
+                })
+            })?;
+
+    if resolved_dir != target_dir {
+        std::fs::create_dir_all(&resolved_dir)?;
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel::<ExportJobMessage>();
+
+    std::thread::spawn(
+        clone!(@strong model, @strong resolved_dir, @strong kind => move || {
+            let job = ExportJob::new(
+                resolved_dir.clone(),
+                match kind {
+                    None | Some(model::ExportKind::PlainCopy) => None,
+                    Some(model::ExportKind::Conversion)
+                    | Some(model::ExportKind::SfzInstrument) => Some(Conversion::Wav(
+                        WavSpec {
+                            channels: 2,
+                            sample_rate: 44100,
+                            bits_per_sample: 16,
+                            sample_format: WavSampleFormat::Int,
+                        },
+                        Some(RateConversionQuality::High),
+                    )),
+                });
+
+            let set_name = sampleset.name().to_string();
+            let files = exported_files(
+                &resolved_dir,
+                &file_names,
+                !matches!(kind, None | Some(model::ExportKind::PlainCopy)),
+            );
+
+            job.perform(&sampleset, &model.sources, Some(tx));
+
+            let files = rename_exported_files(&files).unwrap_or_else(|e| {
+                log::log!(log::Level::Error, "Failed to rename exported files: {e}");
+                Vec::new()
+            });
+
+            if let Some(model::ExportKind::SfzInstrument) = kind {
+                if let Err(e) = export_sfz_instrument(&set_name, &resolved_dir, &files) {
+                    log::log!(log::Level::Error, "Failed to export SFZ instrument: {e}");
+                }
+            }
+
+            if let Some(cover_image) = &cover_image {
+                if let Err(e) = export_cover_image(cover_image, &resolved_dir) {
+                    log::log!(log::Level::Error, "Failed to export cover image: {e}");
+                }
+            }
+        }),
+    );
+
+    Ok(AppModel {
+        sets_export_state: Some(model::ExportState::Exporting),
+        sets_export_progress: Some((0, num_samples)),
+        sets_export_last_settings: Some((target_dir, kind, template)),
+        sets_export_errors: Vec::new(),
+        export_job_rx: Some(Rc::new(rx)),
+        ..model
+    })
+}
+
+/// Play a metronome click alongside the drum machine, bypassing the sample preview settings.
+fn play_metronome_click(model: &AppModel, accent: bool) -> Result<(), anyhow::Error> {
+    model
+        .audiothread_tx
+        .as_ref()
+        .ok_or(anyhow!("Audio thread not running"))?
+        .send(audiothread::Message::PlaySymphoniaSource(
+            audiothread::SymphoniaSource::from_buf_reader(BufReader::new(std::io::Cursor::new(
+                metronome_click_wav(accent),
+            )))?,
+        ))
+        .map_err(audiothread_send_error)
+}
+
+/// Whether playing a sample should first cut off whatever is playing. DropAll would also
+/// silence the drum machine, so previews are only cut off while it isn't playing.
+fn cuts_off_previews(model: &AppModel) -> bool {
+    model.sample_playback_behavior() == SamplePlaybackBehavior::PlaySingleSample
+        && !model.drum_machine.is_playing()
+}
+
+fn play_sample_stream(
+    model: &AppModel,
+    stream: impl std::io::Read + Send + Sync + 'static,
+) -> Result<(), anyhow::Error> {
+    let audiothread_tx = model
+        .audiothread_tx
+        .as_ref()
+        .ok_or(anyhow!("Audio thread not running"))?;
+
+    if model.samples_preview_muted {
+        return Ok(());
+    }
+
+    if cuts_off_previews(model) {
+        audiothread_tx
+            .send(audiothread::Message::DropAll)
+            .map_err(audiothread_send_error)?;
+    }
+
+    let volume = model
+        .config
+        .as_ref()
+        .map(|config| config.preview_volume)
+        .unwrap_or(100);
+
+    match (model.samples_preview_semitones, volume) {
+        (0, 100) => audiothread_tx
+            .send(audiothread::Message::PlaySymphoniaSource(
+                audiothread::SymphoniaSource::from_buf_reader(BufReader::new(stream))?,
+            ))
+            .map_err(audiothread_send_error),
+
+        // processing reads the whole sample, which is left to a thread of its own so as not
+        // to hold up the main thread
+        (semitones, volume) => {
+            let audiothread_tx = audiothread_tx.clone();
+
+            std::thread::spawn(move || {
+                let result = processed_preview(stream, semitones, volume).and_then(|data| {
+                    audiothread_tx
+                        .send(audiothread::Message::PlaySymphoniaSource(
+                            audiothread::SymphoniaSource::from_buf_reader(BufReader::new(
+                                std::io::Cursor::new(data),
+                            ))?,
+                        ))
+                        .map_err(|_| anyhow!("Send error on audio thread control channel"))
+                });
+
+                if let Err(e) = result {
+                    log::log!(log::Level::Error, "Failed to play preview: {e}");
+                }
+            });
+
+            Ok(())
+        }
+    }
+}
+
+/// Read a sample for preview with its pitch shifted by `semitones`, which also changes its
+/// speed, and its level scaled to `volume` percent. Files in other formats than WAV are
+/// decoded into WAV files to be processed, or returned unchanged if they can't be decoded.
+fn processed_preview(
+    mut stream: impl std::io::Read,
+    semitones: i32,
+    volume: u32,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data)?;
+
+    if !util::is_wav(&data) {
+        data = match util::decode_to_wav(data.clone()) {
+            Ok(wav) => wav,
+            Err(e) => {
+                log::log!(
+                    log::Level::Warn,
+                    "Playing sample at original pitch and volume: {e}"
+                );
+                return Ok(data);
+            }
+        };
+    }
+
+    if semitones != 0 {
+        if let Err(e) = util::wav_scale_rate(&mut data, 2f64.powf(semitones as f64 / 12.0)) {
+            log::log!(log::Level::Warn, "Playing sample at original pitch: {e}");
+        }
+    }
+
+    if volume != 100 {
+        if let Err(e) = util::wav_scale_amplitude(&mut data, volume as f32 / 100.0) {
+            log::log!(log::Level::Warn, "Playing sample at full volume: {e}");
+        }
+    }
+
+    Ok(data)
+}
+
+/// The velocity curve of the configuration, or the default curve if there is no config.
+fn velocity_curve(model: &AppModel) -> VelocityCurve {
+    model
+        .config
+        .as_ref()
+        .map(|config| config.velocity_curve.clone())
+        .unwrap_or_default()
+}
+
+/// Shut down the audiothread and the drum machine render thread, then spawn new ones using
+/// `config`. The drum machine keeps its sequence, tempo and swing.
+fn respawn_audiothread(model: AppModel, config: &AppConfig) -> Result<AppModel, anyhow::Error> {
+    let had_dks_render_thread = model.drum_machine.render_thread_tx.is_some();
+    let prev_render_thread_tx = model.drum_machine.render_thread_tx.clone();
+    let prev_tx = model.audiothread_tx.clone();
+
+    // shut down the previous threads in the background, rather than sleep the main thread
+    std::thread::spawn(move || {
+        if let Some(control_tx) = prev_render_thread_tx {
+            match control_tx.send(drumkit_render_thread::Message::Shutdown) {
+                Ok(_) => (),
+                Err(e) => {
+                    log::log!(
+                        log::Level::Error,
+                        "Error shutting down drumkit sequence render thread: {e}"
+                    );
+                }
+            }
+
+            // give drum machine thread some time to shut down gracefully
+            std::thread::sleep(Duration::from_millis(250));
+        }
+
+        if let Some(prev_tx) = prev_tx {
+            match prev_tx.send(audiothread::Message::Shutdown) {
+                Ok(_) => (),
+                Err(e) => {
+                    log::log!(log::Level::Error, "Error shutting down audiothread: {e}")
+                }
+            }
+        }
+    });
+
+    let (audiothread_tx, audiothread_rx) = mpsc::channel::<audiothread::Message>();
+
+    let _audiothread_handle = Some(Rc::new(audiothread::spawn(
+        audiothread_rx,
+        Some(
+            audiothread::Opts::default()
+                .with_name("asampo")
+                .with_spec(AudioSpec::new(config.output_samplerate_hz, 2)?)
+                .with_conversion_quality(config.sample_rate_conversion_quality)
+                .with_buffer_size((config.buffer_size_frames as usize).try_into()?),
+        ),
+    )));
+
+    let drum_machine = if had_dks_render_thread {
+        model
+            .drum_machine
+            .clone()
+            .with_new_render_thread(audiothread_tx.clone(), &config.velocity_curve)?
+    } else {
+        model.drum_machine.clone()
+    };
+
+    Ok(AppModel {
+        audiothread_tx: Some(audiothread_tx),
+        _audiothread_handle,
+        audiothread_config: Some(config.clone()),
+        audio_send_failed: false,
+        drum_machine,
+        ..model
+    })
+}
+
+/// Save the config of `model` and clear the pending config save, if any.
+fn save_config(model: AppModel) -> Result<AppModel, anyhow::Error> {
+    let config = model
+        .config
+        .as_ref()
+        .ok_or(anyhow!("There should be an active config"))?;
+
+    log::log!(
+        log::Level::Info,
+        "Saving config to {:?}",
+        config.config_save_path
+    );
+    ConfigFile::save(config, &config.config_save_path)?;
+
+    Ok(model.clear_config_save_timeout())
+}
+
+/// Whether the audio output settings of the config differ from those the audiothread was
+/// spawned with.
+fn audio_output_changed(model: &AppModel) -> bool {
+    match (&model.config, &model.audiothread_config) {
+        (Some(config), Some(audiothread_config)) => audiothread_config.audio_output_differs(config),
+        _ => false,
+    }
+}
+
+/// Whether the audiothread has stopped or can no longer be reached.
+fn audio_lost(model: &AppModel) -> bool {
+    model.audio_send_failed
+        || model
+            ._audiothread_handle
+            .as_ref()
+            .is_some_and(|handle| handle.is_finished())
+}
+
+/// Respawn a lost audiothread, waiting twice as long after each attempt before the next and
+/// giving up after `AUDIO_RECONNECT_MAX_ATTEMPTS` until the audio settings are changed.
+fn reconnect_audio(model: AppModel) -> Result<AppModel, anyhow::Error> {
+    if model
+        .audio_reconnect_deadline
+        .is_some_and(|t| t > Instant::now())
+    {
+        return Ok(model);
+    }
+
+    if model.audio_reconnect_attempts >= AUDIO_RECONNECT_MAX_ATTEMPTS {
+        return Ok(if model.audio_reconnecting {
+            log::log!(log::Level::Error, "Audio could not be reconnected");
+
+            AppModel {
+                audio_reconnecting: false,
+                ..model
+            }
+            .show_toast("Audio could not be reconnected, check the audio settings")
+        } else {
+            model
+        });
+    }
+
+    let config = model
+        .config
+        .clone()
+        .ok_or(anyhow!("There should be an active config"))?;
+
+    let attempts = model.audio_reconnect_attempts + 1;
+
+    log::log!(
+        log::Level::Warn,
+        "Audiothread stopped, reconnecting (attempt {attempts} of {AUDIO_RECONNECT_MAX_ATTEMPTS})"
+    );
+
+    let model = AppModel {
+        audio_reconnecting: true,
+        audio_reconnect_attempts: attempts,
+        audio_reconnect_deadline: Some(
+            Instant::now() + AUDIO_RECONNECT_BACKOFF * 2u32.pow(attempts - 1),
+        ),
+        ..model
+    };
+
+    match respawn_audiothread(model.clone(), &config) {
+        Ok(model) => Ok(model),
+        Err(e) => {
+            log::log!(log::Level::Error, "Error reconnecting audio: {e}");
+            Ok(model)
+        }
+    }
+}
+
+/// Move the current filter of a sample list to the top of the filter history.
+fn remember_filter(model: AppModel, id: SampleListId) -> AppModel {
+    let filter = model.viewvalues.samples_list(id).filter.clone();
+
+    match model.config.clone() {
+        Some(config) if !filter.is_empty() && config.filter_history.first() != Some(&filter) => {
+            model
+                .set_config(config.with_filter_history_entry(&filter))
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3))
+        }
+
+        _ => model,
+    }
+}
+
+fn remembered_savefile_password(model: &AppModel, password: String) -> Option<String> {
+    model
+        .config
+        .as_ref()
+        .is_some_and(|config| config.remember_savefile_password)
+        .then_some(password)
+}
+
+fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow::Error> {
+    match message {
+        AppMessage::TimerTick => {
+            let model = autosave_if_due(rescan_changed_sources(model));
+
+            let model = if model
+                .config_save_timeout
+                .is_some_and(|t| t <= Instant::now())
+            {
+                save_config(model)?
+            } else {
+                model
+            };
+
+            if audio_output_changed(&model) {
+                let config = model
+                    .config
+                    .clone()
+                    .expect("There should be an active config");
+
+                log::log!(log::Level::Info, "Respawning audiothread with new config");
+
+                // new settings deserve a new round of reconnection attempts
+                Ok(AppModel {
+                    audio_reconnect_attempts: 0,
+                    audio_reconnect_deadline: None,
+                    ..respawn_audiothread(model, &config)?
+                })
+            } else if audio_lost(&model) {
+                reconnect_audio(model)
+            } else if model.audio_reconnecting
+                && !model
+                    .audio_reconnect_deadline
+                    .is_some_and(|t| t > Instant::now())
+            {
+                log::log!(log::Level::Info, "Audio reconnected");
+
+                Ok(AppModel {
+                    audio_reconnecting: false,
+                    audio_reconnect_attempts: 0,
+                    audio_reconnect_deadline: None,
+                    ..model
+                }
+                .show_toast("Audio reconnected"))
+            } else {
+                Ok(model)
+            }
+        }
+
+        AppMessage::ShowToast(text) => Ok(model.show_toast(text)),
+        AppMessage::ToastsShown => Ok(model.clear_toasts()),
+
+        AppMessage::SettingsOutputSampleRateChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_samplerate_choice(choice);
+
+            Ok(model
+                .set_latency_approx_label_by_config(&new_config)
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsBufferSizeChanged(samples) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_buffer_size(samples);
+
+            Ok(model
+                .set_latency_approx_label_by_config(&new_config)
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsSampleRateConversionQualityChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_conversion_quality_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsTestOutputClicked => {
+            let config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?;
+
+            // test the settings as shown, rather than wait for them to be applied
+            let model = if model.config_save_timeout.is_some() {
+                save_config(model)?
+            } else {
+                model
+            };
+
+            let model = if audio_output_changed(&model) {
+                respawn_audiothread(model, &config)?
+            } else {
+                model
+            };
+
+            model
+                .audiothread_tx
+                .as_ref()
+                .ok_or(anyhow!("Audio thread not running"))?
+                .send(audiothread::Message::PlaySymphoniaSource(
+                    audiothread::SymphoniaSource::from_buf_reader(BufReader::new(
+                        std::io::Cursor::new(output_test_wav()),
+                    ))?,
+                ))
+                .map_err(audiothread_send_error)?;
+
+            Ok(model.show_toast(format!(
+                "Playing test clicks with {} frames per buffer ({}). If they crackle or \
+                stutter, try a larger buffer.",
+                config.buffer_size_frames,
+                config.fmt_latency_approx()
+            )))
+        }
+
+        AppMessage::AudioProfileSelected(name) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_audio_profile(&name)?;
+
+            Ok(model
+                .set_latency_approx_label_by_config(&new_config)
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::AudioProfileSaveClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                settings_audio_profile_save_show_dialog: true,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::AudioProfileDeleteClicked => {
+            let config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?;
+
+            let name = config
+                .active_audio_profile()
+                .map(|profile| profile.name.clone())
+                .ok_or(anyhow!("No audio profile is active"))?;
+
+            Ok(model
+                .set_config(config.without_audio_profile(&name))
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsSamplePlaybackBehaviorChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_sample_playback_behavior_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsWorkspaceSamplePlaybackBehaviorChanged(choice) => Ok(AppModel {
+            sample_playback_behavior_override: WORKSPACE_SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS
+                .value_for(&choice)
+                .ok_or(anyhow!(
+                    "Invalid choice for workspace sample playback behavior"
+                ))?
+                .clone(),
+            ..model
+        }),
+
+        AppMessage::SettingsSampleFilterModeChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_sample_filter_mode_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3))
+                .tap(AppModel::populate_samples_listmodel))
+        }
+
+        AppMessage::SettingsWatchSourcesChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_watch_sources_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3))
+                .watch_sources())
+        }
+
+        AppMessage::SettingsSourceLoadingChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_source_loading_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsSamplesListKeepPlaceChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_samples_list_keep_place_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsStopPreviewOnPageChangeChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_stop_preview_on_page_change_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsPadLabelsChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_pad_label_set_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsPadLabelsEditClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                settings_pad_labels_show_dialog: true,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SettingsVelocityCurveChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_velocity_curve_choice(choice);
+
+            let drum_machine = model
+                .drum_machine
+                .clone()
+                .with_velocity_curve(&new_config.velocity_curve)?;
+
+            Ok(AppModel {
+                drum_machine,
+                ..model
+            }
+            .set_config(new_config)
+            .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsColorSchemeChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_color_scheme_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsUiScaleChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_ui_scale_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsTroubleshootingLogChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_troubleshooting_log_choice(choice);
+
+            jsonlog::set_enabled(new_config.troubleshooting_log)?;
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsProfilerOverlayChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_profiler_overlay_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsSavefileEncryptionChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_savefile_encryption_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsReviewChangesChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_review_changes_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsReopenLastWorkspaceChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_reopen_last_workspace_choice(choice);
+
+            Ok(model
+                .set_config(new_config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SettingsSavefilePasswordMemoryChanged(choice) => {
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_savefile_password_memory_choice(choice);
+
+            Ok(AppModel {
+                savefile_password: if new_config.remember_savefile_password {
+                    model.savefile_password.clone()
+                } else {
+                    None
+                },
+                ..model
+            }
+            .set_config(new_config)
+            .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::AddFilesystemSourceNameChanged(text) => Ok(model
+            .set_sources_add_fs_name_entry(text)
+            .validate_sources_add_fs_fields()),
+
+        AppMessage::AddFilesystemSourcePathChanged(text) => Ok(model
+            .set_sources_add_fs_path_entry(text)
+            .validate_sources_add_fs_fields()),
+
+        AppMessage::AddFilesystemSourcePathBrowseClicked => {
+            Ok(model.signal_sources_add_fs_begin_browse())
+        }
+
+        AppMessage::AddFilesystemSourcePathBrowseSubmitted(text) => {
+            let exts = match model.viewvalues.sources_add_fs_extensions_entry.is_empty() {
+                true => SOURCE_PREVIEW_DEFAULT_EXTENSIONS
+                    .iter()
+                    .map(|ext| ext.to_string())
+                    .collect(),
+                false => model
+                    .viewvalues
+                    .sources_add_fs_extensions_entry
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .collect::<Vec<String>>(),
+            };
+
+            let preview_files =
+                util::list_files_with_extensions(&text, &exts).unwrap_or_else(|e| {
+                    log::log!(log::Level::Error, "Error listing {text}: {e}");
+                    Vec::new()
+                });
+
+            let model = AppModel {
+                viewflags: ViewFlags {
+                    sources_add_fs_show_preview: true,
+                    ..model.viewflags
+                },
+                viewvalues: ViewValues {
+                    sources_add_fs_preview_files: preview_files,
+                    ..model.viewvalues
+                },
+                ..model
+            };
+
+            Ok(match (
+                model.viewvalues.sources_add_fs_name_entry.is_empty(),
+                Path::new(&text).file_name(),
+            ) {
+                (true, Some(filename)) => model
+                    .set_sources_add_fs_name_entry(
+                        filename
+                            .to_str()
+                            .ok_or(anyhow!("Path contains invalid UTF-8"))?,
+                    )
+                    .set_sources_add_fs_path_entry(text),
+
+                _ => model.set_sources_add_fs_path_entry(text),
+            }
+            .validate_sources_add_fs_fields())
+        }
+
+        AppMessage::SourcePreviewDialogOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                sources_add_fs_show_preview: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SourcePreviewFileClicked(filename) => {
+            play_sample_stream(&model, std::fs::File::open(&filename)?)?;
+
+            Ok(model)
+        }
+
+        AppMessage::AddFilesystemSourcePathBrowseError(error) => {
+            log::log!(log::Level::Debug, "Error browsing for folder: {error:?}");
+
+            Ok(model)
+        }
+
+        AppMessage::AddFilesystemSourceExtensionsChanged(text) => Ok(model
+            .set_sources_add_fs_extensions_entry(text)
+            .validate_sources_add_fs_fields()),
+
+        AppMessage::AddFilesystemSourceClicked => Ok(model
+            .commit_file_system_source()?
+            .tap(AppModel::populate_samples_listmodel)),
+
+        AppMessage::SourceLoadingMessage(uuid, messages) => {
+            if let Some(rescanned) = model.sources_rescanning.get(&uuid) {
+                let mut rescanned = rescanned.borrow_mut();
+
+                for message in messages {
+                    match message {
+                        Ok(sample) => rescanned.push(sample),
+                        Err(e) => log::log!(log::Level::Error, "Error rescanning source: {e}"),
+                    }
+                }
+
+                drop(rescanned);
+                return Ok(model);
+            }
+
+            let mut samples = model.samples.borrow_mut();
+            let len_before = samples.len();
+
+            for message in messages {
+                match message {
+                    Ok(sample) => {
+                        samples.push(sample);
+                    }
+
+                    Err(e) => log::log!(log::Level::Error, "Error loading source: {e}"),
+                }
+            }
+
+            let added = samples.len() - len_before;
+
+            let imported = match model.sets_kit_imports.contains_key(&uuid) {
+                true => samples[len_before..].to_vec(),
+                false => Vec::new(),
+            };
+
+            drop(samples);
+
+            model
+                .source_sample_count_add(uuid, added)?
+                .add_imported_kit_samples(&uuid, &imported)
+        }
+
+        AppMessage::SourcesDedupToggled(dedup) => Ok(AppModel {
+            samples_dedup_overlapping: dedup,
+            ..model
+        }
+        .tap(AppModel::populate_samples_listmodel)),
+
+        AppMessage::SourceLoadingDisconnected(uuid) => {
+            let model = match model.sources_rescanning.contains_key(&uuid) {
+                true => model.finish_source_rescan(&uuid)?,
+                false => model.finish_kit_import(&uuid)?,
+            };
+
+            model.populate_samples_listmodel();
+
+            Ok(AppModel {
+                sources_loading: model.sources_loading.clone_and_remove(&uuid)?,
+                ..model
+            }
+            .watch_source(&uuid))
+        }
+
+        AppMessage::SourceFilesChanged(uuid) => Ok(AppModel {
+            sources_rescan_due: model
+                .sources_rescan_due
+                .clone_and_insert(uuid, Instant::now() + SOURCE_RESCAN_DELAY),
+            ..model
+        }),
+
+        AppMessage::SampleListSampleSelected(id, index) => {
+            let grouped = model.samples_list_grouped();
+            let item = model.viewvalues.samples_list(id).entry(index, grouped);
+
+            match item.as_ref().map(|x| &x.value) {
+                Some(sample) => {
+                    let stream = model
+                        .sources
+                        .get(
+                            sample
+                                .borrow()
+                                .source_uuid()
+                                .ok_or(anyhow!("Sample missing source uuid"))?,
+                        )
+                        .ok_or(anyhow!("Failed to get source for sample"))?
+                        .stream(&sample.borrow())
+                        .map_err(|e| {
+                            toast_error(format!("Could not play '{}': {e}", sample.borrow().name()))
+                        })?;
+
+                    play_sample_stream(&model, stream)?;
+
+                    Ok(remember_filter(
+                        AppModel {
+                            samplelist_selected_sample: Some(sample.borrow().clone()),
+                            samplelist_selected_samples: vec![sample.borrow().clone()],
+                            ..model.record_audition(&sample.borrow())
+                        },
+                        id,
+                    ))
+                }
+                // a source header in the grouped list
+                None if grouped => Ok(model),
+                None => Err(anyhow!("Could not obtain clicked sample (this is a bug)")),
+            }
+        }
+
+        AppMessage::SampleListMultipleSelected(id, indices) => Ok(AppModel {
+            samplelist_selected_sample: None,
+            samplelist_selected_samples: indices
+                .iter()
+                .filter_map(|index| {
+                    model
+                        .viewvalues
+                        .samples_list(id)
+                        .entry(*index, model.samples_list_grouped())
+                        .map(|entry| entry.value.borrow().clone())
+                })
+                .collect(),
+            ..model
+        }),
+
+        AppMessage::SamplesFilterChanged(id, text) => {
+            if text == model.viewvalues.samples_list(id).filter {
+                return Ok(model);
+            }
+
+            let list = model.viewvalues.samples_list(id).clone().with_filter(text);
+
+            Ok(AppModel {
+                viewvalues: model.viewvalues.with_samples_list(id, list),
+                ..model
+            }
+            .tap(|model| model.populate_samples_list(id)))
+        }
+
+        AppMessage::SamplesFilterActivated(id) => Ok(remember_filter(model, id)),
+
+        AppMessage::SamplesSearchesClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_searches_show_popover: true,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SamplesSearchesPopoverOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_searches_show_popover: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SamplesHistoryClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_history_show_popover: true,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SamplesHistoryPopoverOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_history_show_popover: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SamplesSaveSearchClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_save_search_show_dialog: !model.viewvalues.samples_list.filter.is_empty(),
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SavedSearchRemoved(name) => {
+            let config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .without_saved_search(&name);
+
+            Ok(model
+                .set_config(config)
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
+        AppMessage::SamplesSplitViewToggled(split) => Ok(AppModel {
+            samples_split_view: split,
+            ..model
+        }
+        .tap(|model| {
+            if model.samples_split_view {
+                model.populate_samples_list(SampleListId::Split)
+            }
+        })),
+
+        AppMessage::SamplesFavoritesOnlyToggled(favorites_only) => Ok(AppModel {
+            samples_favorites_only: favorites_only,
+            ..model
+        }
+        .tap(AppModel::populate_samples_listmodel)),
+
+        AppMessage::SampleFavoriteToggled(sample, favorite) => {
+            if model.is_favorite(&sample) == favorite {
+                return Ok(model);
+            }
+
+            Ok(model
+                .set_favorite(&sample, favorite)?
+                .tap(AppModel::populate_samples_listmodel))
+        }
+
+        // also sent when the sidebar follows the selection, including to no selection at all
+        AppMessage::SampleSidebarFavoriteToggled(favorite) => {
+            match model.samplelist_selected_sample.clone() {
+                Some(sample) if model.is_favorite(&sample) != favorite => Ok(model
+                    .set_favorite(&sample, favorite)?
+                    .tap(AppModel::populate_samples_listmodel)),
+
+                _ => Ok(model),
+            }
+        }
+
+        AppMessage::SampleCompareAssignClicked(slot) => {
+            let sample = model
+                .samplelist_selected_sample
+                .clone()
+                .ok_or(anyhow!("No selected sample"))?;
+
+            Ok(match slot {
+                CompareSlot::A => AppModel {
+                    samples_compare_a: Some(sample),
+                    samples_compare_playing: None,
+                    ..model
+                },
+                CompareSlot::B => AppModel {
+                    samples_compare_b: Some(sample),
+                    samples_compare_playing: None,
+                    ..model
+                },
+            })
+        }
+
+        AppMessage::SampleCompareSwitchClicked => {
+            let slot = match model.samples_compare_playing {
+                Some(CompareSlot::A) => CompareSlot::B,
+                _ => CompareSlot::A,
+            };
+
+            let sample = match slot {
+                CompareSlot::A => model.samples_compare_a.as_ref(),
+                CompareSlot::B => model.samples_compare_b.as_ref(),
+            }
+            .ok_or(anyhow!("Both samples to compare should be set"))?;
+
+            let stream = model
+                .sources
+                .get(
+                    sample
+                        .source_uuid()
+                        .ok_or(anyhow!("Sample missing source uuid"))?,
+                )
+                .ok_or(anyhow!("Failed to get source for sample"))?
+                .stream(sample)?;
+
+            // cut off the other sample regardless of the playback behavior, unless that would
+            // also silence the drum machine
+            if !model.drum_machine.is_playing() {
+                if let Some(audiothread_tx) = &model.audiothread_tx {
+                    audiothread_tx
+                        .send(audiothread::Message::DropAll)
+                        .map_err(audiothread_send_error)?;
+                }
+            }
+
+            play_sample_stream(&model, stream)?;
+
+            let sample = sample.clone();
+
+            Ok(AppModel {
+                samples_compare_playing: Some(slot),
+                ..model.record_audition(&sample)
+            })
+        }
+
+        AppMessage::SamplesPreviewPitchChanged(semitones) => Ok(AppModel {
+            samples_preview_semitones: semitones,
+            ..model
+        }),
+
+        AppMessage::SamplesGroupBySourceToggled(grouped) => {
+            let config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?;
+
+            if config.samples_list_grouped == grouped {
+                return Ok(model);
+            }
+
+            Ok(model
+                .set_config(AppConfig {
+                    samples_list_grouped: grouped,
+                    ..config
+                })
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3))
+                .tap(AppModel::populate_samples_listmodel))
+        }
+
+        AppMessage::SampleSidebarRenameClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_sidebar_rename_show_dialog: model.samplelist_selected_sample.is_some(),
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SampleSidebarAddToSetClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_sidebar_add_to_set_show_dialog: model.sets.is_empty(),
+                samples_sidebar_add_to_set_show_popover: !model.sets.is_empty(),
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SampleSidebarAddToSetPopoverOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_sidebar_add_to_set_show_popover: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SampleSidebarAddToSetChosen(uuid) => {
+            model::util::add_selected_samples_to_sampleset_by_uuid(model, &uuid)
+        }
+
+        AppMessage::SampleSidebarAddToNewSetClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_sidebar_add_to_set_show_dialog: true,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SampleSidebarAddToMostRecentlyUsedSetClicked => {
+            let mru_uuid = model
+                .sets_most_recently_used_uuid
+                .ok_or(anyhow!("No sample set recently added to"))?;
+
+            model::util::add_selected_samples_to_sampleset_by_uuid(model, &mru_uuid)
+        }
+
+        AppMessage::SourceEnabled(uuid) => {
+            let source = model
+                .sources
+                .get(&uuid)
+                .ok_or(anyhow!("Source not found"))?;
 
             let (tx, rx) = std::sync::mpsc::channel::<Result<Sample, libasampo::errors::Error>>();
 
@@ -495,70 +2184,289 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             .remove_source(&uuid)?
             .tap(AppModel::populate_samples_listmodel)),
 
+        AppMessage::SourceRescanClicked(uuid) => model.begin_source_rescan(&uuid),
+
         AppMessage::LoadFromSavefile(filename) => {
+            if let Some(held_by) = lockfile::held_elsewhere(&filename, &model.window_id) {
+                return Ok(with_savefile_lock_conflict(model, filename, held_by, false));
+            }
+
             log::log!(log::Level::Info, "Loading from {filename}");
 
-            match Savefile::load(&filename) {
-                Ok(loaded_app_model) => {
-                    let model = AppModel {
-                        viewvalues: ViewValues {
-                            sources_sample_count: loaded_app_model
-                                .sources
-                                .keys()
-                                .map(|k| (*k, 0))
-                                .collect(),
-                            ..model.viewvalues
+            if savefile::is_encrypted(&filename).unwrap_or(false) {
+                match model.savefile_password.clone() {
+                    Some(password) => begin_load(model, filename, Some(password), false),
+
+                    None => Ok(AppModel {
+                        viewflags: ViewFlags {
+                            savefile_load_password_show_dialog: true,
+                            ..model.viewflags
                         },
-                        sources: loaded_app_model.sources,
-                        sources_order: loaded_app_model.sources_order,
-                        sets: loaded_app_model.sets,
-                        sets_order: loaded_app_model.sets_order,
+                        savefile_pending: Some(filename),
                         ..model
-                    };
+                    }),
+                }
+            } else {
+                begin_load(model, filename, None, false)
+            }
+        }
 
-                    model.samples.borrow_mut().clear();
-                    model.populate_samples_listmodel();
+        AppMessage::RecoveryRestoreAccepted => {
+            let recovery_path = savefile::recovery_path();
+            log::log!(log::Level::Info, "Restoring from {recovery_path}");
 
-                    Ok(AppModel {
-                        sources_loading: model
-                            .sources
-                            .iter()
-                            .filter_map(|(uuid, source)| match source.is_enabled() {
-                                true => {
-                                    let (tx, rx) = std::sync::mpsc::channel::<
-                                        Result<Sample, libasampo::errors::Error>,
-                                    >();
-                                    std::thread::spawn(clone!(@strong source => move || {
-                                        source.list_async(tx);
-                                    }));
-
-                                    Some((*uuid, Rc::new(rx)))
-                                }
-
-                                false => None,
-                            })
-                            .collect(),
-                        ..model
-                    })
-                }
-                Err(e) => Err(anyhow::Error::new(ErrorWithEffect::AlertDialog {
-                    text: "Error loading savefile".to_string(),
-                    detail: e.to_string(),
-                })),
+            begin_load(model, recovery_path, None, true)
+        }
+
+        AppMessage::RecoveryRestoreDeclined => {
+            remove_recovery_file();
+            Ok(model)
+        }
+
+        AppMessage::SavefileLockedDialogOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                savefile_locked_show_dialog: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SavefileLockTakeoverAccepted => {
+            let conflict = model
+                .savefile_lock_conflict
+                .clone()
+                .ok_or(anyhow!("No savefile lock conflict pending"))?;
+
+            log::log!(
+                log::Level::Info,
+                "Taking over lock on {} from {}",
+                conflict.filename,
+                conflict.held_by
+            );
+
+            lockfile::acquire(&conflict.filename, &model.window_id)?;
+
+            let model = AppModel {
+                savefile_lock_conflict: None,
+                ..model
+            };
+
+            if conflict.saving {
+                update_model(model, AppMessage::SaveToSavefile(conflict.filename))
+            } else {
+                update_model(model, AppMessage::LoadFromSavefile(conflict.filename))
             }
         }
 
+        AppMessage::SavefileLockTakeoverDeclined => Ok(AppModel {
+            savefile_lock_conflict: None,
+            ..model
+        }),
+
         AppMessage::SaveToSavefile(filename) => {
-            log::log!(log::Level::Info, "Saving to {filename}");
+            if let Some(held_by) = lockfile::held_elsewhere(&filename, &model.window_id) {
+                return Ok(with_savefile_lock_conflict(model, filename, held_by, true));
+            }
 
-            match Savefile::save(&model, &filename) {
-                Ok(_) => Ok(AppModel {
-                    savefile: Some(filename),
+            let review = model
+                .config
+                .as_ref()
+                .is_some_and(|config| config.review_changes_before_saving)
+                && model.savefile.as_ref() == Some(&filename)
+                && Path::new(&filename).exists()
+                && model.modified();
+
+            if review {
+                return Ok(AppModel {
+                    viewflags: ViewFlags {
+                        savefile_review_show_dialog: true,
+                        ..model.viewflags
+                    },
+                    savefile_review: Some(SavefileReview {
+                        filename,
+                        changes: model.snapshot().changes_since(&model.savefile_snapshot),
+                    }),
                     ..model
-                }),
+                });
+            }
+
+            save_to(model, filename)
+        }
+
+        AppMessage::SavefileReviewDialogOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                savefile_review_show_dialog: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SavefileReviewAccepted => {
+            let review = model
+                .savefile_review
+                .clone()
+                .ok_or(anyhow!("No save waiting for review"))?;
+
+            save_to(
+                AppModel {
+                    savefile_review: None,
+                    ..model
+                },
+                review.filename,
+            )
+        }
+
+        AppMessage::SavefileReviewDeclined => Ok(AppModel {
+            savefile_review: None,
+            ..model
+        }),
+
+        AppMessage::ExportSettings(filename) => {
+            let config = model
+                .config
+                .as_ref()
+                .ok_or(anyhow!("There should be an active config"))?;
+
+            ConfigFile::save(
+                &AppConfig {
+                    recent_savefiles: Vec::new(),
+                    ..config.clone()
+                },
+                &filename,
+            )
+            .map_err(|e| {
+                anyhow::Error::new(ErrorWithEffect::AlertDialog {
+                    text: "Error exporting settings".to_string(),
+                    detail: e.to_string(),
+                })
+            })?;
+
+            Ok(model)
+        }
+
+        AppMessage::ImportSettings(filename) => {
+            let imported = ConfigFile::load(&filename)
+                .and_then(|config| config.validate().map(|_| config))
+                .map_err(|e| {
+                    anyhow::Error::new(ErrorWithEffect::AlertDialog {
+                        text: "Error importing settings".to_string(),
+                        detail: e.to_string(),
+                    })
+                })?;
+
+            Ok(AppModel {
+                viewflags: ViewFlags {
+                    settings_import_show_dialog: true,
+                    ..model.viewflags
+                },
+                settings_import: Some(imported),
+                ..model
+            })
+        }
+
+        AppMessage::SettingsImportDialogOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                settings_import_show_dialog: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SettingsImportAccepted(groups) => {
+            let imported = model
+                .settings_import
+                .clone()
+                .ok_or(anyhow!("No settings waiting to be imported"))?;
+
+            let new_config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?
+                .with_imported_settings(imported, &groups);
+
+            Ok(AppModel {
+                settings_import: None,
+                ..model
+            }
+            .set_latency_approx_label_by_config(&new_config)
+            .set_config(new_config)
+            .set_config_save_timeout(Instant::now()))
+        }
+
+        AppMessage::SettingsImportDeclined => Ok(AppModel {
+            settings_import: None,
+            ..model
+        }),
+
+        AppMessage::ImportKit(filename) => {
+            let kit = kitimport::load(&filename).map_err(|e| {
+                anyhow::Error::new(ErrorWithEffect::AlertDialog {
+                    text: "Error importing kit".to_string(),
+                    detail: e.to_string(),
+                })
+            })?;
+
+            let sample_dir = kit
+                .sample_dir()
+                .ok_or(anyhow!("Could not find the sample folder of the kit"))?;
+
+            let mut set = BaseSampleSet::new(kit.name.clone());
+            set.set_labelling(Some(SampleSetLabelling::DrumkitLabelling(
+                DrumkitLabelling::new(),
+            )));
+
+            let set_uuid = *set.uuid();
+            let toast = format!("Importing '{}' ({} samples)", kit.name, kit.samples.len());
+
+            let model = model
+                .add_sampleset(SampleSet::BaseSampleSet(set))
+                .add_file_system_source(kit.name.clone(), sample_dir, kit.extensions())?;
+
+            let source_uuid = *model
+                .sources_order
+                .last()
+                .ok_or(anyhow!("Failed to add a source for the kit"))?;
+
+            Ok(AppModel {
+                sets_kit_imports: model.sets_kit_imports.clone_and_insert(
+                    source_uuid,
+                    KitImport {
+                        set_uuid,
+                        samples: kit.samples,
+                        labels: kit.labels,
+                    },
+                ),
+                ..model
+            }
+            .show_toast(toast)
+            .tap(AppModel::populate_samples_listmodel))
+        }
+
+        // the autosaving window closed, so autosave this workspace from the next tick on
+        AppMessage::AutosaveHandedOver => Ok(AppModel {
+            autosave: true,
+            autosave_deadline: None,
+            ..model
+        }),
+
+        // the window sharing the config has saved it, and any changed audio output settings
+        // are applied on the next tick
+        AppMessage::ConfigShared(config) => {
+            let drum_machine = if velocity_curve(&model) != config.velocity_curve {
+                model
+                    .drum_machine
+                    .clone()
+                    .with_velocity_curve(&config.velocity_curve)?
+            } else {
+                model.drum_machine.clone()
+            };
 
-                Err(e) => Err(e),
+            Ok(AppModel {
+                drum_machine,
+                ..model
             }
+            .set_latency_approx_label_by_config(&config)
+            .set_config(config))
         }
 
         AppMessage::DialogError(error) => {
@@ -583,35 +2491,200 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             ..model
         }),
 
-        AppMessage::InputDialogOpened(context) => match context {
-            InputDialogContext::AddToSampleset => Ok(AppModel {
+        AppMessage::InputDialogOpened(context) => match context {
+            InputDialogContext::AddToSampleset => Ok(AppModel {
+                viewflags: ViewFlags {
+                    samples_sidebar_add_to_set_show_dialog: false,
+                    ..model.viewflags
+                },
+                ..model
+            }),
+
+            InputDialogContext::RenameSample => Ok(AppModel {
+                viewflags: ViewFlags {
+                    samples_sidebar_rename_show_dialog: false,
+                    ..model.viewflags
+                },
+                ..model
+            }),
+
+            InputDialogContext::SaveSearch => Ok(AppModel {
+                viewflags: ViewFlags {
+                    samples_save_search_show_dialog: false,
+                    ..model.viewflags
+                },
+                ..model
+            }),
+
+            InputDialogContext::CustomPadLabels => Ok(AppModel {
+                viewflags: ViewFlags {
+                    settings_pad_labels_show_dialog: false,
+                    ..model.viewflags
+                },
+                ..model
+            }),
+
+            InputDialogContext::SaveAudioProfile => Ok(AppModel {
+                viewflags: ViewFlags {
+                    settings_audio_profile_save_show_dialog: false,
+                    ..model.viewflags
+                },
+                ..model
+            }),
+
+            InputDialogContext::CreateSampleSet => Ok(AppModel {
+                viewflags: ViewFlags {
+                    sets_add_set_show_dialog: false,
+                    ..model.viewflags
+                },
+                ..model
+            }),
+
+            InputDialogContext::RenameSampleSet => Ok(AppModel {
                 viewflags: ViewFlags {
-                    samples_sidebar_add_to_set_show_dialog: false,
+                    sets_rename_set_show_dialog: false,
                     ..model.viewflags
                 },
                 ..model
             }),
 
-            InputDialogContext::CreateSampleSet => Ok(AppModel {
+            InputDialogContext::SavefilePasswordForSave => Ok(AppModel {
                 viewflags: ViewFlags {
-                    sets_add_set_show_dialog: false,
+                    savefile_save_password_show_dialog: false,
+                    ..model.viewflags
+                },
+                ..model
+            }),
+
+            InputDialogContext::SavefilePasswordForLoad => Ok(AppModel {
+                viewflags: ViewFlags {
+                    savefile_load_password_show_dialog: false,
                     ..model.viewflags
                 },
                 ..model
             }),
         },
 
-        AppMessage::InputDialogCanceled(_context) => Ok(model),
+        AppMessage::InputDialogCanceled(context) => match context {
+            InputDialogContext::SavefilePasswordForSave
+            | InputDialogContext::SavefilePasswordForLoad => Ok(AppModel {
+                savefile_pending: None,
+                ..model
+            }),
+
+            _ => Ok(model),
+        },
 
-        AppMessage::InputDialogSubmitted(context, text) => match context {
+        AppMessage::InputDialogSubmitted(context, DialogText { text, .. }) => match context {
             InputDialogContext::AddToSampleset => {
                 let (model, set_uuid) = model::util::get_or_create_sampleset(model, text)?;
-                model::util::add_selected_sample_to_sampleset_by_uuid(model, &set_uuid)
+                model::util::add_selected_samples_to_sampleset_by_uuid(model, &set_uuid)
+            }
+
+            InputDialogContext::RenameSample => {
+                let sample = model
+                    .samplelist_selected_sample
+                    .clone()
+                    .ok_or(anyhow!("No selected sample"))?;
+
+                Ok(model
+                    .rename_sample(&sample, &text)
+                    .tap(AppModel::populate_samples_listmodel))
+            }
+
+            InputDialogContext::SaveSearch => {
+                let config = model
+                    .config
+                    .clone()
+                    .ok_or(anyhow!("There should be an active config"))?
+                    .with_saved_search(&text, &model.viewvalues.samples_list.filter);
+
+                Ok(model
+                    .set_config(config)
+                    .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+            }
+
+            InputDialogContext::SaveAudioProfile => {
+                let name = text.trim();
+
+                if name.is_empty() {
+                    return Err(anyhow!("Audio profiles need a name"));
+                }
+
+                let config = model
+                    .config
+                    .clone()
+                    .ok_or(anyhow!("There should be an active config"))?
+                    .with_audio_profile_saved(name);
+
+                Ok(model
+                    .set_config(config)
+                    .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+            }
+
+            InputDialogContext::CustomPadLabels => {
+                let config = model
+                    .config
+                    .clone()
+                    .ok_or(anyhow!("There should be an active config"))?
+                    .with_custom_pad_labels(
+                        text.split(',')
+                            .map(|label| label.trim().to_string())
+                            .collect(),
+                    );
+
+                Ok(model
+                    .set_config(config)
+                    .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
             }
 
             InputDialogContext::CreateSampleSet => {
                 Ok(model.add_sampleset(SampleSet::BaseSampleSet(BaseSampleSet::new(text))))
             }
+
+            InputDialogContext::RenameSampleSet => {
+                let set_uuid = model
+                    .sets_selected_set
+                    .ok_or(anyhow!("No sample set selected"))?;
+
+                model.rename_sampleset(&set_uuid, &text)
+            }
+
+            InputDialogContext::SavefilePasswordForSave => {
+                let filename = model
+                    .savefile_pending
+                    .clone()
+                    .ok_or(anyhow!("No savefile pending"))?;
+
+                log::log!(log::Level::Info, "Saving encrypted to {filename}");
+
+                begin_save(
+                    AppModel {
+                        savefile_pending: None,
+                        savefile_password: remembered_savefile_password(&model, text.clone()),
+                        ..model
+                    },
+                    filename,
+                    Some(text),
+                )
+            }
+
+            InputDialogContext::SavefilePasswordForLoad => {
+                let filename = model
+                    .savefile_pending
+                    .clone()
+                    .ok_or(anyhow!("No savefile pending"))?;
+
+                begin_load(
+                    AppModel {
+                        savefile_pending: None,
+                        ..model
+                    },
+                    filename,
+                    Some(text),
+                    false,
+                )
+            }
         },
 
         // TODO: replace with function pointer, just like "ok" and "cancel" for input dialog?
@@ -645,7 +2718,8 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             })
         }
 
-        AppMessage::SampleSetSampleSelected(sample) => {
+        AppMessage::SampleSetSampleSelected(sample)
+        | AppMessage::SampleSetMemberPlayClicked(sample) => {
             let stream = model
                 .sources
                 .get(
@@ -656,16 +2730,41 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
                 .ok_or(anyhow!("Failed to get source for sample"))?
                 .stream(&sample)?;
 
-            model
-                .audiothread_tx
-                .as_ref()
-                .unwrap()
-                .send(audiothread::Message::PlaySymphoniaSource(
-                    audiothread::SymphoniaSource::from_buf_reader(BufReader::new(stream))?,
-                ))
-                .map_err(|_| anyhow!("Send error on audio thread control channel"))?;
+            play_sample_stream(&model, stream)?;
 
-            Ok(model)
+            Ok(model.record_audition(&sample))
+        }
+
+        AppMessage::SampleSetDuplicateClicked(uuid) => model.duplicate_sampleset(&uuid),
+
+        AppMessage::SampleSetRenameClicked(uuid) => Ok(AppModel {
+            viewflags: ViewFlags {
+                sets_rename_set_show_dialog: true,
+                ..model.viewflags
+            },
+            sets_selected_set: Some(uuid),
+            ..model
+        }),
+
+        AppMessage::SampleSetExportClicked(uuid) => {
+            let set = model
+                .sets
+                .get(&uuid)
+                .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+            if set.len() == 0 {
+                return Err(anyhow!("Cannot export an empty sample set"));
+            }
+
+            Ok(AppModel {
+                viewflags: ViewFlags {
+                    sets_export_enabled: true,
+                    sets_export_show_dialog: true,
+                    ..model.viewflags
+                },
+                sets_selected_set: Some(uuid),
+                ..model
+            })
         }
 
         AppMessage::SampleSetLabellingKindChanged(kind) => {
@@ -700,6 +2799,110 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             ..model
         }),
 
+        AppMessage::SampleSetCoverBrowseClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                sets_cover_begin_browse: true,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SampleSetCoverBrowseOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                sets_cover_begin_browse: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SampleSetCoverBrowseSubmitted(filename) => {
+            let uuid = model
+                .sets_selected_set
+                .ok_or(anyhow!("No sample set selected"))?;
+
+            Ok(AppModel {
+                sets_cover_images: model.sets_cover_images.clone_and_insert(uuid, filename),
+                ..model
+            })
+        }
+
+        AppMessage::SampleSetCoverBrowseError(_e) => Ok(model),
+
+        AppMessage::SampleSetCoverClearClicked => {
+            let uuid = model
+                .sets_selected_set
+                .ok_or(anyhow!("No sample set selected"))?;
+
+            Ok(AppModel {
+                sets_cover_images: model.sets_cover_images.clone_and_remove(&uuid)?,
+                ..model
+            })
+        }
+
+        AppMessage::SampleSetMemberMoved(index, offset) => {
+            let uuid = model
+                .sets_selected_set
+                .ok_or(anyhow!("No sample set selected"))?;
+
+            model.move_set_member(&uuid, index, offset)
+        }
+
+        AppMessage::SampleSetMemberExportToggled(sample, included) => {
+            let uuid = model
+                .sets_selected_set
+                .ok_or(anyhow!("No sample set selected"))?;
+
+            Ok(model.set_member_excluded_from_export(&uuid, &sample, !included))
+        }
+
+        // revealing selects the sample in the list, which plays it
+        AppMessage::SampleSetMemberFindClicked(sample)
+        | AppMessage::SamplesHistoryEntryClicked(sample) => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_reveal_sample: true,
+                ..model.viewflags
+            },
+            viewvalues: ViewValues {
+                samples_list: model
+                    .viewvalues
+                    .samples_list
+                    .clone()
+                    .with_filter(sample.uri().as_str()),
+                samples_reveal: Some(sample),
+                ..model.viewvalues
+            },
+            ..model
+        }
+        .tap(AppModel::populate_samples_listmodel)),
+
+        AppMessage::SampleRevealed => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_reveal_sample: false,
+                ..model.viewflags
+            },
+            viewvalues: ViewValues {
+                samples_reveal: None,
+                ..model.viewvalues
+            },
+            ..model
+        }),
+
+        AppMessage::SampleOpenFolderClicked => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_open_folder: model.samplelist_selected_sample.is_some(),
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SampleFolderOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_open_folder: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
         AppMessage::ExportDialogOpened(dialogview) => Ok(AppModel {
             viewflags: ViewFlags {
                 view_sensitive: false,
@@ -727,7 +2930,11 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
 
         AppMessage::ExportTargetDirectoryChanged(text) => Ok(AppModel {
             viewflags: ViewFlags {
-                sets_export_fields_valid: !text.is_empty(),
+                sets_export_fields_valid: !text.is_empty()
+                    && model
+                        .viewvalues
+                        .sets_export_filename_template_error
+                        .is_none(),
                 ..model.viewflags
             },
             viewvalues: ViewValues {
@@ -737,6 +2944,27 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             ..model
         }),
 
+        AppMessage::ExportFilenameTemplateChanged(text) => {
+            let error = export_filename_template(&text).err().map(|e| e.to_string());
+
+            Ok(AppModel {
+                viewflags: ViewFlags {
+                    sets_export_fields_valid: !model
+                        .viewvalues
+                        .sets_export_target_dir_entry
+                        .is_empty()
+                        && error.is_none(),
+                    ..model.viewflags
+                },
+                viewvalues: ViewValues {
+                    sets_export_filename_template_entry: text,
+                    sets_export_filename_template_error: error,
+                    ..model.viewvalues
+                },
+                ..model
+            })
+        }
+
         AppMessage::ExportTargetDirectoryBrowseClicked => Ok(AppModel {
             viewflags: ViewFlags {
                 sets_export_begin_browse: true,
@@ -753,94 +2981,336 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             ..model
         }),
 
-        AppMessage::ExportTargetDirectoryBrowseError(_e) => Ok(model),
+        AppMessage::ExportTargetDirectoryBrowseError(_e) => Ok(model),
+
+        AppMessage::PerformExportClicked => {
+            let target_dir = model.viewvalues.sets_export_target_dir_entry.clone();
+            let kind = model.viewvalues.sets_export_kind.clone();
+            let template =
+                export_filename_template(&model.viewvalues.sets_export_filename_template_entry)?;
+
+            perform_export(model, target_dir, kind, template)
+        }
+
+        AppMessage::QuickExportRequested => {
+            if model.sets_export_state == Some(ExportState::Exporting) {
+                return Err(anyhow!("An export is already in progress"));
+            }
+
+            if model.sets_selected_set.is_none() {
+                return Err(anyhow!("No sample set selected"));
+            }
+
+            match model.sets_export_last_settings.clone() {
+                Some((target_dir, kind, template)) => {
+                    perform_export(model, target_dir, kind, template)
+                }
+                None => Ok(AppModel {
+                    viewflags: ViewFlags {
+                        sets_export_show_dialog: true,
+                        ..model.viewflags
+                    },
+                    ..model
+                }),
+            }
+        }
+
+        AppMessage::PlainCopyExportSelected => Ok(AppModel {
+            viewvalues: ViewValues {
+                sets_export_kind: Some(model::ExportKind::PlainCopy),
+                ..model.viewvalues
+            },
+            ..model
+        }),
+
+        AppMessage::ConversionExportSelected => Ok(AppModel {
+            viewvalues: ViewValues {
+                sets_export_kind: Some(model::ExportKind::Conversion),
+                ..model.viewvalues
+            },
+            ..model
+        }),
+
+        AppMessage::SfzExportSelected => Ok(AppModel {
+            viewvalues: ViewValues {
+                sets_export_kind: Some(model::ExportKind::SfzInstrument),
+                ..model.viewvalues
+            },
+            ..model
+        }),
+
+        AppMessage::ExportJobMessage(message) => match message {
+            ExportJobMessage::ItemsCompleted(n) => Ok(AppModel {
+                sets_export_progress: model.sets_export_progress.map(|(_, m)| (n, m)),
+                ..model
+            }),
+            ExportJobMessage::Error(e) => {
+                log::log!(log::Level::Error, "Export error: {e}");
+
+                Ok(AppModel {
+                    sets_export_errors: model
+                        .sets_export_errors
+                        .iter()
+                        .cloned()
+                        .chain(std::iter::once(e.to_string()))
+                        .collect(),
+                    ..model
+                })
+            }
+            ExportJobMessage::Finished => {
+                let model = AppModel {
+                    sets_export_state: Some(ExportState::Finished),
+                    sets_export_progress: None,
+                    export_job_rx: None,
+                    ..model
+                };
+
+                if model.sets_export_errors.is_empty() {
+                    Ok(model.show_toast("Export finished"))
+                } else {
+                    Ok(AppModel {
+                        viewflags: ViewFlags {
+                            sets_export_errors_show_dialog: true,
+                            ..model.viewflags
+                        },
+                        ..model
+                    })
+                }
+            }
+        },
+
+        AppMessage::ExportErrorsDialogOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                sets_export_errors_show_dialog: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::SaveJobMessage(message) => match message {
+            SaveJobMessage::Encrypting => Ok(AppModel {
+                savefile_saving: model.savefile_saving.clone().map(|saving| SaveInProgress {
+                    stage: "Encrypting",
+                    ..saving
+                }),
+                ..model
+            }),
+
+            SaveJobMessage::Writing => Ok(AppModel {
+                savefile_saving: model.savefile_saving.clone().map(|saving| SaveInProgress {
+                    stage: "Writing",
+                    ..saving
+                }),
+                ..model
+            }),
+
+            SaveJobMessage::Error(e) => Err(anyhow::Error::new(ErrorWithEffect::AlertDialog {
+                text: "Error saving workspace".to_string(),
+                detail: e,
+            })),
+
+            SaveJobMessage::Finished => {
+                let saving = model
+                    .savefile_saving
+                    .clone()
+                    .ok_or(anyhow!("No save in progress"))?;
+
+                if model.autosave {
+                    remove_recovery_file();
+                }
+
+                Ok(with_active_savefile(
+                    AppModel {
+                        savefile_saving: None,
+                        save_job_rx: None,
+                        ..model
+                    },
+                    saving.filename,
+                )
+                .mark_saved_as(saving.snapshot))
+            }
+        },
+
+        AppMessage::LoadJobMessage(message) => {
+            match message {
+                LoadJobMessage::Decrypting => Ok(AppModel {
+                    savefile_loading: model.savefile_loading.clone().map(|loading| {
+                        LoadInProgress {
+                            stage: "Decrypting",
+                            ..loading
+                        }
+                    }),
+                    ..model
+                }),
+
+                LoadJobMessage::Building => Ok(AppModel {
+                    savefile_loading: model.savefile_loading.clone().map(|loading| {
+                        LoadInProgress {
+                            stage: "Reading",
+                            ..loading
+                        }
+                    }),
+                    ..model
+                }),
 
-        AppMessage::PerformExportClicked => {
-            use libasampo::samplesets::export::{RateConversionQuality, WavSampleFormat, WavSpec};
+                LoadJobMessage::Error(e) => {
+                    let loading = model
+                        .savefile_loading
+                        .clone()
+                        .ok_or(anyhow!("No savefile being loaded"))?;
 
-            let sampleset = model
-                .sets
-                .get(
-                    &model
-                        .sets_selected_set
-                        .ok_or(anyhow!("No sample set selected"))?,
-                )
-                .ok_or(anyhow!("Broken state, sample set not found"))?
-                .clone();
+                    if loading.password.is_none() || e.is::<NewerFormatError>() {
+                        return Err(savefile_load_error(e));
+                    }
 
-            let num_samples = sampleset.len();
+                    // most likely a wrong password, so ask for it again
+                    Ok(AppModel {
+                        viewflags: ViewFlags {
+                            view_sensitive: true,
+                            savefile_load_password_show_dialog: true,
+                            ..model.viewflags
+                        },
+                        savefile_pending: Some(loading.filename),
+                        savefile_loading: None,
+                        load_job_rx: None,
+                        ..model
+                    }
+                    .show_toast(format!("Could not open savefile: {e}")))
+                }
 
-            let (tx, rx) = std::sync::mpsc::channel::<ExportJobMessage>();
+                LoadJobMessage::Finished(workspace) => {
+                    let loading = model
+                        .savefile_loading
+                        .clone()
+                        .ok_or(anyhow!("No savefile being loaded"))?;
 
-            std::thread::spawn(clone!(@strong model => move || {
-                let job = ExportJob::new(
-                    model
-                        .viewvalues
-                        .sets_export_target_dir_entry
-                        .clone(),
-                    match model.viewvalues.sets_export_kind {
-                        None | Some(model::ExportKind::PlainCopy) => None,
-                        Some(model::ExportKind::Conversion) => Some(Conversion::Wav(
-                            WavSpec {
-                                channels: 2,
-                                sample_rate: 44100,
-                                bits_per_sample: 16,
-                                sample_format: WavSampleFormat::Int,
+                    let savefile_password = match loading.password {
+                        Some(password) => remembered_savefile_password(&model, password),
+                        None => model.savefile_password.clone(),
+                    };
+
+                    let model = load_savefile_into_model(
+                        AppModel {
+                            viewflags: ViewFlags {
+                                view_sensitive: true,
+                                ..model.viewflags
                             },
-                            Some(RateConversionQuality::High),
-                        )),
-                    });
+                            savefile_password,
+                            savefile_loading: None,
+                            load_job_rx: None,
+                            ..model
+                        },
+                        workspace.into_appmodel(),
+                    )?;
+
+                    if loading.recovery {
+                        remove_recovery_file();
+                        Ok(model)
+                    } else {
+                        Ok(with_active_savefile(model, loading.filename))
+                    }
+                }
+            }
+        }
 
-                job.perform(&sampleset, &model.sources, Some(tx));
-            }));
+        // also reached after a failed load, which leaves the state of the load in place
+        AppMessage::LoadJobDisconnected => match model.load_job_rx {
+            Some(_) => Ok(AppModel {
+                viewflags: ViewFlags {
+                    view_sensitive: true,
+                    ..model.viewflags
+                },
+                savefile_loading: None,
+                load_job_rx: None,
+                ..model
+            }),
+
+            None => Ok(model),
+        },
+
+        // also reached after a failed save, which leaves the state of the save in place
+        AppMessage::SaveJobDisconnected => Ok(AppModel {
+            savefile_saving: None,
+            save_job_rx: None,
+            ..model
+        }),
+
+        AppMessage::ExportJobDisconnected => {
+            log::log!(log::Level::Debug, "Export job disconnected");
 
             Ok(AppModel {
-                sets_export_state: Some(model::ExportState::Exporting),
-                sets_export_progress: Some((0, num_samples)),
-                export_job_rx: Some(Rc::new(rx)),
+                export_job_rx: None,
                 ..model
             })
         }
 
-        AppMessage::PlainCopyExportSelected => Ok(AppModel {
-            viewvalues: ViewValues {
-                sets_export_kind: Some(model::ExportKind::PlainCopy),
-                ..model.viewvalues
-            },
-            ..model
-        }),
+        AppMessage::FindDuplicatesRequested => {
+            if model.duplicates_job_rx.is_some() {
+                return Ok(model);
+            }
 
-        AppMessage::ConversionExportSelected => Ok(AppModel {
-            viewvalues: ViewValues {
-                sets_export_kind: Some(model::ExportKind::Conversion),
-                ..model.viewvalues
-            },
-            ..model
-        }),
+            let sources = model.sources.clone();
+            let samples = model.samples.borrow().clone();
+            let (tx, rx) = mpsc::channel::<Vec<Vec<Sample>>>();
 
-        AppMessage::ExportJobMessage(message) => match message {
-            ExportJobMessage::ItemsCompleted(n) => Ok(AppModel {
-                sets_export_progress: model.sets_export_progress.map(|(_, m)| (n, m)),
+            log::log!(
+                log::Level::Info,
+                "Looking for duplicates among {} samples",
+                samples.len()
+            );
+
+            std::thread::spawn(move || {
+                let _ = tx.send(model::util::find_duplicate_samples(&sources, &samples));
+            });
+
+            Ok(AppModel {
+                duplicates_job_rx: Some(Rc::new(rx)),
                 ..model
-            }),
-            ExportJobMessage::Error(e) => Err(e.into()),
-            ExportJobMessage::Finished => Ok(AppModel {
-                sets_export_state: Some(ExportState::Finished),
-                sets_export_progress: None,
-                export_job_rx: None,
+            })
+        }
+
+        AppMessage::DuplicatesJobFinished(groups) => {
+            log::log!(
+                log::Level::Info,
+                "Found {} groups of duplicates",
+                groups.len()
+            );
+
+            Ok(AppModel {
+                viewflags: ViewFlags {
+                    samples_duplicates_show_dialog: true,
+                    ..model.viewflags
+                },
+                samples_duplicates: groups,
+                duplicates_job_rx: None,
                 ..model
-            }),
-        },
+            }
+            .tap(AppModel::populate_samples_listmodel))
+        }
 
-        AppMessage::ExportJobDisconnected => {
-            log::log!(log::Level::Debug, "Export job disconnected");
+        AppMessage::DuplicatesJobDisconnected => {
+            log::log!(log::Level::Debug, "Duplicates job disconnected");
 
             Ok(AppModel {
-                export_job_rx: None,
+                duplicates_job_rx: None,
                 ..model
             })
         }
 
+        AppMessage::DuplicatesDialogOpened => Ok(AppModel {
+            viewflags: ViewFlags {
+                samples_duplicates_show_dialog: false,
+                ..model.viewflags
+            },
+            ..model
+        }),
+
+        AppMessage::DuplicatesHideToggled(hide) => Ok(AppModel {
+            samples_hide_duplicates: hide,
+            ..model
+        }
+        .tap(AppModel::populate_samples_listmodel)),
+
         AppMessage::StopAllSoundButtonClicked => {
             if let Some(dks_render_thread_tx) = &model.drum_machine.render_thread_tx {
                 match dks_render_thread_tx.send(drumkit_render_thread::Message::Shutdown) {
@@ -873,13 +3343,70 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             }
         }
 
+        AppMessage::PageChanged(page) => {
+            let stop_preview = model
+                .config
+                .as_ref()
+                .is_some_and(|config| config.stop_preview_on_page_change);
+
+            // as in play_sample_stream, DropAll would also silence the drum machine
+            if stop_preview && page != "samples" && !model.drum_machine.is_playing() {
+                if let Some(audiothread_tx) = &model.audiothread_tx {
+                    match audiothread_tx.send(audiothread::Message::DropAll) {
+                        Ok(_) => (),
+                        Err(e) => log::log!(log::Level::Error, "Stop previews error: {e}"),
+                    }
+                }
+            }
+
+            Ok(model)
+        }
+
+        AppMessage::PreviewMuteToggled(muted) => {
+            // as in play_sample_stream, leave the drum machine playing
+            if muted && !model.drum_machine.is_playing() {
+                if let Some(audiothread_tx) = &model.audiothread_tx {
+                    match audiothread_tx.send(audiothread::Message::DropAll) {
+                        Ok(_) => (),
+                        Err(e) => log::log!(log::Level::Error, "Mute previews error: {e}"),
+                    }
+                }
+            }
+
+            Ok(AppModel {
+                samples_preview_muted: muted,
+                ..model
+            })
+        }
+
+        AppMessage::PreviewVolumeChanged(volume) => {
+            let config = model
+                .config
+                .clone()
+                .ok_or(anyhow!("There should be an active config"))?;
+
+            if config.preview_volume == volume {
+                return Ok(model);
+            }
+
+            Ok(model
+                .set_config(config.with_preview_volume(volume))
+                .set_config_save_timeout(Instant::now() + Duration::from_secs(3)))
+        }
+
         AppMessage::DrumMachineTempoChanged(tempo) => {
             if let Some(dks_render_thread_tx) = &model.drum_machine.render_thread_tx {
                 let _ = dks_render_thread_tx
                     .send(drumkit_render_thread::Message::SetTempo(tempo.try_into()?));
             }
 
-            Ok(model)
+            Ok(AppModel {
+                drum_machine: DrumMachineModel {
+                    tempo,
+                    ..model.drum_machine
+                },
+                ..model
+            })
         }
 
         AppMessage::DrumMachineSwingChanged(swing) => {
@@ -889,11 +3416,47 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
                 ));
             }
 
-            Ok(model)
+            Ok(AppModel {
+                drum_machine: DrumMachineModel {
+                    swing,
+                    ..model.drum_machine
+                },
+                ..model
+            })
+        }
+
+        AppMessage::DrumMachineVolumeChanged(volume) => {
+            let curve = velocity_curve(&model);
+
+            Ok(AppModel {
+                drum_machine: model.drum_machine.with_volume(volume, &curve)?,
+                ..model
+            })
         }
 
-        AppMessage::DrumMachinePlayClicked => Ok(model),
-        AppMessage::DrumMachineStopClicked => Ok(model),
+        AppMessage::DrumMachineMetronomeToggled(metronome) => Ok(AppModel {
+            drum_machine: DrumMachineModel {
+                metronome,
+                ..model.drum_machine
+            },
+            ..model
+        }),
+
+        AppMessage::DrumMachinePlayClicked => Ok(AppModel {
+            drum_machine: DrumMachineModel {
+                playing: true,
+                ..model.drum_machine
+            },
+            ..model
+        }),
+
+        AppMessage::DrumMachineStopClicked => Ok(AppModel {
+            drum_machine: DrumMachineModel {
+                playing: false,
+                ..model.drum_machine
+            },
+            ..model
+        }),
         AppMessage::DrumMachineBackClicked => Ok(model),
         AppMessage::DrumMachineSaveSequenceClicked => Ok(model),
         AppMessage::DrumMachineSaveSequenceAsClicked => Ok(model),
@@ -908,19 +3471,18 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
         }),
         AppMessage::DrumMachinePartClicked(_n) => Ok(model),
         AppMessage::DrumMachineStepClicked(n) => {
-            let amp = 0.5f32;
+            let pad = model.drum_machine.activated_pad;
+            let label = DRUM_MACHINE_VIEW_LABELS[pad];
             let mut new_sequence = model.drum_machine.sequence.clone();
-            let label = DRUM_MACHINE_VIEW_LABELS[model.drum_machine.activated_pad];
+            let mut step_velocities = model.drum_machine.step_velocities.clone();
 
             if new_sequence
                 .labels_at_step(n)
                 .ok_or(anyhow!("Drum machine sequence has no step {n}"))?
                 .contains(&label)
             {
-                new_sequence.unset_step_trigger(
-                    n,
-                    DRUM_MACHINE_VIEW_LABELS[model.drum_machine.activated_pad],
-                );
+                new_sequence.unset_step_trigger(n, label);
+                step_velocities.remove(&(n, pad));
 
                 if let Some(render_thread_tx) = &model.drum_machine.render_thread_tx {
                     render_thread_tx
@@ -937,14 +3499,19 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
                         })?;
                 }
             } else {
+                let amp = model
+                    .drum_machine
+                    .trigger_amp(DEFAULT_VELOCITY_LEVEL, &velocity_curve(&model));
+
                 new_sequence.set_step_trigger(n, label, amp);
+                step_velocities.insert((n, pad), DEFAULT_VELOCITY_LEVEL);
 
                 if let Some(render_thread_tx) = &model.drum_machine.render_thread_tx {
                     render_thread_tx
                         .send(drumkit_render_thread::Message::EditSequenceSetStepTrigger {
                             step: n,
                             label,
-                            amp,
+                            amp: model.drum_machine.output_amp(amp),
                         })
                         .map_err(|e| {
                             anyhow!(
@@ -957,19 +3524,119 @@ fn update_model(model: AppModel, message: AppMessage) -> Result<AppModel, anyhow
             Ok(AppModel {
                 drum_machine: DrumMachineModel {
                     sequence: new_sequence,
+                    step_velocities,
                     ..model.drum_machine
                 },
                 ..model
             })
         }
 
-        AppMessage::DrumMachinePlaybackEvent(event) => Ok(AppModel {
-            drum_machine: DrumMachineModel {
-                event_latest: Some(event),
-                ..model.drum_machine
-            },
-            ..model
-        }),
+        AppMessage::DrumMachineStepVelocityCycled(n) => {
+            let pad = model.drum_machine.activated_pad;
+            let label = DRUM_MACHINE_VIEW_LABELS[pad];
+
+            if !model
+                .drum_machine
+                .sequence
+                .labels_at_step(n)
+                .ok_or(anyhow!("Drum machine sequence has no step {n}"))?
+                .contains(&label)
+            {
+                return Ok(model);
+            }
+
+            let level = (model.drum_machine.velocity_level(n, pad) + 1) % VELOCITY_LEVELS.len();
+            let amp = model
+                .drum_machine
+                .trigger_amp(level, &velocity_curve(&model));
+
+            let mut new_sequence = model.drum_machine.sequence.clone();
+            new_sequence.set_step_trigger(n, label, amp);
+
+            if let Some(render_thread_tx) = &model.drum_machine.render_thread_tx {
+                render_thread_tx
+                    .send(drumkit_render_thread::Message::EditSequenceSetStepTrigger {
+                        step: n,
+                        label,
+                        amp: model.drum_machine.output_amp(amp),
+                    })
+                    .map_err(|e| {
+                        anyhow!("Failed sending update event to drum sequence render thread: {e}")
+                    })?;
+            }
+
+            Ok(AppModel {
+                drum_machine: DrumMachineModel {
+                    sequence: new_sequence,
+                    step_velocities: model
+                        .drum_machine
+                        .step_velocities
+                        .clone_and_insert((n, pad), level),
+                    ..model.drum_machine
+                },
+                ..model
+            })
+        }
+
+        AppMessage::DrumMachinePlaybackEvent(event) => {
+            if model.drum_machine.metronome && event.step % DRUM_MACHINE_STEPS_PER_BEAT == 0 {
+                if let Err(e) = play_metronome_click(&model, event.step == 0) {
+                    log::log!(log::Level::Error, "Metronome error: {e}");
+                }
+            }
+
+            Ok(AppModel {
+                drum_machine: DrumMachineModel {
+                    event_latest: Some(event),
+                    event_latest_at: Some(Instant::now()),
+                    ..model.drum_machine
+                },
+                ..model
+            })
+        }
+    }
+}
+
+const WINDOW_TITLE: &str = "Asampo 0.0.0";
+
+// The drum machine sequence is always in 4/4 with sixteenth note steps.
+const DRUM_MACHINE_STEPS_PER_BEAT: usize = 4;
+const DRUM_MACHINE_STEPS_PER_BAR: usize = 16;
+
+/// Window title reflecting export progress and drum machine playback position, so that the
+/// state of the application is visible while the window is in the background.
+fn window_title(model: &AppModel) -> String {
+    if let Some((n, m)) = model.sets_export_progress {
+        let percent = if m > 0 { n * 100 / m } else { 0 };
+        return format!("{WINDOW_TITLE} — Exporting {percent}%");
+    }
+
+    if let Some(loading) = &model.savefile_loading {
+        return format!("{WINDOW_TITLE} — {}…", loading.stage);
+    }
+
+    if let Some(saving) = &model.savefile_saving {
+        return format!("{WINDOW_TITLE} — {}…", saving.stage);
+    }
+
+    if model.duplicates_job_rx.is_some() {
+        return format!("{WINDOW_TITLE} — Finding duplicates");
+    }
+
+    // the latest event outlives playback, e.g after stopping all sounds
+    match model
+        .drum_machine
+        .event_latest
+        .as_ref()
+        .filter(|_| model.drum_machine.is_playing())
+    {
+        Some(event) => format!(
+            "{WINDOW_TITLE} — ▶ {}.{}",
+            event.step / DRUM_MACHINE_STEPS_PER_BAR + 1,
+            (event.step % DRUM_MACHINE_STEPS_PER_BAR) / DRUM_MACHINE_STEPS_PER_BEAT + 1
+        ),
+
+        None => WINDOW_TITLE.to_string(),
     }
 }
 
@@ -1009,20 +3676,118 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
             sets_export_target_dir_entry
         );
 
+        maybe_update_text!(
+            old,
+            new,
+            expr dialogview.filename_template_entry,
+            sets_export_filename_template_entry
+        );
+
         if old.viewflags.sets_export_fields_valid != new.viewflags.sets_export_fields_valid {
             dialogview
                 .export_button
                 .set_sensitive(new.viewflags.sets_export_fields_valid);
         }
+
+        if old.viewvalues.sets_export_filename_template_error
+            != new.viewvalues.sets_export_filename_template_error
+        {
+            let error = &new.viewvalues.sets_export_filename_template_error;
+
+            dialogview
+                .filename_template_error_label
+                .set_text(error.as_deref().unwrap_or_default());
+            dialogview
+                .filename_template_error_label
+                .set_visible(error.is_some());
+        }
+    }
+
+    if new.viewflags.sources_add_fs_begin_browse {
+        dialogs::choose_folder(
+            model_ptr.clone(),
+            view,
+            SelectFolderDialogContext::BrowseForFilesystemSource,
+            AppMessage::AddFilesystemSourcePathBrowseSubmitted,
+            AppMessage::AddFilesystemSourcePathBrowseError,
+        );
+    }
+
+    if new.viewflags.sources_add_fs_show_preview {
+        dialogs::source_preview(model_ptr.clone(), view, &new);
+    }
+
+    if new.viewflags.samples_open_folder {
+        open_sample_folder(model_ptr.clone(), view, &new);
+    }
+
+    if new.viewflags.samples_reveal_sample {
+        reveal_sample(model_ptr.clone(), view, &new);
+    }
+
+    if new.viewflags.samples_duplicates_show_dialog {
+        dialogs::duplicates(model_ptr.clone(), view, &new);
+    }
+
+    if !new.viewvalues.toasts.is_empty() {
+        for text in new.viewvalues.toasts.iter() {
+            dialogs::toast(view, text);
+        }
+
+        update(model_ptr.clone(), view, AppMessage::ToastsShown);
+    }
+
+    if new.viewflags.samples_sidebar_add_to_set_show_popover {
+        add_to_set_popover(model_ptr.clone(), view, &new);
+    }
+
+    if new.viewflags.samples_searches_show_popover {
+        searches_popover(model_ptr.clone(), view, &new);
+    }
+
+    if new.viewflags.samples_history_show_popover {
+        history_popover(model_ptr.clone(), view, &new);
+    }
+
+    if new.viewflags.samples_save_search_show_dialog {
+        dialogs::input(
+            model_ptr.clone(),
+            view,
+            InputDialogContext::SaveSearch,
+            "Save search",
+            "Name of search:",
+            &new.viewvalues.samples_list.filter,
+            "Save",
+        );
+    }
+
+    if new.viewflags.settings_audio_profile_save_show_dialog {
+        dialogs::input(
+            model_ptr.clone(),
+            view,
+            InputDialogContext::SaveAudioProfile,
+            "Save audio profile",
+            "Name of profile:",
+            "Studio 96k",
+            "Save",
+        );
     }
 
-    if new.viewflags.sources_add_fs_begin_browse {
-        dialogs::choose_folder(
+    if new.viewflags.settings_pad_labels_show_dialog {
+        let labels = new
+            .config
+            .as_ref()
+            .map(AppConfig::pad_labels)
+            .unwrap_or_default();
+
+        dialogs::input(
             model_ptr.clone(),
             view,
-            SelectFolderDialogContext::BrowseForFilesystemSource,
-            AppMessage::AddFilesystemSourcePathBrowseSubmitted,
-            AppMessage::AddFilesystemSourcePathBrowseError,
+            InputDialogContext::CustomPadLabels,
+            "Custom pad labels",
+            "Pad names, separated by commas:",
+            &labels.join(", "),
+            "Use",
         );
     }
 
@@ -1038,6 +3803,20 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
         );
     }
 
+    if new.viewflags.samples_sidebar_rename_show_dialog {
+        if let Some(sample) = &new.samplelist_selected_sample {
+            dialogs::input(
+                model_ptr.clone(),
+                view,
+                InputDialogContext::RenameSample,
+                "Rename sample",
+                "Name (leave empty to use the file name):",
+                &new.sample_display_name(sample),
+                "Rename",
+            );
+        }
+    }
+
     if new.viewflags.sets_add_set_show_dialog {
         dialogs::input(
             model_ptr.clone(),
@@ -1050,10 +3829,78 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
         );
     }
 
+    if new.viewflags.sets_rename_set_show_dialog {
+        if let Some(set) = new.sets_selected_set.and_then(|uuid| new.sets.get(&uuid)) {
+            dialogs::input(
+                model_ptr.clone(),
+                view,
+                InputDialogContext::RenameSampleSet,
+                "Rename set",
+                "Name of set:",
+                set.name(),
+                "Rename",
+            );
+        }
+    }
+
+    if new.viewflags.savefile_save_password_show_dialog {
+        dialogs::input(
+            model_ptr.clone(),
+            view,
+            InputDialogContext::SavefilePasswordForSave,
+            "Encrypt workspace",
+            "Password:",
+            "",
+            "Save",
+        );
+    }
+
+    if new.viewflags.sets_export_errors_show_dialog {
+        dialogs::export_errors(model_ptr.clone(), view, &new.sets_export_errors);
+    }
+
+    if new.viewflags.savefile_review_show_dialog {
+        if let Some(review) = &new.savefile_review {
+            dialogs::savefile_review(model_ptr.clone(), view, review);
+        }
+    }
+
+    if new.viewflags.settings_import_show_dialog {
+        dialogs::settings_import(model_ptr.clone(), view);
+    }
+
+    if new.viewflags.savefile_locked_show_dialog {
+        if let Some(conflict) = &new.savefile_lock_conflict {
+            dialogs::savefile_locked(model_ptr.clone(), view, conflict);
+        }
+    }
+
+    if new.viewflags.savefile_load_password_show_dialog {
+        dialogs::input(
+            model_ptr.clone(),
+            view,
+            InputDialogContext::SavefilePasswordForLoad,
+            "Open encrypted workspace",
+            "Password:",
+            "",
+            "Open",
+        );
+    }
+
     if new.viewflags.sets_export_show_dialog {
         dialogs::sampleset_export(model_ptr.clone(), view, new.clone());
     }
 
+    if new.viewflags.sets_cover_begin_browse {
+        dialogs::choose_image(
+            model_ptr.clone(),
+            view,
+            AppMessage::SampleSetCoverBrowseOpened,
+            AppMessage::SampleSetCoverBrowseSubmitted,
+            AppMessage::SampleSetCoverBrowseError,
+        );
+    }
+
     if new.viewflags.sets_export_begin_browse {
         dialogs::choose_folder(
             model_ptr.clone(),
@@ -1071,6 +3918,15 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
 
     if old.sources != new.sources {
         update_sources_list(model_ptr.clone(), new.clone(), view);
+    } else if old.sources_loading.len() != new.sources_loading.len() {
+        update_sources_overlap(&new, view);
+    }
+
+    if old.sources != new.sources
+        || old.sources_loading.len() != new.sources_loading.len()
+        || old.viewvalues.sources_sample_count != new.viewvalues.sources_sample_count
+    {
+        update_sources_loading_progress(&new, view);
     }
 
     if old.viewvalues.sources_sample_count != new.viewvalues.sources_sample_count {
@@ -1087,10 +3943,21 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
         }
     }
 
-    if old.samplelist_selected_sample != new.samplelist_selected_sample {
+    if old.samplelist_selected_sample != new.samplelist_selected_sample
+        || old.samplelist_selected_samples != new.samplelist_selected_samples
+        || old.samples_display_names != new.samples_display_names
+    {
         update_samples_sidebar(model_ptr.clone(), new.clone(), view);
     }
 
+    if old.samplelist_selected_sample != new.samplelist_selected_sample
+        || old.samples_compare_a != new.samples_compare_a
+        || old.samples_compare_b != new.samples_compare_b
+        || old.samples_compare_playing != new.samples_compare_playing
+    {
+        update_samples_compare(view, &new);
+    }
+
     if old.viewflags.samples_sidebar_add_to_prev_enabled
         != new.viewflags.samples_sidebar_add_to_prev_enabled
     {
@@ -1107,10 +3974,19 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
         }
     }
 
-    if old.sets_selected_set != new.sets_selected_set {
+    if old.sets_selected_set != new.sets_selected_set
+        || old.sets_cover_images != new.sets_cover_images
+        || old.sets_sample_order != new.sets_sample_order
+        || old.sets_export_excluded != new.sets_export_excluded
+        || old.samples_display_names != new.samples_display_names
+    {
         update_samplesets_detail(model_ptr.clone(), new.clone(), view);
     }
 
+    if old.sample_playback_behavior_override != new.sample_playback_behavior_override {
+        update_workspace_settings(view, &new);
+    }
+
     if old.sets != new.sets {
         update_samplesets_list(model_ptr.clone(), new.clone(), view);
         update_samplesets_detail(model_ptr.clone(), new.clone(), view);
@@ -1130,8 +4006,9 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
             Some(model::ExportState::Exporting) => {
                 if let Some(dv) = &new.viewvalues.sets_export_dialog_view {
                     dv.window.close();
-                    view.progress_popup.set_visible(true);
                 }
+
+                view.progress_popup.set_visible(true);
             }
 
             Some(model::ExportState::Finished) => {
@@ -1142,235 +4019,410 @@ fn update_view(model_ptr: AppModelPtr, old: AppModel, new: AppModel, view: &Asam
         }
     }
 
-    if old.sets_export_progress != new.sets_export_progress {
+    if old.sets_export_progress != new.sets_export_progress
+        || old.sets_export_errors.len() != new.sets_export_errors.len()
+    {
         if let Some((n, m)) = &new.sets_export_progress {
-            view.progress_popup_progress_bar
-                .set_text(Some(format!("Exporting {n}/{m}").as_str()));
+            let text = match new.sets_export_errors.len() {
+                0 => format!("Exporting {n}/{m}"),
+                errors => format!("Exporting {n}/{m} ({errors} failed)"),
+            };
+
+            view.progress_popup_progress_bar.set_text(Some(&text));
 
             view.progress_popup_progress_bar
                 .set_fraction(*n as f64 / *m as f64);
         }
     }
 
-    if old.drum_machine != new.drum_machine {
-        update_drum_machine_view(new);
+    // follow playback from its first step rather than from the next idle poll
+    if new.drum_machine.playing && !old.drum_machine.playing {
+        timers::wake(&model_ptr);
+    }
+
+    if old.drum_machine != new.drum_machine
+        || old.config.as_ref().map(AppConfig::pad_labels)
+            != new.config.as_ref().map(AppConfig::pad_labels)
+    {
+        update_drum_machine_view(new.clone());
+    }
+
+    let title = window_title(&new);
+
+    if window_title(&old) != title {
+        view.set_title(Some(&title));
+    }
+
+    if old.config != new.config {
+        update_settings_view(view, &new);
+        update_profiler_overlay(view, &new);
+
+        if appearance_changed(&old, &new) {
+            apply_appearance(&new);
+        }
+    }
+
+    if let (Some(old_config), Some(new_config)) = (&old.config, &new.config) {
+        if old_config.recent_savefiles != new_config.recent_savefiles {
+            update_recent_savefiles_menu(view, new_config);
+        }
+
+        if old_config.preview_volume != new_config.preview_volume
+            && view.titlebar_preview_volume_scale.value().round() as u32
+                != new_config.preview_volume
+        {
+            view.titlebar_preview_volume_scale
+                .set_value(new_config.preview_volume as f64);
+        }
+
+        if old_config.audio_profiles != new_config.audio_profiles
+            || old_config.active_audio_profile() != new_config.active_audio_profile()
+        {
+            update_audio_profiles_menu(view, new_config);
+        }
+    }
+
+    if old.samples_list_grouped() != new.samples_list_grouped() {
+        update_samples_list_grouping(view, &new);
     }
 }
 
-fn main() -> ExitCode {
-    env_logger::init();
+thread_local! {
+    /// The model and view of each open window.
+    static WINDOWS: RefCell<Vec<(AppModelPtr, AsampoView)>> = const { RefCell::new(Vec::new()) };
+}
 
-    gtk::gio::resources_register_include!("resources.gresource")
-        .expect("Should be able to register compiled GTK resources.");
+/// The config of the open windows, which all share the same config, if any window is open.
+fn shared_config() -> Option<AppConfig> {
+    WINDOWS.with_borrow(|windows| {
+        windows.iter().find_map(|(model_ptr, _)| {
+            let model = model_ptr.take()?;
+            let config = model.config.clone();
+            model_ptr.set(Some(model));
+            config
+        })
+    })
+}
 
-    let app = Application::builder()
-        .application_id("se.neode.Asampo")
-        .flags(ApplicationFlags::HANDLES_COMMAND_LINE)
-        .build();
+/// Pass a changed config on to the windows other than the one of `model_ptr`, so that one
+/// window saving its config doesn't undo the changes made in another.
+fn share_config(model_ptr: &AppModelPtr, config: &AppConfig) {
+    let others = WINDOWS.with_borrow(|windows| {
+        windows
+            .iter()
+            .filter(|(other, _)| !Rc::ptr_eq(other, model_ptr))
+            .cloned()
+            .collect::<Vec<_>>()
+    });
 
-    app.connect_command_line(clone!(@strong app =>  move |_, _| {
-        app.activate();
-        0
-    }));
+    for (other, view) in others {
+        update(other, &view, AppMessage::ConfigShared(config.clone()));
+    }
+}
 
-    app.connect_activate(|app| {
-        // init css
-        let css_provider = gtk::CssProvider::new();
-        css_provider.load_from_resource("/style.css");
+/// Silence and stop the audio thread and drum machine render thread of a workspace.
+fn shut_down_audio(model: &AppModel) {
+    if let Some(render_thread_tx) = &model.drum_machine.render_thread_tx {
+        if let Err(e) = render_thread_tx.send(drumkit_render_thread::Message::Shutdown) {
+            log::log!(
+                log::Level::Error,
+                "Error shutting down drumkit sequence render thread: {e}"
+            );
+        }
+    }
 
-        gtk::style_context_add_provider_for_display(
-            &Display::default().expect("There should be an available display"),
-            &css_provider,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
+    if let Some(audiothread_tx) = &model.audiothread_tx {
+        if let Err(e) = audiothread_tx
+            .send(audiothread::Message::DropAll)
+            .and_then(|_| audiothread_tx.send(audiothread::Message::Shutdown))
+        {
+            log::log!(log::Level::Error, "Error shutting down audiothread: {e}");
+        }
+    }
+}
 
-        // init config
-        let config = match ConfigFile::load(&ConfigFile::default_path()) {
-            Ok(loaded_config) => {
-                log::log!(
-                    log::Level::Info,
-                    "Loaded config: {}",
-                    loaded_config.config_save_path
-                );
-                loaded_config
-            }
-            Err(e) => {
-                log::log!(log::Level::Error, "Error loading config: {e:?}");
-                log::log!(log::Level::Info, "Using default config");
-                AppConfig::default()
-            }
-        };
+/// Open a window with a workspace of its own, loading `savefile` into it if given. The first
+/// window also offers to restore unsaved work and may reopen the last workspace, while
+/// further windows start out empty and leave the recovery file to the first one until it
+/// closes.
+fn open_window(app: &Application, savefile: Option<String>) {
+    let first = app.windows().is_empty();
+
+    // init config, loading it only for the first window and sharing it between windows
+    let config = match shared_config() {
+        Some(config) => config,
+        None => {
+            let config = match ConfigFile::load(&ConfigFile::default_path()) {
+                Ok(loaded_config) => {
+                    log::log!(
+                        log::Level::Info,
+                        "Loaded config: {}",
+                        loaded_config.config_save_path
+                    );
+                    loaded_config
+                }
+                Err(e) => {
+                    log::log!(log::Level::Error, "Error loading config: {e:?}");
+                    log::log!(log::Level::Info, "Using default config");
+                    AppConfig::default()
+                }
+            };
+
+            ConfigFile::save(&config, &ConfigFile::default_path()).unwrap();
+            config
+        }
+    };
+
+    if let Err(e) = jsonlog::set_enabled(config.troubleshooting_log) {
+        log::log!(log::Level::Error, "Error enabling JSON log: {e}");
+    }
 
-        ConfigFile::save(&config, &ConfigFile::default_path()).unwrap();
-
-        // init audio
-        let (tx, rx) = mpsc::channel();
-        let audiothread_handle = Rc::new(audiothread::spawn(
-            rx,
-            Some(
-                audiothread::Opts::default()
-                    .with_name("asampo")
-                    .with_spec(
-                        AudioSpec::new(config.output_samplerate_hz, 2).unwrap_or_else(|_| {
+    // init audio
+    let (tx, rx) = mpsc::channel();
+    let audiothread_handle = Rc::new(audiothread::spawn(
+        rx,
+        Some(
+            audiothread::Opts::default()
+                .with_name("asampo")
+                .with_spec(
+                    AudioSpec::new(config.output_samplerate_hz, 2).unwrap_or_else(|_| {
+                        log::log!(
+                            log::Level::Error,
+                            "Invalid sample rate in config, using default"
+                        );
+                        AudioSpec::new(48000, 2).unwrap()
+                    }),
+                )
+                .with_conversion_quality(config.sample_rate_conversion_quality)
+                .with_buffer_size(
+                    (config.buffer_size_frames as usize)
+                        .try_into()
+                        .unwrap_or_else(|_| {
                             log::log!(
                                 log::Level::Error,
-                                "Invalid sample rate in config, using default"
+                                "Invalid buffer size in config, using default"
                             );
-                            AudioSpec::new(48000, 2).unwrap()
+                            NonZeroNumFrames::new(1024).unwrap()
                         }),
-                    )
-                    .with_conversion_quality(config.sample_rate_conversion_quality)
-                    .with_buffer_size(
-                        (config.buffer_size_frames as usize)
-                            .try_into()
-                            .unwrap_or_else(|_| {
-                                log::log!(
-                                    log::Level::Error,
-                                    "Invalid buffer size in config, using default"
-                                );
-                                NonZeroNumFrames::new(1024).unwrap()
-                            }),
-                    ),
-            ),
-        ));
+                ),
+        ),
+    ));
 
-        let view = AsampoView::new(app);
+    let view = AsampoView::new(app);
 
-        let model = AppModel::new(
+    let model = AppModel {
+        autosave: first,
+        ..AppModel::new(
             Some(config),
             None,
             Some(tx.clone()),
             Some(audiothread_handle.clone()),
-        );
-        let model_ptr = Rc::new(Cell::new(Some(model.clone())));
+        )
+    };
+    let model_ptr = Rc::new(Cell::new(Some(model.clone())));
 
-        setup_settings_page(model_ptr.clone(), &view);
-        setup_sources_page(model_ptr.clone(), &view);
-        setup_samples_page(model_ptr.clone(), &view);
-        setup_sets_page(model_ptr.clone(), &view);
-        setup_sequences_page(model_ptr.clone(), &view);
+    setup_settings_page(model_ptr.clone(), &view);
+    setup_sources_page(model_ptr.clone(), &view);
+    setup_samples_page(model_ptr.clone(), &view);
+    setup_sets_page(model_ptr.clone(), &view);
+    setup_sequences_page(model_ptr.clone(), &view);
 
-        build_actions(app, model_ptr.clone(), &view);
+    build_actions(app, model_ptr.clone(), &view);
 
-        view.titlebar_stop_button.connect_clicked(
-            clone!(@strong model_ptr, @strong view => move |_| {
-                update(model_ptr.clone(), &view, AppMessage::StopAllSoundButtonClicked);
-            }),
-        );
+    if let Some(config) = &model.config {
+        update_recent_savefiles_menu(&view, config);
+        update_audio_profiles_menu(&view, config);
+    }
 
-        view.present();
+    update_profiler_overlay(&view, &model);
+    apply_appearance(&model);
 
-        // timer for AppMessage::TimerTick
-        gtk::glib::timeout_add_seconds_local(
-            1,
-            clone!(@strong model_ptr, @strong view => move || {
-                update(model_ptr.clone(), &view, AppMessage::TimerTick);
-                gtk::glib::ControlFlow::Continue
-            }),
-        );
+    view.titlebar_stop_button
+        .connect_clicked(clone!(@strong model_ptr, @strong view => move |_| {
+            update(model_ptr.clone(), &view, AppMessage::StopAllSoundButtonClicked);
+        }));
 
-        // timer for async/thread messaging
-        gtk::glib::timeout_add_local(
-            std::time::Duration::from_millis(50),
-            clone!(@strong model_ptr, @strong view => move || {
-                let model = model_ptr.take().unwrap();
-                let export_job_rx = model.export_job_rx.clone();
-                let sources_loading = model.sources_loading.clone();
-                model_ptr.set(Some(model));
-
-                if let Some(rx) = export_job_rx {
-                    loop {
-                        match rx.try_recv() {
-                            Ok(m) => update(
-                                model_ptr.clone(),
-                                &view,
-                                AppMessage::ExportJobMessage(m)
-                            ),
-
-                            Err(e) => {
-                                match e {
-                                    mpsc::TryRecvError::Empty => (),
-                                    mpsc::TryRecvError::Disconnected =>
-                                        update(
-                                            model_ptr.clone(),
-                                            &view,
-                                            AppMessage::ExportJobDisconnected
-                                        ),
-                                }
-
-                                break
-                            },
-                        }
-                    }
-                }
+    view.titlebar_mute_button
+        .connect_toggled(clone!(@strong model_ptr, @strong view => move |e| {
+            update(model_ptr.clone(), &view, AppMessage::PreviewMuteToggled(e.is_active()));
+        }));
 
-                for uuid in sources_loading.keys() {
-                    let recv = sources_loading.get(uuid).unwrap();
+    if let Some(config) = &model.config {
+        view.titlebar_preview_volume_scale
+            .set_value(config.preview_volume as f64);
+    }
 
-                    match recv.try_recv() {
-                        Ok(message) => {
-                            let mut messages = vec![message];
-                            messages.extend(recv.try_iter());
+    view.titlebar_preview_volume_scale.connect_value_changed(
+        clone!(@strong model_ptr, @strong view => move |e| {
+            update(
+                model_ptr.clone(),
+                &view,
+                AppMessage::PreviewVolumeChanged(e.value().round() as u32),
+            );
+        }),
+    );
 
-                            update(
-                                model_ptr.clone(),
-                                &view,
-                                AppMessage::SourceLoadingMessage(*uuid, messages)
-                            );
-                        }
+    view.stack.connect_visible_child_name_notify(
+        clone!(@strong model_ptr, @strong view => move |stack| {
+            if let Some(page) = stack.visible_child_name() {
+                update(model_ptr.clone(), &view, AppMessage::PageChanged(page.to_string()));
+            }
+        }),
+    );
 
-                        Err(e) => {
-                            match e {
-                                mpsc::TryRecvError::Empty => (),
-                                mpsc::TryRecvError::Disconnected => {
-                                    update(
-                                        model_ptr.clone(),
-                                        &view,
-                                        AppMessage::SourceLoadingDisconnected(*uuid)
-                                    );
-                                },
-                            }
-                        }
-                    };
-                }
+    view.present();
 
-                gtk::glib::ControlFlow::Continue
-            }),
+    WINDOWS.with_borrow_mut(|windows| windows.push((model_ptr.clone(), view.clone())));
+
+    if let Some(savefile) = savefile {
+        update(
+            model_ptr.clone(),
+            &view,
+            AppMessage::LoadFromSavefile(savefile),
         );
+    } else if first && Path::new(&savefile::recovery_path()).exists() {
+        dialogs::recovery_restore(model_ptr.clone(), &view);
+    } else if let Some(last) = model
+        .config
+        .as_ref()
+        .filter(|config| first && config.reopen_last_workspace)
+        .and_then(|config| config.recent_savefiles.first())
+    {
+        if Path::new(last).exists() {
+            update(
+                model_ptr.clone(),
+                &view,
+                AppMessage::LoadFromSavefile(last.clone()),
+            );
+        } else {
+            log::log!(log::Level::Warn, "Last workspace not found: {last}");
+            update(
+                model_ptr.clone(),
+                &view,
+                AppMessage::ShowToast(
+                    "Could not reopen the last workspace, file not found".to_string(),
+                ),
+            );
+        }
+    }
 
-        gtk::glib::timeout_add_local(
-            std::time::Duration::from_millis(4),
-            clone!(@strong model_ptr, @strong view => move || {
-                let model = model_ptr.take().unwrap();
+    let timer = timers::start(model_ptr.clone(), &view);
 
-                let mut event: Option<DrumkitSequenceEvent> = None;
+    view.connect_close_request(clone!(@strong model_ptr => move |_| {
+        timer.stop();
 
-                if let Some(event_rx) = &model.drum_machine.event_rx {
-                    match event_rx.borrow_mut().latest() {
-                        Some(ev) if model.drum_machine.event_latest.is_none() ||
-                            ev.step != model.drum_machine.event_latest.as_ref().unwrap().step =>
-                        {
-                            event = Some(ev.clone());
-                        }
-                        _ => (),
-                    }
+        let remaining = WINDOWS.with_borrow_mut(|windows| {
+            windows.retain(|(other, _)| !Rc::ptr_eq(other, &model_ptr));
+            windows.first().cloned()
+        });
+
+        let mut autosaving = false;
+
+        model_ptr.with_model(|model| {
+            autosaving = model.autosave;
+
+            // let a save in progress finish, rather than leave the savefile half written
+            if let Some(rx) = &model.save_job_rx {
+                for _ in rx.iter() {}
+            }
+
+            if let Some(savefile) = &model.savefile {
+                lockfile::release(savefile, &model.window_id);
+            }
+
+            shut_down_audio(&model);
+            model
+        });
+
+        if let Some((other, view)) = remaining.filter(|_| autosaving) {
+            update(other, &view, AppMessage::AutosaveHandedOver);
+        }
+
+        gtk::glib::Propagation::Proceed
+    }));
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    crashdump::install_panic_hook();
+
+    // export in this process and without GTK, rather than in a primary instance or a window
+    match cli::parse_export_args(&std::env::args().skip(1).collect::<Vec<String>>()) {
+        Ok(Some(export_args)) => return ExitCode::from(cli::run_export(export_args)),
+        Ok(None) => (),
+        Err(e) => return ExitCode::from(cli::usage_error(e)),
+    }
+
+    gtk::gio::resources_register_include!("resources.gresource")
+        .expect("Should be able to register compiled GTK resources.");
+
+    let app = Application::builder()
+        .application_id("se.neode.Asampo")
+        .flags(ApplicationFlags::HANDLES_COMMAND_LINE)
+        .build();
+
+    // savefile given on the command line, to be loaded once the window is up
+    let pending_savefile: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+    app.connect_command_line(
+        clone!(@strong app, @strong pending_savefile => move |_, cmdline| {
+            let mut args = cmdline
+                .arguments()
+                .iter()
+                .skip(1)
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect::<Vec<String>>();
+
+            if cli::take_flag(&mut args, cli::JSON_LOG_FLAG) {
+                if let Err(e) = jsonlog::force_enabled() {
+                    log::log!(log::Level::Error, "Error enabling JSON log: {e}");
                 }
+            }
 
-                model_ptr.replace(Some(model));
+            match cli::parse_savefile_arg(&args) {
+                Ok(savefile) => {
+                    // resolve relative to the invoking shell, which may not be the primary instance
+                    *pending_savefile.borrow_mut() = savefile.and_then(|savefile| {
+                        cmdline
+                            .create_file_for_arg(savefile)
+                            .path()
+                            .map(|path| path.to_string_lossy().to_string())
+                    });
 
-                if let Some(ev) = event {
-                    update(
-                        model_ptr.clone(),
-                        &view,
-                        AppMessage::DrumMachinePlaybackEvent(ev.clone())
-                    );
+                    app.activate();
+                    cli::EXIT_OK
                 }
+                Err(e) => cli::usage_error(e),
+            }
+        }),
+    );
 
-                gtk::glib::ControlFlow::Continue
-            }),
+    app.connect_startup(|app| {
+        // init css
+        let css_provider = gtk::CssProvider::new();
+        css_provider.load_from_resource("/style.css");
+
+        gtk::style_context_add_provider_for_display(
+            &Display::default().expect("There should be an available display"),
+            &css_provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
         );
+
+        app.add_action_entries([ActionEntry::builder("new_window")
+            .activate(|app: &Application, _, _| open_window(app, None))
+            .build()]);
+
+        app.set_accels_for_action("app.new_window", &["<Control>n"]);
     });
 
+    app.connect_activate(clone!(@strong pending_savefile => move |app| {
+        match (pending_savefile.take(), app.active_window()) {
+            // present the open window, unless asked to open a workspace
+            (None, Some(window)) => window.present(),
+            (savefile, _) => open_window(app, savefile),
+        }
+    }));
+
     app.run()
 }
 
@@ -1379,6 +4431,150 @@ mod tests {
     use super::*;
     use crate::testutils::savefile_for_test;
 
+    #[test]
+    fn test_cuts_off_previews() {
+        let model = AppModel::new(Some(AppConfig::default()), None, None, None);
+
+        let single = AppModel {
+            sample_playback_behavior_override: Some(SamplePlaybackBehavior::PlaySingleSample),
+            ..model.clone()
+        };
+
+        assert!(cuts_off_previews(&single));
+
+        assert!(!cuts_off_previews(&AppModel {
+            drum_machine: DrumMachineModel {
+                event_latest_at: Some(Instant::now()),
+                ..single.drum_machine.clone()
+            },
+            ..single.clone()
+        }));
+
+        assert!(cuts_off_previews(&AppModel {
+            drum_machine: DrumMachineModel {
+                event_latest_at: Some(Instant::now() - crate::model::PLAYBACK_IDLE_AFTER),
+                ..single.drum_machine.clone()
+            },
+            ..single
+        }));
+
+        assert!(!cuts_off_previews(&AppModel {
+            sample_playback_behavior_override: Some(SamplePlaybackBehavior::PlayUntilEnd),
+            ..model
+        }));
+    }
+
+    #[test]
+    fn test_reconnect_audio_backs_off_and_gives_up() {
+        let model = AppModel {
+            audio_send_failed: true,
+            audio_reconnecting: true,
+            ..AppModel::new(Some(AppConfig::default()), None, None, None)
+        };
+
+        assert!(audio_lost(&model));
+
+        let waiting = reconnect_audio(AppModel {
+            audio_reconnect_attempts: 1,
+            audio_reconnect_deadline: Some(Instant::now() + Duration::from_secs(60)),
+            ..model.clone()
+        })
+        .unwrap();
+
+        assert_eq!(waiting.audio_reconnect_attempts, 1);
+        assert!(waiting.audio_reconnecting);
+
+        let given_up = reconnect_audio(AppModel {
+            audio_reconnect_attempts: AUDIO_RECONNECT_MAX_ATTEMPTS,
+            ..model
+        })
+        .unwrap();
+
+        assert_eq!(
+            given_up.audio_reconnect_attempts,
+            AUDIO_RECONNECT_MAX_ATTEMPTS
+        );
+        assert!(!given_up.audio_reconnecting);
+        assert!(audio_lost(&given_up));
+    }
+
+    #[test]
+    fn test_only_audio_output_changes_respawn_audio() {
+        let config = AppConfig::default();
+
+        let model = AppModel {
+            audiothread_config: Some(config.clone()),
+            ..AppModel::new(Some(config.clone()), None, None, None)
+        };
+
+        assert!(!audio_output_changed(
+            &model
+                .clone()
+                .set_config(config.clone().with_filter_history_entry("kick"))
+        ));
+
+        assert!(!audio_output_changed(
+            &model
+                .clone()
+                .set_config(config.clone().with_preview_volume(50))
+        ));
+
+        assert!(audio_output_changed(
+            &model.set_config(config.with_buffer_size(256))
+        ));
+    }
+
+    #[test]
+    fn test_rename_exported_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let target_dir = dir.path().to_str().unwrap();
+
+        for name in ["kick.wav", "snare.wav", "unrelated.wav"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+
+        let files = exported_files(
+            target_dir,
+            &[
+                ("snare.flac".to_string(), "snare".to_string()),
+                ("kick.flac".to_string(), "Big kick v1.2".to_string()),
+                ("missing.flac".to_string(), "missing".to_string()),
+            ],
+            true,
+        );
+
+        assert_eq!(
+            rename_exported_files(&files).unwrap(),
+            vec![
+                dir.path().join("snare.wav"),
+                dir.path().join("Big kick v1.2.wav")
+            ]
+        );
+
+        assert!(!dir.path().join("kick.wav").exists());
+        assert!(dir.path().join("unrelated.wav").exists());
+    }
+
+    #[test]
+    fn test_secret_dialog_text_is_redacted() {
+        let message = AppMessage::InputDialogSubmitted(
+            InputDialogContext::SavefilePasswordForSave,
+            DialogText::new(
+                &InputDialogContext::SavefilePasswordForSave,
+                "hunter2".to_string(),
+            ),
+        );
+
+        assert!(!format!("{message:?}").contains("hunter2"));
+
+        let message = AppMessage::InputDialogSubmitted(
+            InputDialogContext::RenameSampleSet,
+            DialogText::new(&InputDialogContext::RenameSampleSet, "Kit".to_string()),
+        );
+
+        assert!(format!("{message:?}").contains("Kit"));
+    }
+
     #[test]
     fn test_using_real_savefile_in_test() {
         use libasampo::sources::{file_system_source::FilesystemSource, Source};
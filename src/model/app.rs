@@ -4,7 +4,8 @@
 
 use std::{
     cell::{Cell, RefCell},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    path::Path,
     rc::Rc,
     sync::mpsc,
     thread::JoinHandle,
@@ -15,45 +16,299 @@ use anyhow::anyhow;
 use gtk::prelude::ListModelExt;
 use libasampo::{
     samples::{Sample, SampleOps},
-    samplesets::{export::ExportJobMessage, SampleSet, SampleSetOps},
+    samplesets::{
+        export::ExportJobMessage, BaseSampleSet, DrumkitLabel, DrumkitLabelling, SampleSet,
+        SampleSetLabelling, SampleSetOps,
+    },
     sources::{Source, SourceOps},
 };
 use uuid::Uuid;
 
 use crate::{
-    config::AppConfig,
+    config::{AppConfig, SampleFilterMode, SamplePlaybackBehavior},
     ext::{ClonedHashMapExt, ClonedVecExt},
-    model::{DrumMachineModel, ModelResult, ViewFlags, ViewValues},
+    fswatch::SourceWatcher,
+    model::{
+        DrumMachineModel, ExportKind, FilenameTemplate, ModelResult, SampleListId, ViewFlags,
+        ViewModelOps, ViewValues,
+    },
+    savefile::{LoadJobMessage, SaveJobMessage},
+    util,
     view::samples::SampleListEntry,
 };
 
+pub const FAVORITES_SET_NAME: &str = "Favorites";
+
+/// Number of samples kept in [`AppModel::samples_audition_history`].
+pub const AUDITION_HISTORY_MAX: usize = 20;
+
+/// One of the two samples being compared by switching between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareSlot {
+    A,
+    B,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExportState {
     Exporting,
     Finished,
 }
 
+/// A savefile about to be opened or saved while another instance holds its lock file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavefileLockConflict {
+    pub filename: String,
+    pub held_by: String,
+    pub saving: bool,
+}
+
+/// A save over the active savefile waiting for the changes to be reviewed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavefileReview {
+    pub filename: String,
+    pub changes: Vec<String>,
+}
+
+/// A sample set being filled from an imported kit as the source of the kit's samples loads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KitImport {
+    pub set_uuid: Uuid,
+
+    /// Paths of the samples of the kit, in kit order.
+    pub samples: Vec<String>,
+
+    /// The drum machine label of each sample, if the kit plays it on a known note.
+    pub labels: Vec<Option<DrumkitLabel>>,
+}
+
+impl KitImport {
+    fn includes(&self, sample: &Sample) -> bool {
+        self.samples
+            .iter()
+            .any(|path| sample.uri().as_str().ends_with(path.as_str()))
+    }
+}
+
+/// A savefile being written on a worker thread, along with the state of the workspace that
+/// is being saved, since the workspace may change before saving finishes.
+#[derive(Debug, Clone)]
+pub struct SaveInProgress {
+    pub filename: String,
+    pub snapshot: WorkspaceSnapshot,
+    pub stage: &'static str,
+}
+
+/// A savefile being loaded on a worker thread.
+#[derive(Debug, Clone)]
+pub struct LoadInProgress {
+    pub filename: String,
+    pub password: Option<String>,
+    pub stage: &'static str,
+
+    /// Whether the recovery file is being restored, which doesn't become the active savefile.
+    pub recovery: bool,
+}
+
+/// The parts of the workspace that are written to a savefile, used to tell whether the
+/// workspace has unsaved changes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkspaceSnapshot {
+    pub sources: HashMap<Uuid, Source>,
+    pub sets: HashMap<Uuid, SampleSet>,
+    pub sets_cover_images: HashMap<Uuid, String>,
+    pub sets_sample_order: HashMap<Uuid, Vec<String>>,
+    pub sets_export_excluded: HashMap<Uuid, HashSet<String>>,
+    pub sets_favorites: Option<Uuid>,
+    pub samples_display_names: HashMap<String, String>,
+    pub sample_playback_behavior: Option<SamplePlaybackBehavior>,
+}
+
+impl WorkspaceSnapshot {
+    /// Describe what has changed from `old` to this snapshot, one line per change.
+    pub fn changes_since(&self, old: &WorkspaceSnapshot) -> Vec<String> {
+        let source_name = |source: &Source| source.name().unwrap_or("Unnamed").to_string();
+
+        let mut sources = Vec::new();
+
+        for (uuid, source) in self.sources.iter() {
+            match old.sources.get(uuid) {
+                None => sources.push(format!("Added source '{}'", source_name(source))),
+                Some(old_source) if old_source != source => {
+                    sources.push(format!("Changed source '{}'", source_name(source)))
+                }
+                Some(_) => (),
+            }
+        }
+
+        for (uuid, source) in old.sources.iter() {
+            if !self.sources.contains_key(uuid) {
+                sources.push(format!("Removed source '{}'", source_name(source)));
+            }
+        }
+
+        let mut sets = Vec::new();
+
+        for (uuid, set) in self.sets.iter() {
+            match old.sets.get(uuid) {
+                None => sets.push(format!("Added set '{}'", set.name())),
+
+                Some(old_set) => {
+                    let renamed = old_set.name() != set.name();
+
+                    if renamed {
+                        sets.push(format!(
+                            "Renamed set '{}' to '{}'",
+                            old_set.name(),
+                            set.name()
+                        ));
+                    }
+
+                    if old_set.len() != set.len() {
+                        sets.push(format!(
+                            "Set '{}' went from {} to {} samples",
+                            set.name(),
+                            old_set.len(),
+                            set.len()
+                        ));
+                    } else if (!renamed && old_set != set)
+                        || old.sets_cover_images.get(uuid) != self.sets_cover_images.get(uuid)
+                        || old.sets_sample_order.get(uuid) != self.sets_sample_order.get(uuid)
+                        || old.sets_export_excluded.get(uuid) != self.sets_export_excluded.get(uuid)
+                    {
+                        sets.push(format!("Changed set '{}'", set.name()));
+                    }
+                }
+            }
+        }
+
+        for (uuid, set) in old.sets.iter() {
+            if !self.sets.contains_key(uuid) {
+                sets.push(format!("Removed set '{}'", set.name()));
+            }
+        }
+
+        sources.sort();
+        sets.sort();
+
+        let mut changes = sources;
+        changes.extend(sets);
+
+        let renamed_samples = self
+            .samples_display_names
+            .iter()
+            .filter(|(uri, name)| old.samples_display_names.get(*uri) != Some(name))
+            .count()
+            + old
+                .samples_display_names
+                .keys()
+                .filter(|uri| !self.samples_display_names.contains_key(*uri))
+                .count();
+
+        if renamed_samples > 0 {
+            changes.push(format!("Renamed {renamed_samples} sample(s)"));
+        }
+
+        if self.sample_playback_behavior != old.sample_playback_behavior {
+            changes.push("Changed the sample playback behavior".to_string());
+        }
+
+        changes
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AppModel {
     pub config: Option<AppConfig>,
     pub config_save_timeout: Option<std::time::Instant>,
     pub savefile: Option<String>,
+    pub savefile_pending: Option<String>,
+    pub savefile_lock_conflict: Option<SavefileLockConflict>,
+    pub savefile_review: Option<SavefileReview>,
+
+    /// Settings read from a file, waiting for the user to pick which ones to import.
+    pub settings_import: Option<AppConfig>,
+    pub savefile_saving: Option<SaveInProgress>,
+    pub save_job_rx: Option<Rc<mpsc::Receiver<SaveJobMessage>>>,
+    pub savefile_loading: Option<LoadInProgress>,
+    pub load_job_rx: Option<Rc<mpsc::Receiver<LoadJobMessage>>>,
+    pub savefile_password: Option<String>,
+    pub sample_playback_behavior_override: Option<SamplePlaybackBehavior>,
+    pub savefile_snapshot: WorkspaceSnapshot,
+    pub autosave_snapshot: WorkspaceSnapshot,
+    pub autosave_deadline: Option<Instant>,
+
+    /// Whether the workspace is autosaved to the recovery file, which only one window does at
+    /// a time: the first one, and then a remaining window once that one closes.
+    pub autosave: bool,
+
+    /// Identifies the window of the workspace, e.g in the lock files it writes.
+    pub window_id: Uuid,
     pub viewflags: ViewFlags,
     pub viewvalues: ViewValues,
     pub audiothread_tx: Option<mpsc::Sender<audiothread::Message>>,
     pub _audiothread_handle: Option<Rc<JoinHandle<()>>>,
+
+    /// The config the audiothread was spawned with.
+    pub audiothread_config: Option<AppConfig>,
+    pub audio_reconnecting: bool,
+
+    /// Whether sending to the audiothread failed, which means it has to be respawned.
+    pub audio_send_failed: bool,
+
+    /// Reconnection attempts made since the audio was last connected.
+    pub audio_reconnect_attempts: u32,
+    pub audio_reconnect_deadline: Option<Instant>,
     pub sources: HashMap<Uuid, Source>,
     pub sources_order: Vec<Uuid>,
     pub sources_loading:
         HashMap<Uuid, Rc<mpsc::Receiver<Result<Sample, libasampo::errors::Error>>>>,
+    pub sources_rescanning: HashMap<Uuid, Rc<RefCell<Vec<Sample>>>>,
+    pub sources_watching: HashMap<Uuid, Rc<SourceWatcher>>,
+    pub sources_rescan_due: HashMap<Uuid, Instant>,
     pub samples: Rc<RefCell<Vec<Sample>>>,
     pub samplelist_selected_sample: Option<Sample>,
+    pub samplelist_selected_samples: Vec<Sample>,
+    pub samples_duplicates: Vec<Vec<Sample>>,
+    pub samples_hide_duplicates: bool,
+    pub samples_preview_muted: bool,
+    pub samples_split_view: bool,
+    pub samples_dedup_overlapping: bool,
+    pub samples_favorites_only: bool,
+    pub samples_compare_a: Option<Sample>,
+    pub samples_compare_b: Option<Sample>,
+    pub samples_compare_playing: Option<CompareSlot>,
+
+    /// Semitones by which to shift the pitch (and speed) of previewed samples.
+    pub samples_preview_semitones: i32,
+
+    /// Recently played samples and when they were played, most recent first.
+    pub samples_audition_history: Vec<(Sample, Instant)>,
+
+    /// Names to show instead of the file names of samples, by sample uri.
+    pub samples_display_names: HashMap<String, String>,
+    pub duplicates_job_rx: Option<Rc<mpsc::Receiver<Vec<Vec<Sample>>>>>,
     pub sets: HashMap<Uuid, SampleSet>,
     pub sets_order: Vec<Uuid>,
+    pub sets_cover_images: HashMap<Uuid, String>,
+    pub sets_sample_order: HashMap<Uuid, Vec<String>>,
+
+    /// Uris of the members of each sample set that are left out when the set is exported.
+    pub sets_export_excluded: HashMap<Uuid, HashSet<String>>,
+
+    /// The built-in set of starred samples, created when the first sample is starred. It
+    /// can't be deleted.
+    pub sets_favorites: Option<Uuid>,
     pub sets_selected_set: Option<Uuid>,
     pub sets_most_recently_used_uuid: Option<Uuid>,
+    pub sets_recently_used: Vec<Uuid>,
     pub sets_export_state: Option<ExportState>,
     pub sets_export_progress: Option<(usize, usize)>,
+    pub sets_export_last_settings: Option<(String, Option<ExportKind>, Option<FilenameTemplate>)>,
+    pub sets_export_errors: Vec<String>,
+
+    /// Kits being imported, by the uuid of the source created for their samples.
+    pub sets_kit_imports: HashMap<Uuid, KitImport>,
     pub export_job_rx: Option<Rc<mpsc::Receiver<ExportJobMessage>>>,
     pub drum_machine: DrumMachineModel,
 }
@@ -75,36 +330,177 @@ impl AppModel {
             DrumMachineModel::new(None, None)
         };
 
+        let audiothread_config = audiothread_tx.as_ref().and(config.clone());
+
         AppModel {
             config,
             config_save_timeout: None,
             savefile,
+            savefile_pending: None,
+            savefile_lock_conflict: None,
+            savefile_review: None,
+            settings_import: None,
+            savefile_saving: None,
+            save_job_rx: None,
+            savefile_loading: None,
+            load_job_rx: None,
+            savefile_password: None,
+            sample_playback_behavior_override: None,
+            savefile_snapshot: WorkspaceSnapshot::default(),
+            autosave_snapshot: WorkspaceSnapshot::default(),
+            autosave_deadline: None,
+            autosave: true,
+            window_id: Uuid::new_v4(),
             viewflags: ViewFlags::default(),
             viewvalues,
             audiothread_tx,
             _audiothread_handle: audiothread_handle,
+            audiothread_config,
+            audio_reconnecting: false,
+            audio_send_failed: false,
+            audio_reconnect_attempts: 0,
+            audio_reconnect_deadline: None,
             sources: HashMap::new(),
             sources_order: Vec::new(),
             sources_loading: HashMap::new(),
+            sources_rescanning: HashMap::new(),
+            sources_watching: HashMap::new(),
+            sources_rescan_due: HashMap::new(),
             samples: Rc::new(RefCell::new(Vec::new())),
             samplelist_selected_sample: None,
+            samplelist_selected_samples: Vec::new(),
+            samples_duplicates: Vec::new(),
+            samples_hide_duplicates: false,
+            samples_preview_muted: false,
+            samples_split_view: false,
+            samples_dedup_overlapping: false,
+            samples_favorites_only: false,
+            samples_compare_a: None,
+            samples_compare_b: None,
+            samples_compare_playing: None,
+            samples_preview_semitones: 0,
+            samples_audition_history: Vec::new(),
+            samples_display_names: HashMap::new(),
+            duplicates_job_rx: None,
             sets: HashMap::new(),
             sets_order: Vec::new(),
+            sets_cover_images: HashMap::new(),
+            sets_sample_order: HashMap::new(),
+            sets_export_excluded: HashMap::new(),
+            sets_favorites: None,
             sets_selected_set: None,
             sets_most_recently_used_uuid: None,
+            sets_recently_used: Vec::new(),
             sets_export_state: None,
             sets_export_progress: None,
+            sets_export_last_settings: None,
+            sets_export_errors: Vec::new(),
+            sets_kit_imports: HashMap::new(),
             export_job_rx: None,
             drum_machine,
         }
     }
 
+    pub fn snapshot(&self) -> WorkspaceSnapshot {
+        WorkspaceSnapshot {
+            sources: self.sources.clone(),
+            sets: self.sets.clone(),
+            sets_cover_images: self.sets_cover_images.clone(),
+            sets_sample_order: self.sets_sample_order.clone(),
+            sets_export_excluded: self.sets_export_excluded.clone(),
+            sets_favorites: self.sets_favorites,
+            samples_display_names: self.samples_display_names.clone(),
+            sample_playback_behavior: self.sample_playback_behavior_override.clone(),
+        }
+    }
+
+    fn differs_from(&self, snapshot: &WorkspaceSnapshot) -> bool {
+        self.sources != snapshot.sources
+            || self.sets != snapshot.sets
+            || self.sets_cover_images != snapshot.sets_cover_images
+            || self.sets_sample_order != snapshot.sets_sample_order
+            || self.sets_export_excluded != snapshot.sets_export_excluded
+            || self.sets_favorites != snapshot.sets_favorites
+            || self.samples_display_names != snapshot.samples_display_names
+            || self.sample_playback_behavior_override != snapshot.sample_playback_behavior
+    }
+
+    /// Whether the workspace has changed since it was last saved to or loaded from a savefile.
+    pub fn modified(&self) -> bool {
+        self.differs_from(&self.savefile_snapshot)
+    }
+
+    /// Whether the workspace has changed since it was last written to the recovery file.
+    pub fn modified_since_autosave(&self) -> bool {
+        self.differs_from(&self.autosave_snapshot)
+    }
+
+    /// The sample playback behavior of the workspace, falling back to the global setting.
+    pub fn sample_playback_behavior(&self) -> SamplePlaybackBehavior {
+        self.sample_playback_behavior_override
+            .clone()
+            .or(self
+                .config
+                .as_ref()
+                .map(|config| config.sample_playback_behavior.clone()))
+            .unwrap_or(AppConfig::default().sample_playback_behavior)
+    }
+
+    /// Sample sets ordered for quick selection: most recently added to first, then the
+    /// remaining sets by name.
+    pub fn sets_by_recent_use(&self) -> Vec<(Uuid, &SampleSet)> {
+        let mut rest = self
+            .sets
+            .iter()
+            .filter(|(uuid, _)| !self.sets_recently_used.contains(uuid))
+            .map(|(uuid, set)| (*uuid, set))
+            .collect::<Vec<_>>();
+
+        rest.sort_by(|a, b| a.1.name().cmp(b.1.name()));
+
+        self.sets_recently_used
+            .iter()
+            .filter_map(|uuid| self.sets.get(uuid).map(|set| (*uuid, set)))
+            .chain(rest)
+            .collect()
+    }
+
+    pub fn mark_saved(self) -> Self {
+        AppModel {
+            savefile_snapshot: self.snapshot(),
+            autosave_snapshot: self.snapshot(),
+            ..self
+        }
+    }
+
+    /// Mark the workspace as saved in the state it was in when `snapshot` was taken.
+    pub fn mark_saved_as(self, snapshot: WorkspaceSnapshot) -> Self {
+        AppModel {
+            savefile_snapshot: snapshot.clone(),
+            autosave_snapshot: snapshot,
+            ..self
+        }
+    }
+
+    pub fn mark_autosaved(self) -> Self {
+        AppModel {
+            autosave_snapshot: self.snapshot(),
+            ..self
+        }
+    }
+
     pub fn disable_source(self, uuid: &Uuid) -> ModelResult {
         self.samples
             .borrow_mut()
             .retain(|s| s.source_uuid() != Some(uuid));
 
         Ok(AppModel {
+            sources_watching: self
+                .sources_watching
+                .iter()
+                .filter(|(watched, _)| *watched != uuid)
+                .map(|(watched, watcher)| (*watched, watcher.clone()))
+                .collect(),
             sources: self.sources.cloned_update_with(
                 |mut s: HashMap<Uuid, Source>| -> Result<HashMap<Uuid, Source>, anyhow::Error> {
                     s.get_mut(uuid)
@@ -134,49 +530,496 @@ impl AppModel {
         })
     }
 
+    /// List the files of an enabled source again. The listed samples are collected separately
+    /// and replace the current samples of the source once listing is done.
+    pub fn begin_source_rescan(self, uuid: &Uuid) -> ModelResult {
+        let source = self
+            .sources
+            .get(uuid)
+            .ok_or(anyhow!("Failed to rescan source: UUID not present"))?
+            .clone();
+
+        if !source.is_enabled() {
+            return Err(anyhow!("Failed to rescan source: source is disabled"));
+        }
+
+        if self.sources_loading.contains_key(uuid) {
+            return Err(anyhow!("Failed to rescan source: source is still loading"));
+        }
+
+        let (loader_tx, loader_rx) = mpsc::channel::<Result<Sample, libasampo::errors::Error>>();
+
+        std::thread::spawn(move || {
+            source.list_async(loader_tx);
+        });
+
+        AppModel {
+            sources_rescanning: self
+                .sources_rescanning
+                .clone_and_insert(*uuid, Rc::new(RefCell::new(Vec::new()))),
+            ..self
+        }
+        .add_source_loader(*uuid, loader_rx)
+    }
+
+    /// Replace the samples of a rescanned source with the newly listed samples.
+    pub fn finish_source_rescan(self, uuid: &Uuid) -> ModelResult {
+        let rescanned = self
+            .sources_rescanning
+            .get(uuid)
+            .ok_or(anyhow!(
+                "Failed to finish rescan: source is not being rescanned"
+            ))?
+            .take();
+
+        let model = AppModel {
+            sources_rescanning: self.sources_rescanning.clone_and_remove(uuid)?,
+            ..self
+        };
+
+        // the source may have been disabled or removed while it was being listed
+        if !model
+            .sources
+            .get(uuid)
+            .is_some_and(|source| source.is_enabled())
+        {
+            return Ok(model);
+        }
+
+        let mut samples = model.samples.borrow_mut();
+
+        let uris_before = samples
+            .iter()
+            .filter(|s| s.source_uuid() == Some(uuid))
+            .map(|s| s.uri().as_str().to_string())
+            .collect::<HashSet<String>>();
+
+        let uris_after = rescanned
+            .iter()
+            .map(|s| s.uri().as_str().to_string())
+            .collect::<HashSet<String>>();
+
+        log::log!(
+            log::Level::Info,
+            "Rescanned source {uuid}: {} added, {} removed",
+            uris_after.difference(&uris_before).count(),
+            uris_before.difference(&uris_after).count(),
+        );
+
+        let count = rescanned.len();
+
+        samples.retain(|s| s.source_uuid() != Some(uuid));
+        samples.extend(rescanned);
+        drop(samples);
+
+        let name = model
+            .sources
+            .get(uuid)
+            .and_then(|source| source.name())
+            .unwrap_or("Unnamed")
+            .to_string();
+
+        Ok(model
+            .reset_source_sample_count(*uuid)?
+            .source_sample_count_add(*uuid, count)?
+            .show_toast(format!("Rescanned '{name}'")))
+    }
+
+    /// Watch the folders of a listed, enabled source for added and removed files, if
+    /// enabled in the config. The folders to watch are taken from the samples of the source.
+    pub fn watch_source(self, uuid: &Uuid) -> AppModel {
+        let enabled = self
+            .config
+            .as_ref()
+            .is_some_and(|config| config.watch_sources)
+            && self.sources.get(uuid).is_some_and(|s| s.is_enabled());
+
+        if !enabled {
+            return self;
+        }
+
+        let samples = self.samples.borrow();
+
+        let watcher = SourceWatcher::new(
+            samples
+                .iter()
+                .filter(|s| s.source_uuid() == Some(uuid))
+                .map(|s| s.uri().as_str()),
+        );
+
+        drop(samples);
+
+        match watcher {
+            Ok(watcher) => AppModel {
+                sources_watching: self
+                    .sources_watching
+                    .clone_and_insert(*uuid, Rc::new(watcher)),
+                ..self
+            },
+
+            Err(e) => {
+                log::log!(log::Level::Error, "Unable to watch source {uuid}: {e}");
+                self
+            }
+        }
+    }
+
+    /// Start or stop watching the folders of all enabled sources that are done loading,
+    /// following the config.
+    pub fn watch_sources(self) -> AppModel {
+        let watch = self
+            .config
+            .as_ref()
+            .is_some_and(|config| config.watch_sources);
+
+        if !watch {
+            return AppModel {
+                sources_watching: HashMap::new(),
+                sources_rescan_due: HashMap::new(),
+                ..self
+            };
+        }
+
+        let uuids = self
+            .sources_order
+            .iter()
+            .filter(|uuid| {
+                !self.sources_watching.contains_key(uuid)
+                    && !self.sources_loading.contains_key(uuid)
+            })
+            .copied()
+            .collect::<Vec<Uuid>>();
+
+        uuids
+            .iter()
+            .fold(self, |model, uuid| model.watch_source(uuid))
+    }
+
+    /// Find sources listing the same files, e.g because one source folder is inside another.
+    /// Maps the uuid of each overlapping source to the uuids of the sources it overlaps.
+    pub fn overlapping_sources(&self) -> HashMap<Uuid, Vec<Uuid>> {
+        let mut first_seen = HashMap::<&str, Uuid>::new();
+        let mut overlaps = HashMap::<Uuid, Vec<Uuid>>::new();
+        let samples = self.samples.borrow();
+
+        for sample in samples.iter() {
+            let Some(source_uuid) = sample.source_uuid() else {
+                continue;
+            };
+
+            match first_seen.get(sample.uri().as_str()) {
+                Some(other) if other != source_uuid => {
+                    for (a, b) in [(*source_uuid, *other), (*other, *source_uuid)] {
+                        let entry = overlaps.entry(a).or_default();
+
+                        if !entry.contains(&b) {
+                            entry.push(b);
+                        }
+                    }
+                }
+
+                Some(_) => (),
+
+                None => {
+                    first_seen.insert(sample.uri().as_str(), *source_uuid);
+                }
+            }
+        }
+
+        overlaps
+    }
+
     pub fn populate_samples_listmodel(&self) {
-        let filter = &self.viewvalues.samples_list_filter;
-        self.viewvalues.samples_listview_model.remove_all();
+        self.populate_samples_list(SampleListId::Main);
+
+        if self.samples_split_view {
+            self.populate_samples_list(SampleListId::Split);
+        }
+    }
 
-        if filter.is_empty() {
-            let samples = self
-                .samples
-                .borrow()
+    /// The uris of the samples hidden as duplicates, if hiding duplicates. Only the first
+    /// sample of each group of identical samples is kept.
+    pub fn hidden_duplicates(&self) -> HashSet<String> {
+        match self.samples_hide_duplicates {
+            true => self
+                .samples_duplicates
                 .iter()
+                .flat_map(|group| group.iter().skip(1))
+                .map(|s| s.uri().as_str().to_string())
+                .collect(),
+            false => HashSet::new(),
+        }
+    }
+
+    pub fn populate_samples_list(&self, id: SampleListId) {
+        let list = self.viewvalues.samples_list(id);
+        let filter = &list.filter;
+        let samples = self.samples.borrow();
+
+        let hidden = self.hidden_duplicates();
+
+        // when de-duplicating overlapping sources, list each file only the first time it is seen
+        let mut seen = HashSet::<&str>::new();
+
+        let favorites = self.sets_favorites.and_then(|uuid| self.sets.get(&uuid));
+
+        let visible_samples = samples
+            .iter()
+            .filter(|s| hidden.is_empty() || !hidden.contains(s.uri().as_str()))
+            .filter(|s| !self.samples_dedup_overlapping || seen.insert(s.uri().as_str()))
+            .filter(|s| {
+                !self.samples_favorites_only || favorites.is_some_and(|set| set.contains(s))
+            })
+            .collect::<Vec<&Sample>>();
+
+        let visible = || visible_samples.iter().copied();
+
+        let entries = if filter.is_empty() {
+            visible()
                 .map(|s| SampleListEntry::new(s.clone()))
+                .collect::<Vec<_>>()
+        } else if self
+            .config
+            .as_ref()
+            .is_some_and(|config| config.sample_filter_mode == SampleFilterMode::Fuzzy)
+        {
+            let mut matches = visible()
+                .filter_map(|s| {
+                    util::fuzzy_match(filter, s.uri().as_str())
+                        .map(|(score, positions)| (score, s.clone(), positions))
+                })
                 .collect::<Vec<_>>();
 
-            self.viewvalues
-                .samples_listview_model
-                .extend_from_slice(samples.as_slice());
+            matches.sort_by(|a, b| b.0.cmp(&a.0));
+
+            matches
+                .into_iter()
+                .map(|(_, s, positions)| SampleListEntry::new_highlighted(s, positions))
+                .collect::<Vec<_>>()
         } else {
             let fragments = filter
                 .split(' ')
                 .map(|s| s.to_string().to_lowercase())
                 .collect::<Vec<_>>();
 
-            let mut samples = self.samples.borrow().clone();
+            visible()
+                .filter(|x| {
+                    fragments
+                        .iter()
+                        .all(|frag| x.uri().as_str().to_lowercase().contains(frag))
+                })
+                .map(|s| SampleListEntry::new(s.clone()))
+                .collect::<Vec<_>>()
+        };
 
-            samples.retain(|x| {
-                fragments
-                    .iter()
-                    .all(|frag| x.uri().as_str().to_lowercase().contains(frag))
-            });
+        if !self.samples_display_names.is_empty() {
+            for entry in entries.iter() {
+                let name = self
+                    .samples_display_names
+                    .get(entry.value.borrow().uri().as_str())
+                    .cloned();
 
-            self.viewvalues.samples_listview_model.extend_from_slice(
-                samples
-                    .iter()
-                    .map(|s| SampleListEntry::new(s.clone()))
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            );
+                entry.display_name.replace(name);
+            }
+        }
+
+        if let Some(favorites) = favorites {
+            for entry in entries.iter() {
+                let favorite = favorites.contains(&entry.value.borrow());
+                entry.favorite.replace(favorite);
+            }
         }
 
         log::log!(
             log::Level::Debug,
-            "Showing {} samples",
-            self.viewvalues.samples_listview_model.n_items()
+            "Showing {} samples in {id:?} list",
+            entries.len()
         );
+
+        match self
+            .config
+            .as_ref()
+            .is_some_and(|config| config.samples_list_keep_place)
+        {
+            true => list.update(entries),
+            false => list.fill(entries),
+        }
+
+        if self.samples_list_grouped() {
+            list.set_groups(
+                self.sources_order
+                    .iter()
+                    .filter_map(|uuid| self.sources.get(uuid).map(|source| (uuid, source)))
+                    .filter(|(_, source)| source.is_enabled())
+                    .map(|(uuid, source)| (*uuid, source.name().unwrap_or("Unnamed").to_string()))
+                    .collect(),
+            );
+        }
+    }
+
+    /// The name to show for a sample, which is its file name unless renamed in the workspace.
+    pub fn sample_display_name(&self, sample: &Sample) -> String {
+        self.samples_display_names
+            .get(sample.uri().as_str())
+            .cloned()
+            .unwrap_or(sample.name().to_string())
+    }
+
+    /// The file names of the exported members of a sample set, in their user-defined order,
+    /// paired with the file stems they are exported under. Members renamed in the workspace
+    /// are exported under their display names, and `template` names the files after the set,
+    /// the member names and their order.
+    pub fn export_file_names(
+        &self,
+        set_uuid: &Uuid,
+        template: Option<&FilenameTemplate>,
+    ) -> Result<Vec<(String, String)>, anyhow::Error> {
+        let set_name = self
+            .sets
+            .get(set_uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?
+            .name()
+            .to_string();
+
+        let members = self.exported_set_members(set_uuid)?;
+
+        Ok(members
+            .iter()
+            .enumerate()
+            .filter_map(|(i, sample)| {
+                let stem = Path::new(sample.name()).file_stem()?.to_str()?.to_string();
+
+                let name = match self.samples_display_names.get(sample.uri().as_str()) {
+                    Some(name) => name.clone(),
+                    None => stem,
+                };
+
+                let export_stem = match template {
+                    Some(template) => template.expand(&set_name, &name, i, members.len()),
+                    None => name.replace(['/', '\\'], "_"),
+                };
+
+                Some((sample.name().to_string(), export_stem))
+            })
+            .collect())
+    }
+
+    /// Set the name to show for a sample, or go back to its file name if the name is empty.
+    pub fn rename_sample(self, sample: &Sample, name: &str) -> AppModel {
+        let uri = sample.uri().as_str().to_string();
+        let name = name.trim();
+
+        AppModel {
+            samples_display_names: if name.is_empty() || name == sample.name() {
+                self.samples_display_names
+                    .iter()
+                    .filter(|(key, _)| **key != uri)
+                    .map(|(key, name)| (key.clone(), name.clone()))
+                    .collect()
+            } else {
+                self.samples_display_names
+                    .clone_and_insert(uri, name.to_string())
+            },
+            ..self
+        }
+    }
+
+    pub fn samples_list_grouped(&self) -> bool {
+        self.config
+            .as_ref()
+            .is_some_and(|config| config.samples_list_grouped)
+    }
+
+    /// Add newly loaded samples of a source to the set of the kit imported with it, if any,
+    /// keeping only the samples that are part of the kit.
+    pub fn add_imported_kit_samples(self, source_uuid: &Uuid, samples: &[Sample]) -> ModelResult {
+        let Some(import) = self.sets_kit_imports.get(source_uuid) else {
+            return Ok(self);
+        };
+
+        let source = self
+            .sources
+            .get(source_uuid)
+            .ok_or(anyhow!("Source not found"))?;
+
+        let mut sets = self.sets.clone();
+
+        let set = sets
+            .get_mut(&import.set_uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+        for sample in samples.iter().filter(|sample| import.includes(sample)) {
+            set.add(source, sample.clone())?;
+        }
+
+        Ok(AppModel { sets, ..self })
+    }
+
+    /// Put the members of an imported kit's set in kit order and label them by the notes the
+    /// kit plays them on, once the source of its samples has finished loading.
+    pub fn finish_kit_import(self, source_uuid: &Uuid) -> ModelResult {
+        let Some(import) = self.sets_kit_imports.get(source_uuid) else {
+            return Ok(self);
+        };
+
+        let mut sets = self.sets.clone();
+
+        let set = sets
+            .get_mut(&import.set_uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+        let members = import
+            .samples
+            .iter()
+            .zip(import.labels.iter())
+            .filter_map(|(path, label)| {
+                set.list()
+                    .into_iter()
+                    .find(|sample| sample.uri().as_str().ends_with(path.as_str()))
+                    .map(|sample| (sample.uri().clone(), *label))
+            })
+            .collect::<Vec<_>>();
+
+        let mut labelling = match set.labelling() {
+            Some(SampleSetLabelling::DrumkitLabelling(labelling)) => labelling.clone(),
+            _ => DrumkitLabelling::new(),
+        };
+
+        for (uri, label) in members.iter() {
+            if let Some(label) = label {
+                labelling.set(uri.clone(), *label);
+            }
+        }
+
+        match set {
+            SampleSet::BaseSampleSet(ref mut set) => {
+                set.set_labelling(Some(SampleSetLabelling::DrumkitLabelling(labelling)))
+            }
+        }
+
+        let order = members
+            .iter()
+            .map(|(uri, _)| uri.as_str().to_string())
+            .collect::<Vec<String>>();
+
+        if order.len() < import.samples.len() {
+            log::log!(
+                log::Level::Warn,
+                "Imported kit '{}': {} of {} samples not found",
+                set.name(),
+                import.samples.len() - order.len(),
+                import.samples.len()
+            );
+        }
+
+        Ok(AppModel {
+            sets,
+            sets_sample_order: self
+                .sets_sample_order
+                .clone_and_insert(import.set_uuid, order),
+            sets_kit_imports: self.sets_kit_imports.clone_and_remove(source_uuid)?,
+            ..self
+        })
     }
 
     pub fn add_sampleset(self, set: SampleSet) -> Self {
@@ -187,14 +1030,359 @@ impl AppModel {
         }
     }
 
+    pub fn rename_sampleset(self, uuid: &Uuid, name: &str) -> ModelResult {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(anyhow!("Sample set name cannot be empty"));
+        }
+
+        let mut sets = self.sets.clone();
+
+        match sets
+            .get_mut(uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?
+        {
+            SampleSet::BaseSampleSet(ref mut set) => set.set_name(name.to_string()),
+        }
+
+        Ok(AppModel { sets, ..self })
+    }
+
+    /// Add a copy of a sample set, with the same members, labels, member order, export
+    /// exclusions and cover image, right after the original in the sets list.
+    pub fn duplicate_sampleset(self, uuid: &Uuid) -> ModelResult {
+        let set = self
+            .sets
+            .get(uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+        let mut copy = BaseSampleSet::new(format!("Copy of {}", set.name()));
+        copy.set_labelling(set.labelling().cloned());
+
+        for sample in set.list() {
+            let source = self
+                .sources
+                .get(
+                    sample
+                        .source_uuid()
+                        .ok_or(anyhow!("Sample set member has no source"))?,
+                )
+                .ok_or(anyhow!("Could not obtain source for sample set member"))?;
+
+            copy.add(source, sample.clone())?;
+        }
+
+        let copy_uuid = *copy.uuid();
+
+        let mut sets_order = self.sets_order.clone();
+        let position = sets_order
+            .iter()
+            .position(|set_uuid| set_uuid == uuid)
+            .map(|index| index + 1)
+            .unwrap_or(sets_order.len());
+        sets_order.insert(position, copy_uuid);
+
+        Ok(AppModel {
+            sets_order,
+            sets: self
+                .sets
+                .clone_and_insert(copy_uuid, SampleSet::BaseSampleSet(copy)),
+            sets_cover_images: match self.sets_cover_images.get(uuid).cloned() {
+                Some(path) => self.sets_cover_images.clone_and_insert(copy_uuid, path),
+                None => self.sets_cover_images,
+            },
+            sets_sample_order: match self.sets_sample_order.get(uuid).cloned() {
+                Some(order) => self.sets_sample_order.clone_and_insert(copy_uuid, order),
+                None => self.sets_sample_order,
+            },
+            sets_export_excluded: match self.sets_export_excluded.get(uuid).cloned() {
+                Some(members) => self
+                    .sets_export_excluded
+                    .clone_and_insert(copy_uuid, members),
+                None => self.sets_export_excluded,
+            },
+            ..self
+        })
+    }
+
     #[cfg(test)]
     pub fn remove_sampleset(self, uuid: &Uuid) -> ModelResult {
+        if self.sets_favorites.as_ref() == Some(uuid) {
+            return Err(anyhow!("The favorites set cannot be deleted"));
+        }
+
         Ok(AppModel {
             sets_order: self.sets_order.clone_and_remove(uuid)?,
             sets: self.sets.clone_and_remove(uuid)?,
+            sets_cover_images: self
+                .sets_cover_images
+                .iter()
+                .filter(|(set_uuid, _)| *set_uuid != uuid)
+                .map(|(set_uuid, path)| (*set_uuid, path.clone()))
+                .collect(),
+            sets_sample_order: self
+                .sets_sample_order
+                .iter()
+                .filter(|(set_uuid, _)| *set_uuid != uuid)
+                .map(|(set_uuid, order)| (*set_uuid, order.clone()))
+                .collect(),
+            sets_export_excluded: self
+                .sets_export_excluded
+                .iter()
+                .filter(|(set_uuid, _)| *set_uuid != uuid)
+                .map(|(set_uuid, members)| (*set_uuid, members.clone()))
+                .collect(),
+            sets_recently_used: self
+                .sets_recently_used
+                .iter()
+                .filter(|set_uuid| *set_uuid != uuid)
+                .copied()
+                .collect(),
             ..self
         })
     }
+
+    /// Remember that a sample was played, keeping only the [`AUDITION_HISTORY_MAX`] most
+    /// recent ones. A sample played again moves to the front instead of being listed twice.
+    pub fn record_audition(self, sample: &Sample) -> AppModel {
+        let mut history = vec![(sample.clone(), Instant::now())];
+
+        history.extend(
+            self.samples_audition_history
+                .iter()
+                .filter(|(s, _)| s.uri().as_str() != sample.uri().as_str())
+                .take(AUDITION_HISTORY_MAX - 1)
+                .cloned(),
+        );
+
+        AppModel {
+            samples_audition_history: history,
+            ..self
+        }
+    }
+
+    pub fn is_favorite(&self, sample: &Sample) -> bool {
+        self.sets_favorites
+            .and_then(|uuid| self.sets.get(&uuid))
+            .is_some_and(|set| set.contains(sample))
+    }
+
+    /// Star or unstar a sample, creating the favorites set when the first sample is starred.
+    pub fn set_favorite(self, sample: &Sample, favorite: bool) -> ModelResult {
+        let (model, uuid) = match self
+            .sets_favorites
+            .filter(|uuid| self.sets.contains_key(uuid))
+        {
+            Some(uuid) => (self, uuid),
+            None if !favorite => return Ok(self),
+            None => {
+                let set = BaseSampleSet::new(FAVORITES_SET_NAME.to_string());
+                let uuid = *set.uuid();
+
+                let model = AppModel {
+                    sets_favorites: Some(uuid),
+                    ..self.add_sampleset(SampleSet::BaseSampleSet(set))
+                };
+
+                (model, uuid)
+            }
+        };
+
+        let mut sets = model.sets.clone();
+
+        let set = sets
+            .get_mut(&uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+        if favorite && !set.contains(sample) {
+            let source = model
+                .sources
+                .get(
+                    sample
+                        .source_uuid()
+                        .ok_or(anyhow!("Sample has no source"))?,
+                )
+                .ok_or(anyhow!("Could not obtain source for sample"))?;
+
+            set.add(source, sample.clone())?;
+        } else if !favorite && set.contains(sample) {
+            set.remove(sample)?;
+        }
+
+        Ok(AppModel { sets, ..model }.prune_set_sample_order(&uuid))
+    }
+
+    /// Drop the stored positions of samples that are no longer members of a sample set.
+    fn prune_set_sample_order(self, set_uuid: &Uuid) -> AppModel {
+        let (Some(set), Some(order)) = (
+            self.sets.get(set_uuid),
+            self.sets_sample_order.get(set_uuid),
+        ) else {
+            return self;
+        };
+
+        let members = set
+            .list()
+            .into_iter()
+            .map(|sample| sample.uri().as_str())
+            .collect::<HashSet<&str>>();
+
+        if order.iter().all(|uri| members.contains(uri.as_str())) {
+            return self;
+        }
+
+        let order = order
+            .iter()
+            .filter(|uri| members.contains(uri.as_str()))
+            .cloned()
+            .collect::<Vec<String>>();
+
+        AppModel {
+            sets_sample_order: self.sets_sample_order.clone_and_insert(*set_uuid, order),
+            ..self
+        }
+    }
+
+    /// The members of a sample set in their user-defined order. Members without a stored
+    /// position (e.g. recently added ones) are placed last, sorted by name.
+    pub fn ordered_set_members(&self, set_uuid: &Uuid) -> Result<Vec<Sample>, anyhow::Error> {
+        let set = self
+            .sets
+            .get(set_uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+        let positions = self
+            .sets_sample_order
+            .get(set_uuid)
+            .map(|order| {
+                order
+                    .iter()
+                    .enumerate()
+                    .map(|(i, uri)| (uri.as_str(), i))
+                    .collect::<HashMap<&str, usize>>()
+            })
+            .unwrap_or_default();
+
+        let position = |sample: &Sample| positions.get(sample.uri().as_str()).copied();
+
+        let mut members = set.list().into_iter().cloned().collect::<Vec<Sample>>();
+
+        members.sort_by(|a, b| match (position(a), position(b)) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.name().cmp(b.name()),
+        });
+
+        Ok(members)
+    }
+
+    /// Move the member at `index` of a sample set by `offset` positions in the member order.
+    pub fn move_set_member(self, set_uuid: &Uuid, index: usize, offset: isize) -> ModelResult {
+        let mut order = self
+            .ordered_set_members(set_uuid)?
+            .iter()
+            .map(|sample| sample.uri().as_str().to_string())
+            .collect::<Vec<String>>();
+
+        let target = index
+            .checked_add_signed(offset)
+            .filter(|target| *target < order.len())
+            .ok_or(anyhow!("Cannot move sample set member out of bounds"))?;
+
+        let uri = order.remove(index);
+        order.insert(target, uri);
+
+        Ok(AppModel {
+            sets_sample_order: self.sets_sample_order.clone_and_insert(*set_uuid, order),
+            ..self
+        })
+    }
+
+    /// The members of a sample set in their user-defined order, leaving out those excluded
+    /// from export and hidden duplicates.
+    pub fn exported_set_members(&self, set_uuid: &Uuid) -> Result<Vec<Sample>, anyhow::Error> {
+        let hidden = self.hidden_duplicates();
+
+        Ok(self
+            .ordered_set_members(set_uuid)?
+            .into_iter()
+            .filter(|sample| {
+                !self.is_excluded_from_export(set_uuid, sample)
+                    && !hidden.contains(sample.uri().as_str())
+            })
+            .collect())
+    }
+
+    pub fn is_excluded_from_export(&self, set_uuid: &Uuid, sample: &Sample) -> bool {
+        self.sets_export_excluded
+            .get(set_uuid)
+            .is_some_and(|excluded| excluded.contains(sample.uri().as_str()))
+    }
+
+    pub fn set_member_excluded_from_export(
+        self,
+        set_uuid: &Uuid,
+        sample: &Sample,
+        excluded: bool,
+    ) -> AppModel {
+        let mut members = self
+            .sets_export_excluded
+            .get(set_uuid)
+            .cloned()
+            .unwrap_or_default();
+
+        if excluded {
+            members.insert(sample.uri().as_str().to_string());
+        } else {
+            members.remove(sample.uri().as_str());
+        }
+
+        let sets_export_excluded = if members.is_empty() {
+            self.sets_export_excluded
+                .iter()
+                .filter(|(uuid, _)| *uuid != set_uuid)
+                .map(|(uuid, members)| (*uuid, members.clone()))
+                .collect()
+        } else {
+            self.sets_export_excluded
+                .clone_and_insert(*set_uuid, members)
+        };
+
+        AppModel {
+            sets_export_excluded,
+            ..self
+        }
+    }
+
+    /// The sample set to hand to an export job: a copy of the set with its members in their
+    /// user-defined order, without the members that are excluded from export or hidden as
+    /// duplicates.
+    pub fn set_for_export(&self, set_uuid: &Uuid) -> Result<SampleSet, anyhow::Error> {
+        let set = self
+            .sets
+            .get(set_uuid)
+            .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+        let mut result = BaseSampleSet::new(set.name().to_string());
+        result.set_labelling(set.labelling().cloned());
+
+        for sample in self.exported_set_members(set_uuid)?.iter() {
+            let source = self
+                .sources
+                .get(
+                    sample
+                        .source_uuid()
+                        .ok_or(anyhow!("Sample set member has no source"))?,
+                )
+                .ok_or(anyhow!("Could not obtain source for sample set member"))?;
+
+            result.add(source, sample.clone())?;
+        }
+
+        Ok(SampleSet::BaseSampleSet(result))
+    }
 }
 
 pub trait AppModelOps {
@@ -287,8 +1475,6 @@ impl AppModelOps for AppModel {
 
 #[cfg(test)]
 mod tests {
-    use libasampo::samplesets::BaseSampleSet;
-
     use super::*;
 
     #[test]
@@ -305,4 +1491,148 @@ mod tests {
 
         assert!(!model.sets.contains_key(set.uuid()));
     }
+
+    #[test]
+    fn test_favorites_set_cannot_be_removed() {
+        let model = AppModel::new(None, None, None, None);
+        let set = BaseSampleSet::new(FAVORITES_SET_NAME.to_string());
+
+        let model = AppModel {
+            sets_favorites: Some(*set.uuid()),
+            ..model.add_sampleset(SampleSet::BaseSampleSet(set.clone()))
+        };
+
+        assert!(model.remove_sampleset(set.uuid()).is_err());
+    }
+
+    #[test]
+    fn test_prune_set_sample_order() {
+        let set = BaseSampleSet::new("Kit".to_string());
+        let model = AppModel::new(None, None, None, None)
+            .add_sampleset(SampleSet::BaseSampleSet(set.clone()));
+
+        let model = AppModel {
+            sets_sample_order: HashMap::from([(*set.uuid(), vec!["file:///kick.wav".to_string()])]),
+            ..model
+        }
+        .prune_set_sample_order(set.uuid());
+
+        assert_eq!(model.sets_sample_order.get(set.uuid()), Some(&Vec::new()));
+        assert!(model.ordered_set_members(set.uuid()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let model = AppModel::new(None, None, None, None);
+        let kit = BaseSampleSet::new("Kit".to_string());
+        let old = model
+            .add_sampleset(SampleSet::BaseSampleSet(kit.clone()))
+            .add_sampleset(SampleSet::BaseSampleSet(BaseSampleSet::new(
+                "Other".to_string(),
+            )));
+
+        let saved = old.snapshot();
+
+        assert!(saved.changes_since(&saved).is_empty());
+
+        let other = *old
+            .sets
+            .values()
+            .find(|set| set.name() == "Other")
+            .unwrap()
+            .uuid();
+
+        let new = old
+            .rename_sampleset(kit.uuid(), "Drums")
+            .unwrap()
+            .remove_sampleset(&other)
+            .unwrap()
+            .add_sampleset(SampleSet::BaseSampleSet(BaseSampleSet::new(
+                "New".to_string(),
+            )));
+
+        assert_eq!(
+            new.snapshot().changes_since(&saved),
+            vec![
+                "Added set 'New'".to_string(),
+                "Removed set 'Other'".to_string(),
+                "Renamed set 'Kit' to 'Drums'".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_sampleset() {
+        let model = AppModel::new(None, None, None, None);
+        let first = BaseSampleSet::new("Kit".to_string());
+        let second = BaseSampleSet::new("Other".to_string());
+
+        let model = model
+            .add_sampleset(SampleSet::BaseSampleSet(first.clone()))
+            .add_sampleset(SampleSet::BaseSampleSet(second.clone()));
+
+        let model = AppModel {
+            sets_cover_images: model
+                .sets_cover_images
+                .clone_and_insert(*first.uuid(), "cover.png".to_string()),
+            ..model
+        };
+
+        let model = model.duplicate_sampleset(first.uuid()).unwrap();
+
+        assert_eq!(model.sets.len(), 3);
+        assert_eq!(model.sets_order[0], *first.uuid());
+        assert_eq!(model.sets_order[2], *second.uuid());
+
+        let copy_uuid = model.sets_order[1];
+        assert_ne!(copy_uuid, *first.uuid());
+        assert_eq!(model.sets.get(&copy_uuid).unwrap().name(), "Copy of Kit");
+        assert_eq!(
+            model.sets_cover_images.get(&copy_uuid),
+            Some(&"cover.png".to_string())
+        );
+    }
+
+    #[test]
+    fn test_modified() {
+        let model = AppModel::new(None, None, None, None);
+        assert!(!model.modified());
+
+        let model = model.add_sampleset(SampleSet::BaseSampleSet(BaseSampleSet::new(
+            "Favorites".to_string(),
+        )));
+        assert!(model.modified());
+        assert!(model.modified_since_autosave());
+
+        let model = model.mark_autosaved();
+        assert!(model.modified());
+        assert!(!model.modified_since_autosave());
+
+        let model = model.mark_saved();
+        assert!(!model.modified());
+    }
+
+    #[test]
+    fn test_sample_playback_behavior_override() {
+        let config = AppConfig {
+            sample_playback_behavior: SamplePlaybackBehavior::PlaySingleSample,
+            ..AppConfig::default()
+        };
+
+        let model = AppModel::new(Some(config), None, None, None);
+        assert_eq!(
+            model.sample_playback_behavior(),
+            SamplePlaybackBehavior::PlaySingleSample
+        );
+
+        let model = AppModel {
+            sample_playback_behavior_override: Some(SamplePlaybackBehavior::PlayUntilEnd),
+            ..model
+        };
+        assert_eq!(
+            model.sample_playback_behavior(),
+            SamplePlaybackBehavior::PlayUntilEnd
+        );
+        assert!(model.modified());
+    }
 }
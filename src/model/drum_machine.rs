@@ -4,22 +4,119 @@
 
 use std::{
     cell::RefCell,
+    collections::HashMap,
     rc::Rc,
     sync::mpsc::{self, Sender},
+    time::{Duration, Instant},
 };
 
+use anyhow::anyhow;
 use libasampo::sequences::{
     drumkit_render_thread, DrumkitSequence, DrumkitSequenceEvent, NoteLength, StepSequenceOps,
     TimeSpec,
 };
 
+use crate::{config::VelocityCurve, view::sequences::LABELS};
+
 #[derive(Clone, Debug)]
 pub struct DrumMachineModel {
     pub render_thread_tx: Option<Sender<drumkit_render_thread::Message>>,
     pub event_rx: Option<Rc<RefCell<single_value_channel::Receiver<Option<DrumkitSequenceEvent>>>>>,
     pub event_latest: Option<DrumkitSequenceEvent>,
+
+    /// When [`DrumMachineModel::event_latest`] was received.
+    pub event_latest_at: Option<Instant>,
     pub sequence: DrumkitSequence,
     pub activated_pad: usize,
+    pub tempo: u16,
+    pub swing: u32,
+    pub metronome: bool,
+
+    /// Whether playback has been started with the play button and not stopped since.
+    pub playing: bool,
+
+    /// Output level of the drum machine in percent, applied to the triggers as they are sent
+    /// to the render thread rather than stored in the sequence.
+    pub volume: u32,
+
+    /// Index into [`VELOCITY_LEVELS`] for each triggered (step, pad) pair.
+    pub step_velocities: HashMap<(usize, usize), usize>,
+}
+
+/// How long after its latest step the drum machine is considered to have stopped playing.
+pub const PLAYBACK_IDLE_AFTER: Duration = Duration::from_millis(500);
+
+pub const VELOCITY_LEVELS: [f32; 3] = [0.25, 0.5, 1.0];
+pub const DEFAULT_VELOCITY_LEVEL: usize = 1;
+
+/// Amplitude of a velocity level after applying a velocity curve.
+pub fn velocity_amp(level: usize, curve: &VelocityCurve) -> f32 {
+    curve.apply(VELOCITY_LEVELS[level])
+}
+
+const METRONOME_RATE: u32 = 44100;
+const METRONOME_CLICK_MILLIS: u32 = 30;
+
+/// A short, decaying sine blip as a 16-bit mono WAV file, higher pitched for the accented
+/// first beat of the bar.
+pub fn metronome_click_wav(accent: bool) -> Vec<u8> {
+    let mut wav = wav_header(METRONOME_RATE * METRONOME_CLICK_MILLIS / 1000);
+    wav.extend(click_frames(accent).flat_map(i16::to_le_bytes));
+    wav
+}
+
+const OUTPUT_TEST_CLICKS: u32 = 8;
+const OUTPUT_TEST_INTERVAL_MILLIS: u32 = 250;
+
+/// A bar of evenly spaced metronome clicks, twice over, as a 16-bit mono WAV file. Gaps or
+/// crackles while it plays suggest that the audio buffer is too small.
+pub fn output_test_wav() -> Vec<u8> {
+    let interval = METRONOME_RATE * OUTPUT_TEST_INTERVAL_MILLIS / 1000;
+    let mut wav = wav_header(interval * OUTPUT_TEST_CLICKS);
+
+    for i in 0..OUTPUT_TEST_CLICKS {
+        let click = click_frames(i % 4 == 0).collect::<Vec<i16>>();
+        let silence = std::iter::repeat(0i16).take(interval as usize - click.len());
+
+        wav.extend(click.into_iter().chain(silence).flat_map(i16::to_le_bytes));
+    }
+
+    wav
+}
+
+fn click_frames(accent: bool) -> impl Iterator<Item = i16> {
+    let frequency = if accent { 2000.0 } else { 1500.0 };
+    let num_frames = METRONOME_RATE * METRONOME_CLICK_MILLIS / 1000;
+
+    (0..num_frames).map(move |n| {
+        let t = n as f32 / METRONOME_RATE as f32;
+        let envelope = 1.0 - n as f32 / num_frames as f32;
+        let value = (t * frequency * std::f32::consts::TAU).sin() * envelope * envelope * 0.5;
+
+        (value * i16::MAX as f32) as i16
+    })
+}
+
+/// The header of a 16-bit mono WAV file of `num_frames` frames.
+fn wav_header(num_frames: u32) -> Vec<u8> {
+    let data_len = num_frames * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&METRONOME_RATE.to_le_bytes());
+    wav.extend_from_slice(&(METRONOME_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    wav
 }
 
 impl PartialEq for DrumMachineModel {
@@ -34,7 +131,11 @@ impl PartialEq for DrumMachineModel {
             _ => return false,
         }
 
-        if self.activated_pad != other.activated_pad || self.sequence != other.sequence {
+        if self.activated_pad != other.activated_pad
+            || self.playing != other.playing
+            || self.sequence != other.sequence
+            || self.step_velocities != other.step_velocities
+        {
             return false;
         }
 
@@ -55,9 +156,72 @@ impl DrumMachineModel {
             render_thread_tx,
             event_rx: event_rx.map(|x| Rc::new(RefCell::new(x))),
             event_latest: None,
+            event_latest_at: None,
             sequence: empty_sequence,
             activated_pad: 8,
+            tempo: 120,
+            swing: 0,
+            metronome: false,
+            playing: false,
+            volume: 100,
+            step_velocities: HashMap::new(),
+        }
+    }
+
+    /// Whether the drum machine has played a step within [`PLAYBACK_IDLE_AFTER`].
+    pub fn is_playing(&self) -> bool {
+        self.event_latest_at
+            .is_some_and(|t| t.elapsed() < PLAYBACK_IDLE_AFTER)
+    }
+
+    pub fn velocity_level(&self, step: usize, pad: usize) -> usize {
+        *self
+            .step_velocities
+            .get(&(step, pad))
+            .unwrap_or(&DEFAULT_VELOCITY_LEVEL)
+    }
+
+    /// Amplitude of a trigger at a velocity level, as stored in the sequence.
+    pub fn trigger_amp(&self, level: usize, curve: &VelocityCurve) -> f32 {
+        velocity_amp(level, curve)
+    }
+
+    /// Amplitude to play a trigger of the sequence at, including the drum machine volume.
+    pub fn output_amp(&self, amp: f32) -> f32 {
+        amp * self.volume as f32 / 100.0
+    }
+
+    /// Send every trigger of the sequence to a render thread, at the current volume.
+    fn send_triggers(
+        &self,
+        render_thread_tx: &Sender<drumkit_render_thread::Message>,
+        curve: &VelocityCurve,
+    ) -> Result<(), anyhow::Error> {
+        let mut step = 0;
+
+        while let Some(labels) = self.sequence.labels_at_step(step) {
+            for label in labels.iter() {
+                let pad = LABELS
+                    .iter()
+                    .position(|x| x == label)
+                    .ok_or(anyhow!("Unknown drum machine label"))?;
+
+                render_thread_tx
+                    .send(drumkit_render_thread::Message::EditSequenceSetStepTrigger {
+                        step,
+                        label: *label,
+                        amp: self
+                            .output_amp(self.trigger_amp(self.velocity_level(step, pad), curve)),
+                    })
+                    .map_err(|e| {
+                        anyhow!("Failed sending update event to drum sequence render thread: {e}")
+                    })?;
+            }
+
+            step += 1;
         }
+
+        Ok(())
     }
 
     pub fn new_with_render_thread(audiothread_tx: mpsc::Sender<audiothread::Message>) -> Self {
@@ -68,4 +232,90 @@ impl DrumMachineModel {
 
         Self::new(Some(render_tx), Some(event_rx))
     }
+
+    /// Spawn a new render thread for this drum machine, e.g after the audiothread has been
+    /// respawned, and bring it up to date with the current tempo, swing and sequence.
+    pub fn with_new_render_thread(
+        self,
+        audiothread_tx: mpsc::Sender<audiothread::Message>,
+        curve: &VelocityCurve,
+    ) -> Result<Self, anyhow::Error> {
+        let respawned = Self::new_with_render_thread(audiothread_tx);
+
+        let render_thread_tx = respawned
+            .render_thread_tx
+            .clone()
+            .ok_or(anyhow!("Drum machine render thread should be running"))?;
+
+        let send = |message| {
+            render_thread_tx.send(message).map_err(|e| {
+                anyhow!("Failed sending update event to drum sequence render thread: {e}")
+            })
+        };
+
+        send(drumkit_render_thread::Message::SetTempo(
+            self.tempo.try_into()?,
+        ))?;
+
+        send(drumkit_render_thread::Message::SetSwing(
+            (self.swing as f64 / 100.0).try_into()?,
+        ))?;
+
+        self.send_triggers(&render_thread_tx, curve)?;
+
+        Ok(Self {
+            render_thread_tx: respawned.render_thread_tx,
+            event_rx: respawned.event_rx,
+            event_latest: None,
+            event_latest_at: None,
+            ..self
+        })
+    }
+
+    /// Change the volume of the drum machine, leaving the sequence as it is.
+    pub fn with_volume(self, volume: u32, curve: &VelocityCurve) -> Result<Self, anyhow::Error> {
+        let result = Self { volume, ..self };
+
+        if let Some(render_thread_tx) = &result.render_thread_tx {
+            result.send_triggers(render_thread_tx, curve)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Re-apply the velocity levels of all triggered steps, e.g. using a new velocity curve.
+    pub fn with_velocity_curve(self, curve: &VelocityCurve) -> Result<Self, anyhow::Error> {
+        let mut sequence = self.sequence.clone();
+        let mut step = 0;
+
+        while let Some(labels) = self.sequence.labels_at_step(step) {
+            for label in labels.iter() {
+                let pad = LABELS
+                    .iter()
+                    .position(|x| x == label)
+                    .ok_or(anyhow!("Unknown drum machine label"))?;
+
+                let amp = self.trigger_amp(self.velocity_level(step, pad), curve);
+                sequence.set_step_trigger(step, *label, amp);
+
+                if let Some(render_thread_tx) = &self.render_thread_tx {
+                    render_thread_tx
+                        .send(drumkit_render_thread::Message::EditSequenceSetStepTrigger {
+                            step,
+                            label: *label,
+                            amp: self.output_amp(amp),
+                        })
+                        .map_err(|e| {
+                            anyhow!(
+                                "Failed sending update event to drum sequence render thread: {e}"
+                            )
+                        })?;
+                }
+            }
+
+            step += 1;
+        }
+
+        Ok(Self { sequence, ..self })
+    }
 }
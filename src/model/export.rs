@@ -0,0 +1,142 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use anyhow::anyhow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Set,
+    Name,
+    Index,
+}
+
+/// A template for the names of exported files, e.g. `{set}/{index}_{name}`, which may put
+/// the files in subdirectories of the export directory. The extension of each file is added
+/// to the expanded template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilenameTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl FilenameTemplate {
+    /// Parse and validate a template, checking that it only uses known placeholders, that it
+    /// gives each file a name of its own and that it stays within the export directory.
+    pub fn parse(template: &str) -> Result<FilenameTemplate, anyhow::Error> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while !rest.is_empty() {
+            match rest.find(['{', '}']) {
+                Some(0) if rest.starts_with('}') => {
+                    return Err(anyhow!("Unexpected '}}' in file name template"))
+                }
+
+                Some(0) => {
+                    let end = rest
+                        .find('}')
+                        .ok_or(anyhow!("Unclosed '{{' in file name template"))?;
+
+                    parts.push(match &rest[1..end] {
+                        "set" => TemplatePart::Set,
+                        "name" => TemplatePart::Name,
+                        "index" => TemplatePart::Index,
+                        other => {
+                            return Err(anyhow!(
+                                "Unknown placeholder {{{other}}}, use {{set}}, {{name}} or \
+                                {{index}}"
+                            ))
+                        }
+                    });
+
+                    rest = &rest[end + 1..];
+                }
+
+                Some(i) => {
+                    parts.push(TemplatePart::Text(rest[..i].to_string()));
+                    rest = &rest[i..];
+                }
+
+                None => {
+                    parts.push(TemplatePart::Text(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+
+        if !parts
+            .iter()
+            .any(|part| matches!(part, TemplatePart::Name | TemplatePart::Index))
+        {
+            return Err(anyhow!(
+                "The file name template needs {{name}} or {{index}} to tell the files apart"
+            ));
+        }
+
+        let text = parts
+            .iter()
+            .filter_map(|part| match part {
+                TemplatePart::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+
+        if text.contains('\\') {
+            return Err(anyhow!("Use '/' to separate directories in file names"));
+        }
+
+        let template = FilenameTemplate { parts };
+        let expanded = template.expand("set", "name", 1, 1);
+
+        if expanded.starts_with('/')
+            || expanded
+                .split('/')
+                .any(|component| component.is_empty() || component == "." || component == "..")
+        {
+            return Err(anyhow!("File names must stay within the export directory"));
+        }
+
+        Ok(template)
+    }
+
+    /// The file name, without extension, of the member at `index` (counting from 0) of the
+    /// `count` exported members of a sample set. Indices are counted from 1 and padded with
+    /// zeros so that the files sort in set order.
+    pub fn expand(&self, set: &str, name: &str, index: usize, count: usize) -> String {
+        let width = count.max(1).to_string().len();
+        let sanitized = |value: &str| value.replace(['/', '\\'], "_");
+
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Text(text) => text.clone(),
+                TemplatePart::Set => sanitized(set),
+                TemplatePart::Name => sanitized(name),
+                TemplatePart::Index => format!("{:0width$}", index + 1),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filename_template() {
+        let template = FilenameTemplate::parse("{set}/{index}_{name}").unwrap();
+
+        assert_eq!(template.expand("Kit", "kick", 0, 12), "Kit/01_kick");
+        assert_eq!(template.expand("A/B", "snare", 11, 12), "A_B/12_snare");
+
+        assert!(FilenameTemplate::parse("{name").is_err());
+        assert!(FilenameTemplate::parse("name}").is_err());
+        assert!(FilenameTemplate::parse("{label}_{name}").is_err());
+        assert!(FilenameTemplate::parse("{set}").is_err());
+        assert!(FilenameTemplate::parse("/tmp/{name}").is_err());
+        assert!(FilenameTemplate::parse("../{name}").is_err());
+        assert!(FilenameTemplate::parse("{set}//{name}").is_err());
+        assert!(FilenameTemplate::parse("{set}\\{name}").is_err());
+    }
+}
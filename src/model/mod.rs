@@ -13,13 +13,21 @@ use libasampo::{
 
 mod app;
 mod drum_machine;
+mod export;
 mod view;
 
 pub mod util;
 
-pub use app::{AppModel, AppModelOps, AppModelPtr, ExportState};
-pub use drum_machine::DrumMachineModel;
-pub use view::{ExportKind, ViewFlags, ViewModelOps, ViewValues};
+pub use app::{
+    AppModel, AppModelOps, AppModelPtr, CompareSlot, ExportState, KitImport, LoadInProgress,
+    SaveInProgress, SavefileLockConflict, SavefileReview,
+};
+pub use drum_machine::{
+    metronome_click_wav, output_test_wav, DrumMachineModel, DEFAULT_VELOCITY_LEVEL,
+    PLAYBACK_IDLE_AFTER, VELOCITY_LEVELS,
+};
+pub use export::FilenameTemplate;
+pub use view::{ExportKind, SampleListId, SampleListValues, ViewFlags, ViewModelOps, ViewValues};
 
 pub fn sources_add_fs_fields_valid(model: &AppModel) -> bool {
     !(model.viewvalues.sources_add_fs_name_entry.is_empty()
@@ -61,7 +69,10 @@ impl ModelOps for AppModel {
                 .map(|s| s.trim().to_string())
                 .collect();
 
-            self.add_file_system_source(name, path, exts)
+            Ok(self
+                .add_file_system_source(name, path, exts)?
+                .clear_sources_add_fs_fields()
+                .set_is_sources_add_fs_fields_valid(false))
         } else {
             Err(anyhow!(
                 "Failed to commit file system source: invalid field(s)"
@@ -88,8 +99,6 @@ impl ModelOps for AppModel {
         self.init_source_sample_count(uuid)?
             .add_source(new_source.clone())?
             .enable_source(&uuid)?
-            .clear_sources_add_fs_fields()
-            .set_is_sources_add_fs_fields_valid(false)
             .add_source_loader(uuid, loader_rx)
     }
 
@@ -2,16 +2,92 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::Hasher,
+    io::Read,
+};
+
 use anyhow::anyhow;
 
 use libasampo::{
-    samples::SampleOps,
+    samples::{Sample, SampleOps},
     samplesets::{BaseSampleSet, SampleSet, SampleSetOps},
+    sources::{Source, SourceOps},
 };
 use uuid::Uuid;
 
 use crate::model::{AppModel, ViewFlags};
 
+/// Group samples having identical content, as determined by hashing the sample streams.
+/// Only groups of two or more samples are returned, each in the order the samples were given.
+pub fn find_duplicate_samples(
+    sources: &HashMap<Uuid, Source>,
+    samples: &[Sample],
+) -> Vec<Vec<Sample>> {
+    let mut groups: HashMap<(u64, u64), Vec<Sample>> = HashMap::new();
+    let mut order: Vec<(u64, u64)> = Vec::new();
+
+    for sample in samples {
+        let key = match content_hash(sources, sample) {
+            Ok(key) => key,
+            Err(e) => {
+                log::log!(
+                    log::Level::Warn,
+                    "Unable to hash sample {}: {e}",
+                    sample.uri().as_str()
+                );
+                continue;
+            }
+        };
+
+        groups
+            .entry(key)
+            .or_insert_with(|| {
+                order.push(key);
+                Vec::new()
+            })
+            .push(sample.clone());
+    }
+
+    order
+        .into_iter()
+        .filter_map(|key| groups.remove(&key))
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+fn content_hash(
+    sources: &HashMap<Uuid, Source>,
+    sample: &Sample,
+) -> Result<(u64, u64), anyhow::Error> {
+    let mut stream = sources
+        .get(
+            sample
+                .source_uuid()
+                .ok_or(anyhow!("Sample missing source uuid"))?,
+        )
+        .ok_or(anyhow!("Failed to get source for sample"))?
+        .stream(sample)?;
+
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = [0u8; 65536];
+    let mut len = 0u64;
+
+    loop {
+        let n = stream.read(&mut buffer)?;
+
+        if n == 0 {
+            break;
+        }
+
+        hasher.write(&buffer[0..n]);
+        len += n as u64;
+    }
+
+    Ok((hasher.finish(), len))
+}
+
 pub fn get_or_create_sampleset(
     model: AppModel,
     name: String,
@@ -32,31 +108,37 @@ pub fn get_or_create_sampleset(
     }
 }
 
-pub fn add_selected_sample_to_sampleset_by_uuid(
+pub fn add_selected_samples_to_sampleset_by_uuid(
     model: AppModel,
     uuid: &Uuid,
 ) -> Result<AppModel, anyhow::Error> {
-    let sample = model
-        .samplelist_selected_sample
-        .as_ref()
-        .ok_or(anyhow!("No selected sample"))?;
-
-    let source = model
-        .sources
-        .get(
-            sample
-                .source_uuid()
-                .ok_or(anyhow!("Selected sample has no source"))?,
-        )
-        .ok_or(anyhow!("Could not obtain source for selected sample"))?;
+    if model.samplelist_selected_samples.is_empty() {
+        return Err(anyhow!("No selected sample"));
+    }
 
     let mut model = model.clone();
 
-    model
+    let set = model
         .sets
         .get_mut(uuid)
-        .ok_or(anyhow!("Sample set not found (by uuid)"))?
-        .add(source, sample.clone())?;
+        .ok_or(anyhow!("Sample set not found (by uuid)"))?;
+
+    for sample in model.samplelist_selected_samples.iter() {
+        if set.contains(sample) {
+            continue;
+        }
+
+        let source = model
+            .sources
+            .get(
+                sample
+                    .source_uuid()
+                    .ok_or(anyhow!("Selected sample has no source"))?,
+            )
+            .ok_or(anyhow!("Could not obtain source for selected sample"))?;
+
+        set.add(source, sample.clone())?;
+    }
 
     Ok(AppModel {
         viewflags: ViewFlags {
@@ -64,6 +146,15 @@ pub fn add_selected_sample_to_sampleset_by_uuid(
             ..model.viewflags
         },
         sets_most_recently_used_uuid: Some(*uuid),
+        sets_recently_used: std::iter::once(*uuid)
+            .chain(
+                model
+                    .sets_recently_used
+                    .iter()
+                    .filter(|set_uuid| *set_uuid != uuid)
+                    .copied(),
+            )
+            .collect(),
         ..model
     })
 }
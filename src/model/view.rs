@@ -2,17 +2,26 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use anyhow::anyhow;
-use gtk::gio::ListStore;
+use gtk::{
+    gio::{self, ListStore},
+    glib,
+    prelude::*,
+};
+use libasampo::samples::{Sample, SampleOps};
 use uuid::Uuid;
 
 use crate::{
     config::AppConfig,
-    ext::ClonedHashMapExt,
+    ext::{ClonedHashMapExt, ClonedVecExt},
     model::{AppModel, ModelResult},
-    view::{dialogs, samples::SampleListEntry, sequences::DrumMachineView},
+    view::{
+        dialogs,
+        samples::{SampleListEntry, SampleListGroup},
+        sequences::DrumMachineView,
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -20,13 +29,32 @@ pub struct ViewFlags {
     pub view_sensitive: bool,
     pub sources_add_fs_fields_valid: bool,
     pub sources_add_fs_begin_browse: bool,
+    pub sources_add_fs_show_preview: bool,
     pub samples_sidebar_add_to_set_show_dialog: bool,
+    pub samples_sidebar_rename_show_dialog: bool,
+    pub samples_sidebar_add_to_set_show_popover: bool,
+    pub samples_searches_show_popover: bool,
+    pub samples_history_show_popover: bool,
+    pub samples_save_search_show_dialog: bool,
+    pub samples_duplicates_show_dialog: bool,
+    pub samples_reveal_sample: bool,
+    pub samples_open_folder: bool,
     pub samples_sidebar_add_to_prev_enabled: bool,
     pub sets_add_set_show_dialog: bool,
+    pub sets_rename_set_show_dialog: bool,
     pub sets_export_enabled: bool,
     pub sets_export_show_dialog: bool,
     pub sets_export_begin_browse: bool,
     pub sets_export_fields_valid: bool,
+    pub sets_export_errors_show_dialog: bool,
+    pub sets_cover_begin_browse: bool,
+    pub savefile_save_password_show_dialog: bool,
+    pub savefile_load_password_show_dialog: bool,
+    pub savefile_locked_show_dialog: bool,
+    pub savefile_review_show_dialog: bool,
+    pub settings_pad_labels_show_dialog: bool,
+    pub settings_audio_profile_save_show_dialog: bool,
+    pub settings_import_show_dialog: bool,
 }
 
 impl Default for ViewFlags {
@@ -35,13 +63,32 @@ impl Default for ViewFlags {
             view_sensitive: true,
             sources_add_fs_fields_valid: false,
             sources_add_fs_begin_browse: false,
+            sources_add_fs_show_preview: false,
             samples_sidebar_add_to_set_show_dialog: false,
+            samples_sidebar_rename_show_dialog: false,
+            samples_sidebar_add_to_set_show_popover: false,
+            samples_searches_show_popover: false,
+            samples_history_show_popover: false,
+            samples_save_search_show_dialog: false,
+            samples_duplicates_show_dialog: false,
+            samples_reveal_sample: false,
+            samples_open_folder: false,
             samples_sidebar_add_to_prev_enabled: false,
             sets_add_set_show_dialog: false,
+            sets_rename_set_show_dialog: false,
             sets_export_enabled: false,
             sets_export_show_dialog: false,
             sets_export_begin_browse: false,
             sets_export_fields_valid: false,
+            sets_export_errors_show_dialog: false,
+            sets_cover_begin_browse: false,
+            savefile_save_password_show_dialog: false,
+            savefile_load_password_show_dialog: false,
+            savefile_locked_show_dialog: false,
+            savefile_review_show_dialog: false,
+            settings_pad_labels_show_dialog: false,
+            settings_audio_profile_save_show_dialog: false,
+            settings_import_show_dialog: false,
         }
     }
 }
@@ -50,6 +97,198 @@ impl Default for ViewFlags {
 pub enum ExportKind {
     PlainCopy,
     Conversion,
+
+    /// Convert to WAV and write an SFZ instrument playing the samples from consecutive keys.
+    SfzInstrument,
+}
+
+/// Identifies one of the sample lists on the samples page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleListId {
+    Main,
+    Split,
+}
+
+/// The filter and contents of one sample list on the samples page.
+#[derive(Debug, Clone)]
+pub struct SampleListValues {
+    pub filter: String,
+    pub listview_model: ListStore,
+    pub listview_fill: Rc<RefCell<Option<glib::SourceId>>>,
+
+    /// One [`SampleListGroup`] per source, for showing the list grouped by source.
+    pub groups: ListStore,
+    pub tree_model: gtk::TreeListModel,
+}
+
+impl Default for SampleListValues {
+    fn default() -> Self {
+        let groups = ListStore::new::<SampleListGroup>();
+
+        let tree_model = gtk::TreeListModel::new(groups.clone(), false, true, |item| {
+            item.downcast_ref::<SampleListGroup>()
+                .and_then(|group| group.children.borrow().clone())
+        });
+
+        SampleListValues {
+            filter: String::default(),
+            listview_model: ListStore::new::<SampleListEntry>(),
+            listview_fill: Rc::new(RefCell::new(None)),
+            groups,
+            tree_model,
+        }
+    }
+}
+
+impl SampleListValues {
+    pub fn with_filter(self, filter: impl Into<String>) -> Self {
+        SampleListValues {
+            filter: filter.into(),
+            ..self
+        }
+    }
+
+    /// The model shown in the list view, with or without grouping by source.
+    pub fn display_model(&self, grouped: bool) -> gio::ListModel {
+        match grouped {
+            true => self.tree_model.clone().upcast(),
+            false => self.listview_model.clone().upcast(),
+        }
+    }
+
+    /// The sample entry shown at a position in the list view, if that position holds a
+    /// sample rather than a group header.
+    pub fn entry(&self, position: u32, grouped: bool) -> Option<SampleListEntry> {
+        let item = self.display_model(grouped).item(position);
+
+        match grouped {
+            true => item
+                .and_downcast::<gtk::TreeListRow>()?
+                .item()
+                .and_downcast(),
+            false => item.and_downcast(),
+        }
+    }
+
+    /// The position of a sample in the list view, if shown.
+    pub fn position_of(&self, uri: &str, grouped: bool) -> Option<u32> {
+        (0..self.display_model(grouped).n_items()).find(|n| {
+            self.entry(*n, grouped)
+                .is_some_and(|entry| entry.value.borrow().uri().as_str() == uri)
+        })
+    }
+
+    /// Set the sources to group the list by, given as (uuid, name) pairs. Each group shows
+    /// the listed samples of its source, and follows changes to the list.
+    pub fn set_groups(&self, sources: Vec<(Uuid, String)>) {
+        let current = (0..self.groups.n_items())
+            .filter_map(|n| self.groups.item(n).and_downcast::<SampleListGroup>())
+            .map(|group| (*group.uuid.borrow(), group.name.borrow().clone()))
+            .collect::<Vec<_>>();
+
+        if current == sources {
+            return;
+        }
+
+        let groups = sources
+            .into_iter()
+            .map(|(uuid, name)| {
+                let filter = gtk::CustomFilter::new(move |item| {
+                    item.downcast_ref::<SampleListEntry>()
+                        .is_some_and(|entry| entry.value.borrow().source_uuid() == Some(&uuid))
+                });
+
+                let children =
+                    gtk::FilterListModel::new(Some(self.listview_model.clone()), Some(filter));
+
+                SampleListGroup::new(uuid, &name, children.upcast())
+            })
+            .collect::<Vec<_>>();
+
+        self.groups.splice(0, self.groups.n_items(), &groups);
+    }
+
+    /// Replace the contents of the list. Large lists are filled in chunks from an idle
+    /// callback so that the UI stays responsive, and any fill still in progress from a
+    /// previous call is abandoned.
+    pub fn fill(&self, entries: Vec<SampleListEntry>) {
+        self.splice(0, self.listview_model.n_items(), entries);
+    }
+
+    /// Replace the contents of the list, leaving entries that are unchanged at the start and
+    /// end of the list in place so that the scroll position and selection are kept, e.g when
+    /// samples are appended while a source is loading.
+    pub fn update(&self, entries: Vec<SampleListEntry>) {
+        let current = (0..self.listview_model.n_items())
+            .filter_map(|n| {
+                self.listview_model
+                    .item(n)
+                    .and_downcast::<SampleListEntry>()
+            })
+            .collect::<Vec<_>>();
+
+        let same = |(a, b): &(&SampleListEntry, &SampleListEntry)| {
+            a.value.borrow().uri().as_str() == b.value.borrow().uri().as_str()
+                && *a.highlight.borrow() == *b.highlight.borrow()
+                && *a.display_name.borrow() == *b.display_name.borrow()
+        };
+
+        let prefix = current.iter().zip(entries.iter()).take_while(same).count();
+
+        let suffix = current[prefix..]
+            .iter()
+            .rev()
+            .zip(entries[prefix..].iter().rev())
+            .take_while(same)
+            .count();
+
+        let removals = current.len() - prefix - suffix;
+        let additions = entries[prefix..entries.len() - suffix].to_vec();
+
+        if removals > 0 || !additions.is_empty() {
+            self.splice(prefix as u32, removals as u32, additions);
+        }
+    }
+
+    fn splice(&self, position: u32, n_removals: u32, entries: Vec<SampleListEntry>) {
+        if let Some(source_id) = self.listview_fill.take() {
+            source_id.remove();
+        }
+
+        let mut entries = entries.into_iter();
+        let first = entries
+            .by_ref()
+            .take(SAMPLES_LISTVIEW_FILL_CHUNK)
+            .collect::<Vec<_>>();
+
+        self.listview_model.splice(position, n_removals, &first);
+
+        if entries.len() == 0 {
+            return;
+        }
+
+        let listview_model = self.listview_model.clone();
+        let fill = self.listview_fill.clone();
+        let mut position = position + first.len() as u32;
+
+        self.listview_fill
+            .replace(Some(glib::idle_add_local(move || {
+                let chunk = entries
+                    .by_ref()
+                    .take(SAMPLES_LISTVIEW_FILL_CHUNK)
+                    .collect::<Vec<_>>();
+
+                listview_model.splice(position, 0, &chunk);
+                position += chunk.len() as u32;
+
+                if entries.len() == 0 {
+                    fill.take();
+                    glib::ControlFlow::Break
+                } else {
+                    glib::ControlFlow::Continue
+                }
+            })));
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -57,14 +296,23 @@ pub struct ViewValues {
     pub sources_add_fs_name_entry: String,
     pub sources_add_fs_path_entry: String,
     pub sources_add_fs_extensions_entry: String,
+    pub sources_add_fs_preview_files: Vec<String>,
     pub sources_sample_count: HashMap<Uuid, usize>,
-    pub samples_list_filter: String,
+    pub samples_list: SampleListValues,
+    pub samples_split_list: SampleListValues,
+    pub samples_reveal: Option<Sample>,
     pub settings_latency_approx_label: String,
-    pub samples_listview_model: ListStore,
     pub sets_export_dialog_view: Option<dialogs::ExportDialogView>,
     pub sets_export_target_dir_entry: String,
     pub sets_export_kind: Option<ExportKind>,
+    pub sets_export_filename_template_entry: String,
+
+    /// Why the file name template in the export dialog is invalid, if it is.
+    pub sets_export_filename_template_error: Option<String>,
     pub drum_machine: Option<DrumMachineView>,
+
+    /// Short messages waiting to be shown as toasts.
+    pub toasts: Vec<String>,
 }
 
 impl Default for ViewValues {
@@ -73,14 +321,19 @@ impl Default for ViewValues {
             sources_add_fs_name_entry: String::default(),
             sources_add_fs_path_entry: String::default(),
             sources_add_fs_extensions_entry: String::default(),
+            sources_add_fs_preview_files: Vec::new(),
             sources_sample_count: HashMap::new(),
-            samples_list_filter: String::default(),
+            samples_list: SampleListValues::default(),
+            samples_split_list: SampleListValues::default(),
+            samples_reveal: None,
             settings_latency_approx_label: String::default(),
-            samples_listview_model: ListStore::new::<SampleListEntry>(),
             sets_export_dialog_view: None,
             sets_export_target_dir_entry: String::default(),
             sets_export_kind: None,
+            sets_export_filename_template_entry: String::default(),
+            sets_export_filename_template_error: None,
             drum_machine: None,
+            toasts: Vec::new(),
         }
     }
 }
@@ -95,8 +348,30 @@ impl ViewValues {
             ..Self::default()
         }
     }
+
+    pub fn samples_list(&self, id: SampleListId) -> &SampleListValues {
+        match id {
+            SampleListId::Main => &self.samples_list,
+            SampleListId::Split => &self.samples_split_list,
+        }
+    }
+
+    pub fn with_samples_list(self, id: SampleListId, list: SampleListValues) -> Self {
+        match id {
+            SampleListId::Main => ViewValues {
+                samples_list: list,
+                ..self
+            },
+            SampleListId::Split => ViewValues {
+                samples_split_list: list,
+                ..self
+            },
+        }
+    }
 }
 
+const SAMPLES_LISTVIEW_FILL_CHUNK: usize = 2000;
+
 pub trait ViewModelOps {
     fn set_latency_approx_label(self, text: String) -> AppModel;
     fn set_latency_approx_label_by_config(self, config: &AppConfig) -> AppModel;
@@ -110,6 +385,8 @@ pub trait ViewModelOps {
     fn set_sources_add_fs_extensions_entry(self, text: impl Into<String>) -> AppModel;
     fn signal_sources_add_fs_begin_browse(self) -> AppModel;
     fn clear_signal_sources_add_fs_begin_browse(self) -> AppModel;
+    fn show_toast(self, text: impl Into<String>) -> AppModel;
+    fn clear_toasts(self) -> AppModel;
 }
 
 impl ViewModelOps for AppModel {
@@ -264,4 +541,24 @@ impl ViewModelOps for AppModel {
             ..self
         }
     }
+
+    fn show_toast(self, text: impl Into<String>) -> AppModel {
+        AppModel {
+            viewvalues: ViewValues {
+                toasts: self.viewvalues.toasts.clone_and_push(text.into()),
+                ..self.viewvalues
+            },
+            ..self
+        }
+    }
+
+    fn clear_toasts(self) -> AppModel {
+        AppModel {
+            viewvalues: ViewValues {
+                toasts: Vec::new(),
+                ..self.viewvalues
+            },
+            ..self
+        }
+    }
 }
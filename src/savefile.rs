@@ -2,42 +2,133 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
-use std::{io::Write, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::Path,
+    sync::mpsc,
+};
 
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::anyhow;
+use argon2::Argon2;
 use libasampo::{
     self as la,
     prelude::*,
+    samplesets::SampleSet,
     serialize::{TryFromDomain, TryIntoDomain},
+    sources::Source,
 };
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::model::AppModel;
+use crate::{config::SamplePlaybackBehavior, model::AppModel};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum SavefileSamplePlaybackBehavior {
+    PlaySingleSample,
+    PlayUntilEnd,
+}
+
+impl From<&SamplePlaybackBehavior> for SavefileSamplePlaybackBehavior {
+    fn from(value: &SamplePlaybackBehavior) -> Self {
+        match value {
+            SamplePlaybackBehavior::PlaySingleSample => Self::PlaySingleSample,
+            SamplePlaybackBehavior::PlayUntilEnd => Self::PlayUntilEnd,
+        }
+    }
+}
+
+impl From<SavefileSamplePlaybackBehavior> for SamplePlaybackBehavior {
+    fn from(value: SavefileSamplePlaybackBehavior) -> Self {
+        match value {
+            SavefileSamplePlaybackBehavior::PlaySingleSample => Self::PlaySingleSample,
+            SavefileSamplePlaybackBehavior::PlayUntilEnd => Self::PlayUntilEnd,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavefileV1 {
     sources: Vec<la::serialize::Source>,
     samplesets: Vec<la::serialize::SampleSet>,
+
+    #[serde(default)]
+    sampleset_cover_images: HashMap<Uuid, String>,
+
+    #[serde(default)]
+    sampleset_sample_order: HashMap<Uuid, Vec<String>>,
+
+    #[serde(default)]
+    sample_playback_behavior: Option<SavefileSamplePlaybackBehavior>,
+
+    #[serde(default)]
+    sample_display_names: HashMap<String, String>,
+
+    #[serde(default)]
+    sampleset_export_excluded: HashMap<Uuid, HashSet<String>>,
+
+    #[serde(default)]
+    favorites_sampleset: Option<Uuid>,
 }
 
-impl SavefileV1 {
-    pub fn into_appmodel(self) -> Result<AppModel, anyhow::Error> {
-        let mut model = AppModel::new(None, None, None, None);
+/// The contents of a savefile with its sources and sample sets built, which unlike an
+/// [`AppModel`] can be sent between threads.
+#[derive(Debug)]
+pub struct LoadedWorkspace {
+    filename: String,
+    sources: Vec<Source>,
+    sets: Vec<SampleSet>,
+    savefile: SavefileV1,
+}
 
-        for src in self.sources {
-            let source = src.try_into_domain()?;
+impl LoadedWorkspace {
+    pub fn into_appmodel(self) -> AppModel {
+        let mut model = AppModel::new(None, Some(self.filename), None, None);
 
+        for source in self.sources {
             model.sources_order.push(*source.uuid());
             model.sources.insert(*source.uuid(), source);
         }
 
-        for set in self.samplesets {
-            let sampleset = set.try_into_domain()?;
-
+        for sampleset in self.sets {
             model.sets_order.push(*sampleset.uuid());
             model.sets.insert(*sampleset.uuid(), sampleset);
         }
 
-        Ok(model)
+        model.sets_cover_images = self.savefile.sampleset_cover_images;
+        model.sets_sample_order = self.savefile.sampleset_sample_order;
+        model.sample_playback_behavior_override =
+            self.savefile.sample_playback_behavior.map(Into::into);
+        model.samples_display_names = self.savefile.sample_display_names;
+        model.sets_export_excluded = self.savefile.sampleset_export_excluded;
+        model.sets_favorites = self.savefile.favorites_sampleset;
+
+        model
+    }
+}
+
+impl SavefileV1 {
+    fn into_workspace(mut self, filename: &str) -> Result<LoadedWorkspace, anyhow::Error> {
+        let sources = std::mem::take(&mut self.sources)
+            .into_iter()
+            .map(|src| src.try_into_domain())
+            .collect::<Result<Vec<Source>, la::errors::Error>>()?;
+
+        let sets = std::mem::take(&mut self.samplesets)
+            .into_iter()
+            .map(|set| set.try_into_domain())
+            .collect::<Result<Vec<SampleSet>, la::errors::Error>>()?;
+
+        Ok(LoadedWorkspace {
+            filename: filename.to_string(),
+            sources,
+            sets,
+            savefile: self,
+        })
     }
 
     pub fn from_appmodel(model: &AppModel) -> Result<SavefileV1, anyhow::Error> {
@@ -57,42 +148,374 @@ impl SavefileV1 {
                     la::serialize::SampleSet::try_from_domain(model.sets.get(uuid).unwrap())
                 })
                 .collect::<Result<Vec<la::serialize::SampleSet>, la::errors::Error>>()?,
+
+            sampleset_cover_images: model.sets_cover_images.clone(),
+            sampleset_sample_order: model.sets_sample_order.clone(),
+
+            sample_playback_behavior: model
+                .sample_playback_behavior_override
+                .as_ref()
+                .map(Into::into),
+
+            sample_display_names: model.samples_display_names.clone(),
+            sampleset_export_excluded: model.sets_export_excluded.clone(),
+            favorites_sampleset: model.sets_favorites,
         })
     }
 }
 
+/// The savefile format version written by this version of Asampo. Savefiles are tagged with
+/// their format version (e.g `{"V1": {...}}`) and older formats are upgraded when loaded.
+pub const FORMAT_VERSION: u32 = 1;
+
+type Migration = fn(serde_json::Value) -> Result<serde_json::Value, anyhow::Error>;
+
+/// Upgrades between consecutive format versions, where `MIGRATIONS[n - 1]` turns a version
+/// `n` savefile into a version `n + 1` savefile.
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize - 1] = [];
+
+/// Returned when loading a savefile written in a format newer than [`FORMAT_VERSION`].
+#[derive(Debug)]
+pub struct NewerFormatError(pub u32);
+
+impl std::fmt::Display for NewerFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The savefile uses format version {}, but this version of Asampo only supports \
+                versions up to {FORMAT_VERSION}. Please update Asampo to open it.",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for NewerFormatError {}
+
+fn format_version(json: &serde_json::Value) -> Result<u32, anyhow::Error> {
+    json.as_object()
+        .filter(|obj| obj.len() == 1)
+        .and_then(|obj| obj.keys().next())
+        .and_then(|tag| tag.strip_prefix('V'))
+        .and_then(|version| version.parse::<u32>().ok())
+        .filter(|version| *version >= 1)
+        .ok_or(anyhow!("Not an Asampo savefile"))
+}
+
+fn migrate(json: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    let version = format_version(&json)?;
+
+    if version > FORMAT_VERSION {
+        return Err(NewerFormatError(version).into());
+    }
+
+    if version < FORMAT_VERSION {
+        log::log!(
+            log::Level::Info,
+            "Upgrading savefile from format version {version} to {FORMAT_VERSION}"
+        );
+    }
+
+    MIGRATIONS[(version - 1) as usize..]
+        .iter()
+        .try_fold(json, |json, migration| migration(json))
+}
+
+const ENCRYPTED_MAGIC: &[u8] = b"ASAMPO-ENCRYPTED-V1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>, anyhow::Error> {
+    let mut key = Key::<Aes256Gcm>::default();
+
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| anyhow!("Failed to derive key from password: {e}"))?;
+
+    Ok(key)
+}
+
+fn encrypt(plaintext: &[u8], password: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    let cipher = Aes256Gcm::new(&derive_key(password, &salt)?);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt savefile"))?;
+
+    let mut result =
+        Vec::with_capacity(ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+
+    result.extend_from_slice(ENCRYPTED_MAGIC);
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(nonce.as_slice());
+    result.extend_from_slice(&ciphertext);
+
+    Ok(result)
+}
+
+fn decrypt(data: &[u8], password: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let data = data
+        .strip_prefix(ENCRYPTED_MAGIC)
+        .ok_or(anyhow!("Savefile is not encrypted"))?;
+
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(anyhow!("Encrypted savefile is truncated"));
+    }
+
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    Aes256Gcm::new(&derive_key(password, salt)?)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Wrong password or corrupted savefile"))
+}
+
+fn write_savefile(filename: &str, data: &[u8]) -> Result<(), anyhow::Error> {
+    if let Some(path) = Path::new(filename).parent() {
+        std::fs::create_dir_all(path)?;
+    }
+
+    let mut fd = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(filename)?;
+
+    fd.write_all(data)?;
+
+    Ok(())
+}
+
+/// Location of the file that the workspace is periodically autosaved to.
+pub fn recovery_path() -> String {
+    dirs::data_local_dir()
+        .expect("System should have a common data dir")
+        .join("asampo")
+        .join("recovery.json")
+        .to_str()
+        .expect("Should be able to construct the recovery file path")
+        .to_string()
+}
+
+pub fn is_encrypted(filename: &str) -> Result<bool, anyhow::Error> {
+    Ok(std::fs::read(filename)?.starts_with(ENCRYPTED_MAGIC))
+}
+
+/// Progress reports from a savefile being saved on a worker thread.
+#[derive(Debug, Clone)]
+pub enum SaveJobMessage {
+    Encrypting,
+    Writing,
+    Error(String),
+    Finished,
+}
+
+/// Progress reports from a savefile being loaded on a worker thread.
+#[derive(Debug)]
+pub enum LoadJobMessage {
+    Decrypting,
+    Building,
+    Error(anyhow::Error),
+    Finished(Box<LoadedWorkspace>),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Savefile {
     V1(SavefileV1),
 }
 
 impl Savefile {
-    pub fn save(model: &AppModel, filename: &str) -> Result<(), anyhow::Error> {
-        let json = serde_json::to_string_pretty(&Savefile::V1(SavefileV1::from_appmodel(model)?))?;
+    fn to_json(model: &AppModel) -> Result<String, anyhow::Error> {
+        Ok(serde_json::to_string_pretty(&Savefile::V1(
+            SavefileV1::from_appmodel(model)?,
+        ))?)
+    }
 
-        if let Some(path) = Path::new(filename).parent() {
-            std::fs::create_dir_all(path)?;
+    fn parse(json: &str, filename: &str) -> Result<LoadedWorkspace, anyhow::Error> {
+        match serde_json::from_value::<Savefile>(migrate(serde_json::from_str(json)?)?)? {
+            Savefile::V1(sav) => sav.into_workspace(filename),
         }
+    }
+
+    fn from_json(json: &str, filename: &str) -> Result<AppModel, anyhow::Error> {
+        Ok(Self::parse(json, filename)?.into_appmodel())
+    }
 
-        {
-            let mut fd = std::fs::OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(filename)?;
+    pub fn save(model: &AppModel, filename: &str) -> Result<(), anyhow::Error> {
+        write_savefile(filename, Self::to_json(model)?.as_bytes())
+    }
 
-            fd.write_all(json.as_bytes())?;
-        }
+    /// Save `model` to `filename` on a worker thread, encrypting it if a password is given.
+    /// Only the conversion of the model into savefile form happens on the calling thread.
+    pub fn save_in_background(
+        model: &AppModel,
+        filename: &str,
+        password: Option<String>,
+        tx: mpsc::Sender<SaveJobMessage>,
+    ) -> Result<(), anyhow::Error> {
+        let savefile = Savefile::V1(SavefileV1::from_appmodel(model)?);
+        let filename = filename.to_string();
+
+        std::thread::spawn(move || {
+            let result = serde_json::to_string_pretty(&savefile)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| match password {
+                    Some(password) => {
+                        let _ = tx.send(SaveJobMessage::Encrypting);
+                        encrypt(json.as_bytes(), &password)
+                    }
+
+                    None => Ok(json.into_bytes()),
+                })
+                .and_then(|data| {
+                    let _ = tx.send(SaveJobMessage::Writing);
+                    write_savefile(&filename, &data)
+                });
+
+            let _ = tx.send(match result {
+                Ok(()) => SaveJobMessage::Finished,
+                Err(e) => SaveJobMessage::Error(e.to_string()),
+            });
+        });
 
         Ok(())
     }
 
     pub fn load(filename: &str) -> Result<AppModel, anyhow::Error> {
-        match serde_json::from_str::<Savefile>(&String::from_utf8(std::fs::read(filename)?)?)? {
-            Savefile::V1(sav) => Ok(AppModel {
-                savefile: Some(filename.to_string()),
-                ..sav.into_appmodel()?
-            }),
+        let data = std::fs::read(filename)?;
+
+        if data.starts_with(ENCRYPTED_MAGIC) {
+            return Err(anyhow!("Savefile is encrypted, a password is required"));
         }
+
+        Self::from_json(&String::from_utf8(data)?, filename)
+    }
+
+    /// Load `filename` on a worker thread, decrypting it if a password is given. The loaded
+    /// workspace is sent back once its sources and sample sets have been built.
+    pub fn load_in_background(
+        filename: &str,
+        password: Option<String>,
+        tx: mpsc::Sender<LoadJobMessage>,
+    ) {
+        let filename = filename.to_string();
+
+        std::thread::spawn(move || {
+            let result = std::fs::read(&filename)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| match password {
+                    Some(password) => {
+                        let _ = tx.send(LoadJobMessage::Decrypting);
+                        decrypt(&data, &password)
+                    }
+
+                    None if data.starts_with(ENCRYPTED_MAGIC) => {
+                        Err(anyhow!("Savefile is encrypted, a password is required"))
+                    }
+
+                    None => Ok(data),
+                })
+                .and_then(|data| {
+                    let _ = tx.send(LoadJobMessage::Building);
+                    Self::parse(&String::from_utf8(data)?, &filename)
+                });
+
+            let _ = tx.send(match result {
+                Ok(workspace) => LoadJobMessage::Finished(Box::new(workspace)),
+                Err(e) => LoadJobMessage::Error(e),
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_and_wait(filename: &str, password: &str) -> Result<AppModel, anyhow::Error> {
+        let (tx, rx) = mpsc::channel::<LoadJobMessage>();
+        Savefile::load_in_background(filename, Some(password.to_string()), tx);
+
+        match rx.iter().last() {
+            Some(LoadJobMessage::Finished(workspace)) => Ok(workspace.into_appmodel()),
+            Some(LoadJobMessage::Error(e)) => Err(e),
+            _ => Err(anyhow!("Load job ended without a result")),
+        }
+    }
+
+    #[test]
+    fn test_encrypted_savefile_roundtrip() {
+        let tmpfile = tempfile::NamedTempFile::new()
+            .expect("Should be able to create temporary file")
+            .into_temp_path();
+
+        let filename = tmpfile
+            .to_str()
+            .expect("Temporary file should have UTF-8 filename");
+
+        let (tx, rx) = mpsc::channel::<SaveJobMessage>();
+
+        Savefile::save_in_background(
+            &AppModel::new(None, None, None, None),
+            filename,
+            Some("hunter2".to_string()),
+            tx,
+        )
+        .expect("Should be able to save encrypted savefile");
+
+        assert!(matches!(rx.iter().last(), Some(SaveJobMessage::Finished)));
+
+        assert!(is_encrypted(filename).unwrap());
+        assert!(Savefile::load(filename).is_err());
+        assert!(load_and_wait(filename, "hunter3").is_err());
+        assert!(load_and_wait(filename, "hunter2").is_ok());
+    }
+
+    #[test]
+    fn test_save_in_background() {
+        let tmpfile = tempfile::NamedTempFile::new()
+            .expect("Should be able to create temporary file")
+            .into_temp_path();
+
+        let filename = tmpfile
+            .to_str()
+            .expect("Temporary file should have UTF-8 filename");
+
+        let (tx, rx) = mpsc::channel::<SaveJobMessage>();
+
+        Savefile::save_in_background(&AppModel::new(None, None, None, None), filename, None, tx)
+            .unwrap();
+
+        let messages = rx.iter().collect::<Vec<SaveJobMessage>>();
+
+        assert!(matches!(
+            messages.as_slice(),
+            [SaveJobMessage::Writing, SaveJobMessage::Finished]
+        ));
+
+        assert!(Savefile::load(filename).is_ok());
+    }
+
+    #[test]
+    fn test_savefile_format_version() {
+        let json = Savefile::to_json(&AppModel::new(None, None, None, None)).unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+
+        assert_eq!(format_version(&value).unwrap(), FORMAT_VERSION);
+        assert!(Savefile::from_json(&json, "test.json").is_ok());
+
+        let newer = json.replacen(
+            &format!("\"V{FORMAT_VERSION}\""),
+            &format!("\"V{}\"", FORMAT_VERSION + 1),
+            1,
+        );
+
+        assert!(Savefile::from_json(&newer, "test.json").is_err_and(|e| e.is::<NewerFormatError>()));
+
+        assert!(Savefile::from_json("{\"sources\": []}", "test.json")
+            .is_err_and(|e| !e.is::<NewerFormatError>()));
     }
 }
@@ -4,9 +4,12 @@
 
 #[cfg(test)]
 pub mod savefile_for_test {
-    use std::cell::Cell;
+    use std::{cell::Cell, sync::mpsc};
 
-    use crate::model::AppModel;
+    use crate::{
+        model::AppModel,
+        savefile::{LoadJobMessage, SaveJobMessage},
+    };
 
     thread_local! {
         #[allow(clippy::type_complexity)]
@@ -16,6 +19,10 @@ pub mod savefile_for_test {
         #[allow(clippy::type_complexity)]
         pub static LOAD: Cell<Option<fn(&str) -> Result<AppModel, anyhow::Error>>>
             = Cell::new(None);
+
+        #[allow(clippy::type_complexity)]
+        pub static SAVE_ENCRYPTED: Cell<Option<fn(&AppModel, &str, &str) -> Result<(), anyhow::Error>>>
+            = Cell::new(None);
     }
 
     pub struct Savefile {}
@@ -25,9 +32,45 @@ pub mod savefile_for_test {
             SAVE.get()
                 .expect("A function pointer should be placed in SAVE")(model, filename)
         }
+        pub fn save_in_background(
+            model: &AppModel,
+            filename: &str,
+            password: Option<String>,
+            tx: mpsc::Sender<SaveJobMessage>,
+        ) -> Result<(), anyhow::Error> {
+            let result = match password {
+                Some(password) => Self::save_encrypted(model, filename, &password),
+                None => Self::save(model, filename),
+            };
+
+            let _ = tx.send(match result {
+                Ok(()) => SaveJobMessage::Finished,
+                Err(e) => SaveJobMessage::Error(e.to_string()),
+            });
+
+            Ok(())
+        }
         pub fn load(filename: &str) -> Result<AppModel, anyhow::Error> {
             LOAD.get()
                 .expect("A function pointer should be placed in LOAD")(filename)
         }
+        pub fn save_encrypted(
+            model: &AppModel,
+            filename: &str,
+            password: &str,
+        ) -> Result<(), anyhow::Error> {
+            SAVE_ENCRYPTED
+                .get()
+                .expect("A function pointer should be placed in SAVE_ENCRYPTED")(
+                model, filename, password,
+            )
+        }
+        pub fn load_in_background(
+            filename: &str,
+            password: Option<String>,
+            tx: mpsc::Sender<LoadJobMessage>,
+        ) {
+            crate::savefile::Savefile::load_in_background(filename, password, tx)
+        }
     }
 }
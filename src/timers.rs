@@ -0,0 +1,316 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use gtk::glib::{self, clone};
+use libasampo::sequences::DrumkitSequenceEvent;
+
+use crate::{
+    model::{AppModel, PLAYBACK_IDLE_AFTER},
+    update,
+    view::AsampoView,
+    AppMessage, AppModelPtr,
+};
+
+/// Interval of [`AppMessage::TimerTick`].
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Polling interval while the drum machine is playing, fine enough to follow its steps.
+const PLAYBACK_POLL_INTERVAL: Duration = Duration::from_millis(4);
+
+/// Polling interval while background jobs (loading, exporting, saving, ...) are running.
+const JOBS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Polling interval while otherwise idle, for noticing that files in a watched source have
+/// changed or that the drum machine has started playing other than by its play button.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct Schedule {
+    next_tick: Instant,
+    latest_playback_event: Option<Instant>,
+    stopped: bool,
+
+    /// Counts the times the timer has been woken, so that a run armed before the latest
+    /// wake-up knows to leave the re-arming to the run armed by it.
+    generation: u64,
+}
+
+/// The timer of a window, which keeps the window's model and view alive until stopped.
+#[derive(Clone)]
+pub struct Timer(Rc<RefCell<Schedule>>);
+
+impl Timer {
+    /// Stop the timer from re-arming, e.g when its window is closed.
+    pub fn stop(&self) {
+        self.0.borrow_mut().stopped = true;
+
+        TIMERS.with_borrow_mut(|timers| {
+            timers.retain(|(_, _, schedule)| !Rc::ptr_eq(schedule, &self.0))
+        });
+    }
+}
+
+thread_local! {
+    /// The model, view and schedule of each running timer.
+    static TIMERS: RefCell<Vec<(AppModelPtr, AsampoView, Rc<RefCell<Schedule>>)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Run the timer of the window of `model_ptr` right away rather than after its current
+/// interval, e.g. when the drum machine starts playing while the timer polls at the idle
+/// interval.
+pub fn wake(model_ptr: &AppModelPtr) {
+    let timer = TIMERS.with_borrow(|timers| {
+        timers
+            .iter()
+            .find(|(other, _, _)| Rc::ptr_eq(other, model_ptr))
+            .cloned()
+    });
+
+    if let Some((model_ptr, view, schedule)) = timer {
+        schedule.borrow_mut().generation += 1;
+        arm(model_ptr, view, schedule, Duration::ZERO);
+    }
+}
+
+/// Drive all periodic work from a single timer that re-arms itself after each run, polling
+/// as often as the current activity needs and sleeping until the next tick when idle.
+pub fn start(model_ptr: AppModelPtr, view: &AsampoView) -> Timer {
+    let schedule = Rc::new(RefCell::new(Schedule {
+        next_tick: Instant::now() + TICK_INTERVAL,
+        latest_playback_event: None,
+        stopped: false,
+        generation: 0,
+    }));
+
+    TIMERS
+        .with_borrow_mut(|timers| timers.push((model_ptr.clone(), view.clone(), schedule.clone())));
+
+    arm(model_ptr, view.clone(), schedule.clone(), Duration::ZERO);
+
+    Timer(schedule)
+}
+
+fn arm(model_ptr: AppModelPtr, view: AsampoView, schedule: Rc<RefCell<Schedule>>, delay: Duration) {
+    let generation = schedule.borrow().generation;
+    let superseded = move |schedule: &Rc<RefCell<Schedule>>| {
+        let schedule = schedule.borrow();
+        schedule.stopped || schedule.generation != generation
+    };
+
+    glib::timeout_add_local_once(
+        delay,
+        clone!(@strong model_ptr, @strong view, @strong schedule => move || {
+            if superseded(&schedule) {
+                return;
+            }
+
+            let delay = run(&model_ptr, &view, &schedule);
+
+            // the timer may have been woken, and so re-armed, while running
+            if !superseded(&schedule) {
+                arm(model_ptr, view, schedule, delay);
+            }
+        }),
+    );
+}
+
+/// Do whatever work is due and return how long to wait before running again.
+fn run(model_ptr: &AppModelPtr, view: &AsampoView, schedule: &Rc<RefCell<Schedule>>) -> Duration {
+    if poll_playback(model_ptr, view) {
+        schedule.borrow_mut().latest_playback_event = Some(Instant::now());
+    }
+
+    poll_jobs(model_ptr, view);
+
+    if schedule.borrow().next_tick <= Instant::now() {
+        schedule.borrow_mut().next_tick = Instant::now() + TICK_INTERVAL;
+        update(model_ptr.clone(), view, AppMessage::TimerTick);
+    }
+
+    let schedule = schedule.borrow();
+    let model = model_ptr.take().unwrap();
+
+    let interval = if model.drum_machine.playing
+        || schedule
+            .latest_playback_event
+            .is_some_and(|t| t.elapsed() < PLAYBACK_IDLE_AFTER)
+    {
+        PLAYBACK_POLL_INTERVAL
+    } else if has_running_jobs(&model) {
+        JOBS_POLL_INTERVAL
+    } else if model.drum_machine.event_rx.is_some() || !model.sources_watching.is_empty() {
+        IDLE_POLL_INTERVAL
+    } else {
+        TICK_INTERVAL
+    };
+
+    model_ptr.set(Some(model));
+
+    interval.min(schedule.next_tick.saturating_duration_since(Instant::now()))
+}
+
+fn has_running_jobs(model: &AppModel) -> bool {
+    !model.sources_loading.is_empty()
+        || model.duplicates_job_rx.is_some()
+        || model.export_job_rx.is_some()
+        || model.save_job_rx.is_some()
+        || model.load_job_rx.is_some()
+}
+
+/// Pass on a new step of drum machine playback, if any. Returns whether there was one.
+fn poll_playback(model_ptr: &AppModelPtr, view: &AsampoView) -> bool {
+    let model = model_ptr.take().unwrap();
+
+    let mut event: Option<DrumkitSequenceEvent> = None;
+
+    if let Some(event_rx) = &model.drum_machine.event_rx {
+        match event_rx.borrow_mut().latest() {
+            Some(ev)
+                if model.drum_machine.event_latest.is_none()
+                    || ev.step != model.drum_machine.event_latest.as_ref().unwrap().step =>
+            {
+                event = Some(ev.clone());
+            }
+            _ => (),
+        }
+    }
+
+    model_ptr.replace(Some(model));
+
+    match event {
+        Some(ev) => {
+            update(
+                model_ptr.clone(),
+                view,
+                AppMessage::DrumMachinePlaybackEvent(ev),
+            );
+
+            true
+        }
+
+        None => false,
+    }
+}
+
+/// Pass on messages from background jobs and watched sources.
+fn poll_jobs(model_ptr: &AppModelPtr, view: &AsampoView) {
+    let model = model_ptr.take().unwrap();
+    let export_job_rx = model.export_job_rx.clone();
+    let save_job_rx = model.save_job_rx.clone();
+    let load_job_rx = model.load_job_rx.clone();
+    let duplicates_job_rx = model.duplicates_job_rx.clone();
+    let sources_loading = model.sources_loading.clone();
+    let sources_watching = model.sources_watching.clone();
+
+    let samples_per_tick = match model
+        .config
+        .as_ref()
+        .map(|config| config.source_loading_samples_per_tick)
+    {
+        Some(0) | None => usize::MAX,
+        Some(n) => n as usize,
+    };
+
+    model_ptr.set(Some(model));
+
+    for (uuid, watcher) in sources_watching.iter() {
+        if watcher.changed() {
+            update(
+                model_ptr.clone(),
+                view,
+                AppMessage::SourceFilesChanged(*uuid),
+            );
+        }
+    }
+
+    if let Some(rx) = duplicates_job_rx {
+        match rx.try_recv() {
+            Ok(groups) => update(
+                model_ptr.clone(),
+                view,
+                AppMessage::DuplicatesJobFinished(groups),
+            ),
+
+            Err(mpsc::TryRecvError::Empty) => (),
+
+            Err(mpsc::TryRecvError::Disconnected) => update(
+                model_ptr.clone(),
+                view,
+                AppMessage::DuplicatesJobDisconnected,
+            ),
+        }
+    }
+
+    if let Some(rx) = load_job_rx {
+        drain(model_ptr, view, &rx, AppMessage::LoadJobMessage, || {
+            AppMessage::LoadJobDisconnected
+        });
+    }
+
+    if let Some(rx) = save_job_rx {
+        drain(model_ptr, view, &rx, AppMessage::SaveJobMessage, || {
+            AppMessage::SaveJobDisconnected
+        });
+    }
+
+    if let Some(rx) = export_job_rx {
+        drain(model_ptr, view, &rx, AppMessage::ExportJobMessage, || {
+            AppMessage::ExportJobDisconnected
+        });
+    }
+
+    for (uuid, recv) in sources_loading.iter() {
+        match recv.try_recv() {
+            Ok(message) => {
+                let mut messages = vec![message];
+                messages.extend(recv.try_iter().take(samples_per_tick - 1));
+
+                update(
+                    model_ptr.clone(),
+                    view,
+                    AppMessage::SourceLoadingMessage(*uuid, messages),
+                );
+            }
+
+            Err(mpsc::TryRecvError::Empty) => (),
+
+            Err(mpsc::TryRecvError::Disconnected) => update(
+                model_ptr.clone(),
+                view,
+                AppMessage::SourceLoadingDisconnected(*uuid),
+            ),
+        }
+    }
+}
+
+/// Pass on all messages currently waiting in a job channel, followed by `disconnected` if
+/// the job has ended.
+fn drain<T>(
+    model_ptr: &AppModelPtr,
+    view: &AsampoView,
+    rx: &mpsc::Receiver<T>,
+    message: fn(T) -> AppMessage,
+    disconnected: fn() -> AppMessage,
+) {
+    loop {
+        match rx.try_recv() {
+            Ok(m) => update(model_ptr.clone(), view, message(m)),
+
+            Err(e) => {
+                if e == mpsc::TryRecvError::Disconnected {
+                    update(model_ptr.clone(), view, disconnected());
+                }
+
+                break;
+            }
+        }
+    }
+}
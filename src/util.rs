@@ -2,6 +2,8 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
+use std::{ops::Range, path::Path};
+
 use anyhow::anyhow;
 use gtk::{glib::object::IsA, prelude::*};
 use uuid::Uuid;
@@ -134,8 +136,125 @@ pub fn set_dropdown_choice<T: PartialEq>(
                 == key
         })
     {
-        dropdown.set_selected(position.try_into().unwrap());
+        let position = position.try_into().unwrap();
+
+        // only touch dropdowns whose choice changed
+        if dropdown.selected() != position {
+            dropdown.set_selected(position);
+        }
+    }
+}
+
+/// List the files directly inside `dir` having one of the given extensions, sorted by path.
+pub fn list_files_with_extensions(
+    dir: &str,
+    exts: &[String],
+) -> Result<Vec<String>, anyhow::Error> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| exts.iter().any(|x| x.eq_ignore_ascii_case(ext)))
+        })
+        .filter_map(|path| path.to_str().map(String::from))
+        .collect::<Vec<String>>();
+
+    files.sort();
+    Ok(files)
+}
+
+/// The first key of an SFZ instrument, C1, where General MIDI puts the bass drum.
+pub const SFZ_FIRST_KEY: usize = 36;
+
+/// An SFZ instrument playing each of `files` (relative to the instrument) from its own key,
+/// starting at [`SFZ_FIRST_KEY`].
+pub fn sfz_instrument(name: &str, files: &[String]) -> String {
+    let mut sfz =
+        format!("// {name}\n\n<control>\ndefault_path=./\n\n<group>\nloop_mode=one_shot\n");
+
+    for (i, file) in files.iter().enumerate() {
+        sfz.push_str(&format!(
+            "\n<region>\nsample={file}\nkey={}\n",
+            SFZ_FIRST_KEY + i
+        ));
+    }
+
+    sfz
+}
+
+/// Match the characters of `pattern` in order, but not necessarily adjacent, against `text`,
+/// ignoring case. Returns a score (higher is better) and the char positions of the match,
+/// preferring consecutive characters and characters at the start of words.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let text = text.chars().collect::<Vec<char>>();
+    let mut positions = Vec::new();
+    let mut score = 0i64;
+    let mut next = 0;
+
+    for pc in pattern.chars().filter(|c| !c.is_whitespace()) {
+        let pos = (next..text.len()).find(|i| text[*i].to_lowercase().eq(pc.to_lowercase()))?;
+
+        score += 1;
+
+        if pos == 0 || !text[pos - 1].is_alphanumeric() {
+            score += 8;
+        }
+
+        match positions.last() {
+            Some(prev) if *prev + 1 == pos => score += 5,
+            Some(prev) => score -= (pos - prev - 1).min(10) as i64,
+            None => (),
+        }
+
+        positions.push(pos);
+        next = pos + 1;
     }
+
+    Some((score, positions))
+}
+
+/// Resolve an export target directory, expanding the `<set>` and `<workspace>` tokens to the
+/// name of the exported set and the savefile name (without extension), respectively. Relative
+/// targets are resolved against the directory of the savefile.
+pub fn resolve_export_target(
+    target: &str,
+    savefile: Option<&str>,
+    set_name: &str,
+) -> Result<String, anyhow::Error> {
+    let path_safe = |name: &str| name.replace(['/', '\\'], "_");
+
+    let mut expanded = target.replace("<set>", &path_safe(set_name));
+
+    if expanded.contains("<workspace>") {
+        let stem = savefile
+            .and_then(|filename| Path::new(filename).file_stem())
+            .and_then(|stem| stem.to_str())
+            .ok_or(anyhow!(
+                "Save the workspace to use <workspace> in export targets"
+            ))?;
+
+        expanded = expanded.replace("<workspace>", &path_safe(stem));
+    }
+
+    if Path::new(&expanded).is_absolute() {
+        return Ok(expanded);
+    }
+
+    let savefile_dir = savefile
+        .and_then(|filename| Path::new(filename).parent())
+        .ok_or(anyhow!(
+            "Save the workspace to export to a location relative to it"
+        ))?;
+
+    savefile_dir
+        .join(&expanded)
+        .to_str()
+        .map(String::from)
+        .ok_or(anyhow!("Invalid export target path"))
 }
 
 pub fn resource_as_string(path: &str) -> Result<String, anyhow::Error> {
@@ -159,3 +278,300 @@ pub fn uuidize_builder_template(xml: &str, uuid: Uuid) -> String {
 pub fn idize_builder_template(xml: &str, id: usize) -> String {
     xml.replace("{id}", &format!("{id}"))
 }
+
+/// Whether `data` starts with a RIFF WAVE header.
+pub fn is_wav(data: &[u8]) -> bool {
+    data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WAVE"
+}
+
+/// The range of the contents of the first chunk of a WAV file with the given id.
+fn wav_chunk(wav: &[u8], id: &[u8; 4]) -> Result<Range<usize>, anyhow::Error> {
+    if !is_wav(wav) {
+        return Err(anyhow!("Not a WAV file"));
+    }
+
+    let mut pos = 12;
+
+    while pos + 8 <= wav.len() {
+        let chunk_len = u32::from_le_bytes(wav[pos + 4..pos + 8].try_into()?) as usize;
+
+        if &wav[pos..pos + 4] == id {
+            return Ok(pos + 8..(pos + 8 + chunk_len).min(wav.len()));
+        }
+
+        // chunks are padded to an even length
+        pos += 8 + chunk_len + chunk_len % 2;
+    }
+
+    Err(anyhow!(
+        "WAV file has no '{}' chunk",
+        String::from_utf8_lossy(id).trim()
+    ))
+}
+
+/// The format chunk of a WAV file, checked to hold at least the basic PCM format fields.
+fn wav_format_chunk(wav: &[u8]) -> Result<Range<usize>, anyhow::Error> {
+    let fmt = wav_chunk(wav, b"fmt ")?;
+
+    if fmt.len() < 16 {
+        return Err(anyhow!("Truncated WAV format chunk"));
+    }
+
+    Ok(fmt)
+}
+
+/// Change the sample rate declared in the header of a WAV file by `factor`, so that it plays
+/// back that much faster and higher (or slower and lower) without touching the audio data.
+pub fn wav_scale_rate(wav: &mut [u8], factor: f64) -> Result<(), anyhow::Error> {
+    let fmt = wav_format_chunk(wav)?;
+
+    for offset in [4, 8] {
+        let range = fmt.start + offset..fmt.start + offset + 4;
+        let value = u32::from_le_bytes(wav[range.clone()].try_into()?);
+        let scaled = (value as f64 * factor).round() as u32;
+        wav[range].copy_from_slice(&scaled.max(1).to_le_bytes());
+    }
+
+    Ok(())
+}
+
+/// Decode an audio file in any format known to symphonia into a 16-bit WAV file, so that it
+/// can be processed like one.
+pub fn decode_to_wav(data: Vec<u8>) -> Result<Vec<u8>, anyhow::Error> {
+    use symphonia::core::{
+        audio::SampleBuffer, codecs::DecoderOptions, errors::Error, formats::FormatOptions,
+        io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+    };
+
+    let stream = MediaSourceStream::new(Box::new(std::io::Cursor::new(data)), Default::default());
+
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &Hint::new(),
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?
+        .format;
+
+    let track = format
+        .default_track()
+        .ok_or(anyhow!("Audio file has no tracks"))?;
+    let track_id = track.id;
+
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut samples: Vec<i16> = Vec::new();
+    let mut rate_and_channels = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = decoder.decode(&packet)?;
+        let spec = *decoded.spec();
+        let mut buf = SampleBuffer::<i16>::new(decoded.capacity() as u64, spec);
+
+        buf.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buf.samples());
+        rate_and_channels = Some((spec.rate, spec.channels.count() as u16));
+    }
+
+    let (rate, channels) = rate_and_channels.ok_or(anyhow!("Audio file has no audio data"))?;
+
+    Ok(wav_from_samples(rate, channels, &samples))
+}
+
+/// A 16-bit WAV file of interleaved `samples`.
+pub fn wav_from_samples(rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let block_align = channels * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&WAV_FORMAT_PCM.to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&rate.to_le_bytes());
+    wav.extend_from_slice(&(rate * block_align as u32).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.extend(samples.iter().flat_map(|sample| sample.to_le_bytes()));
+
+    wav
+}
+
+const WAV_FORMAT_PCM: u16 = 1;
+const WAV_FORMAT_FLOAT: u16 = 3;
+const WAV_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Scale the audio data of an integer PCM or floating point WAV file by `gain`, clipping
+/// at full scale.
+pub fn wav_scale_amplitude(wav: &mut [u8], gain: f32) -> Result<(), anyhow::Error> {
+    let fmt = wav_format_chunk(wav)?;
+    let field = |offset: usize| [wav[fmt.start + offset], wav[fmt.start + offset + 1]];
+
+    // the extensible format keeps the actual format in the first bytes of its subformat
+    let format = match u16::from_le_bytes(field(0)) {
+        WAV_FORMAT_EXTENSIBLE if fmt.len() >= 26 => u16::from_le_bytes(field(24)),
+        format => format,
+    };
+
+    let bits = u16::from_le_bytes(field(14));
+    let data = wav_chunk(wav, b"data")?;
+    let data = &mut wav[data];
+
+    let clip = |value: f32, max: f32| value.round().clamp(-max - 1.0, max);
+
+    match (format, bits) {
+        (WAV_FORMAT_PCM, 8) => {
+            for byte in data.iter_mut() {
+                *byte = (clip((*byte as f32 - 128.0) * gain, 127.0) + 128.0) as u8;
+            }
+        }
+
+        (WAV_FORMAT_PCM, 16) => {
+            for sample in data.chunks_exact_mut(2) {
+                let value = i16::from_le_bytes([sample[0], sample[1]]) as f32 * gain;
+                sample.copy_from_slice(&(clip(value, i16::MAX as f32) as i16).to_le_bytes());
+            }
+        }
+
+        (WAV_FORMAT_PCM, 24) => {
+            for sample in data.chunks_exact_mut(3) {
+                let value = (i32::from_le_bytes([0, sample[0], sample[1], sample[2]]) >> 8) as f32;
+                let scaled = clip(value * gain, 8388607.0) as i32;
+                sample.copy_from_slice(&(scaled << 8).to_le_bytes()[1..4]);
+            }
+        }
+
+        (WAV_FORMAT_PCM, 32) => {
+            for sample in data.chunks_exact_mut(4) {
+                let value = i32::from_le_bytes(sample.try_into()?) as f64 * gain as f64;
+                let scaled = value.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32;
+                sample.copy_from_slice(&scaled.to_le_bytes());
+            }
+        }
+
+        (WAV_FORMAT_FLOAT, 32) => {
+            for sample in data.chunks_exact_mut(4) {
+                let value = f32::from_le_bytes(sample.try_into()?) * gain;
+                sample.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        _ => return Err(anyhow!("Unsupported WAV sample format")),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_scale_rate() {
+        let mut wav = crate::model::metronome_click_wav(false);
+        let rate = u32::from_le_bytes(wav[24..28].try_into().unwrap());
+        let byte_rate = u32::from_le_bytes(wav[28..32].try_into().unwrap());
+
+        wav_scale_rate(&mut wav, 2.0).unwrap();
+
+        assert_eq!(
+            u32::from_le_bytes(wav[24..28].try_into().unwrap()),
+            rate * 2
+        );
+        assert_eq!(
+            u32::from_le_bytes(wav[28..32].try_into().unwrap()),
+            byte_rate * 2
+        );
+
+        assert!(wav_scale_rate(&mut b"ID3\x04".to_vec(), 2.0).is_err());
+    }
+
+    #[test]
+    fn test_wav_scale_amplitude() {
+        let original = crate::model::metronome_click_wav(true);
+        let sample = |wav: &[u8], i: usize| i16::from_le_bytes([wav[44 + i * 2], wav[45 + i * 2]]);
+
+        let i = (0..100)
+            .find(|&i| (sample(&original, i) as i32).abs() > 100)
+            .unwrap();
+
+        let mut wav = original.clone();
+        wav_scale_amplitude(&mut wav, 0.5).unwrap();
+        assert!((sample(&wav, i) - sample(&original, i) / 2).abs() <= 1);
+
+        let mut wav = original.clone();
+        wav_scale_amplitude(&mut wav, 1000.0).unwrap();
+        assert!((sample(&wav, i) as i32).abs() >= i16::MAX as i32);
+
+        assert!(wav_scale_amplitude(&mut b"ID3\x04".to_vec(), 0.5).is_err());
+    }
+
+    #[test]
+    fn test_decode_to_wav() {
+        let samples = [0i16, 1000, -1000, i16::MAX, i16::MIN, 0];
+        let wav = wav_from_samples(44100, 2, &samples);
+
+        assert!(is_wav(&wav));
+        assert_eq!(decode_to_wav(wav.clone()).unwrap(), wav);
+        assert!(decode_to_wav(b"ID3\x04".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_sfz_instrument() {
+        let sfz = sfz_instrument("Kit", &["kick.wav".to_string(), "snare.wav".to_string()]);
+
+        assert!(sfz.starts_with("// Kit\n"));
+        assert!(sfz.contains("<region>\nsample=kick.wav\nkey=36\n"));
+        assert!(sfz.contains("<region>\nsample=snare.wav\nkey=37\n"));
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(
+            fuzzy_match("kck", "kick.wav").map(|m| m.1),
+            Some(vec![0, 2, 3])
+        );
+        assert!(fuzzy_match("kcik", "kick.wav").is_none());
+
+        let (word_start, _) = fuzzy_match("sn", "snare.wav").unwrap();
+        let (scattered, _) = fuzzy_match("sn", "bass_one.wav").unwrap();
+        assert!(word_start > scattered);
+    }
+
+    #[test]
+    fn test_resolve_export_target() {
+        assert_eq!(
+            resolve_export_target("/tmp/<set>", None, "Kicks/Snares").unwrap(),
+            "/tmp/Kicks_Snares"
+        );
+
+        assert_eq!(
+            resolve_export_target(
+                "./exports/<workspace>/<set>",
+                Some("/home/user/beats.json"),
+                "Kit"
+            )
+            .unwrap(),
+            "/home/user/./exports/beats/Kit"
+        );
+
+        assert!(resolve_export_target("exports", None, "Kit").is_err());
+    }
+}
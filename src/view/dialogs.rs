@@ -2,16 +2,20 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
+use std::{cell::RefCell, collections::VecDeque};
+
 use gtk::{
     glib::{clone, Propagation},
     prelude::*,
 };
+use libasampo::samples::SampleOps;
 
 use crate::{
-    model::{AppModel, AppModelPtr},
+    config::SETTINGS_GROUP_OPTIONS,
+    model::{AppModel, AppModelPtr, SavefileLockConflict, SavefileReview},
     update, util,
     view::AsampoView,
-    AppMessage, InputDialogContext, SelectFolderDialogContext,
+    AppMessage, DialogText, InputDialogContext, SelectFolderDialogContext,
 };
 
 pub fn choose_folder(
@@ -46,6 +50,44 @@ pub fn choose_folder(
     );
 }
 
+pub fn choose_image(
+    model_ptr: AppModelPtr,
+    view: &AsampoView,
+    opened: AppMessage,
+    ok: fn(String) -> AppMessage,
+    err: fn(gtk::glib::Error) -> AppMessage,
+) {
+    let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+    let filter_images = gtk::FileFilter::new();
+
+    filter_images.set_name(Some("Images"));
+    filter_images.add_pixbuf_formats();
+    filters.append(&filter_images);
+
+    let dialog = gtk::FileDialog::builder()
+        .modal(true)
+        .filters(&filters)
+        .build();
+
+    dialog.open(
+        Some(view),
+        None::<gtk::gio::Cancellable>.as_ref(),
+        clone!(@strong model_ptr, @strong view => move |result| {
+            match result {
+                Ok(gfile) => update(
+                    model_ptr.clone(),
+                    &view,
+                    ok(gfile.path().unwrap().into_os_string().into_string().unwrap())
+                ),
+
+                Err(e) => update(model_ptr.clone(), &view, err(e)),
+            }
+        }),
+    );
+
+    update(model_ptr.clone(), view, opened);
+}
+
 pub fn alert(_model_ptr: AppModelPtr, view: &AsampoView, message: &str, detail: &str) {
     let dialog = gtk::AlertDialog::builder()
         .modal(true)
@@ -56,6 +98,224 @@ pub fn alert(_model_ptr: AppModelPtr, view: &AsampoView, message: &str, detail:
     dialog.show(Some(view));
 }
 
+/// Tell the user about an internal error, offering to open the state dump written for it.
+pub fn panic_dump(view: &AsampoView, text: &str, path: &str) {
+    let dialog = gtk::AlertDialog::builder()
+        .modal(true)
+        .message("Internal error")
+        .detail(format!(
+            "Something went wrong, and the last action was undone. A state dump that can help \
+            with reporting the problem was written to {path}\n\n{text}"
+        ))
+        .buttons(["Close", "Open state dump"])
+        .cancel_button(0)
+        .default_button(0)
+        .build();
+
+    let path = path.to_string();
+
+    dialog.choose(
+        Some(view),
+        None::<gtk::gio::Cancellable>.as_ref(),
+        clone!(@strong view => move |result| {
+            if let Ok(1) = result {
+                gtk::FileLauncher::new(Some(&gtk::gio::File::for_path(&path))).launch(
+                    Some(&view),
+                    None::<gtk::gio::Cancellable>.as_ref(),
+                    |result| {
+                        if let Err(e) = result {
+                            log::log!(log::Level::Error, "Failed to open state dump: {e}");
+                        }
+                    },
+                );
+            }
+        }),
+    );
+}
+
+const TOAST_SECONDS: u32 = 3;
+
+thread_local! {
+    // shared by all windows, each showing only its own messages
+    static TOAST_QUEUE: RefCell<VecDeque<(AsampoView, String)>> =
+        const { RefCell::new(VecDeque::new()) };
+}
+
+/// Briefly show a short message at the bottom of the window. Messages arriving while another
+/// one is shown are queued and shown in turn.
+pub fn toast(view: &AsampoView, text: &str) {
+    TOAST_QUEUE.with_borrow_mut(|queue| queue.push_back((view.clone(), text.to_string())));
+
+    if !view.toast.is_visible() {
+        show_next_toast(view);
+    }
+}
+
+fn show_next_toast(view: &AsampoView) {
+    let next = TOAST_QUEUE.with_borrow_mut(|queue| {
+        queue
+            .iter()
+            .position(|(queued_for, _)| queued_for == view)
+            .and_then(|position| queue.remove(position))
+    });
+
+    match next {
+        Some((_, text)) => {
+            view.toast_label.set_text(&text);
+            view.toast.set_visible(true);
+
+            gtk::glib::timeout_add_seconds_local_once(
+                TOAST_SECONDS,
+                clone!(@strong view => move || show_next_toast(&view)),
+            );
+        }
+
+        None => view.toast.set_visible(false),
+    }
+}
+
+pub fn recovery_restore(model_ptr: AppModelPtr, view: &AsampoView) {
+    let dialog = gtk::AlertDialog::builder()
+        .modal(true)
+        .message("Restore unsaved workspace?")
+        .detail("Asampo found a workspace with unsaved changes from a previous session.")
+        .buttons(["Discard", "Restore"])
+        .cancel_button(0)
+        .default_button(1)
+        .build();
+
+    dialog.choose(
+        Some(view),
+        None::<gtk::gio::Cancellable>.as_ref(),
+        clone!(@strong model_ptr, @strong view => move |result| {
+            match result {
+                Ok(1) => update(model_ptr.clone(), &view, AppMessage::RecoveryRestoreAccepted),
+                _ => update(model_ptr.clone(), &view, AppMessage::RecoveryRestoreDeclined),
+            }
+        }),
+    );
+}
+
+pub fn savefile_locked(model_ptr: AppModelPtr, view: &AsampoView, conflict: &SavefileLockConflict) {
+    let dialog = gtk::AlertDialog::builder()
+        .modal(true)
+        .message("Workspace is open elsewhere")
+        .detail(format!(
+            "{} is open in Asampo on {}. If both keep working on it, changes made in one \
+            may overwrite changes made in the other.",
+            conflict.filename, conflict.held_by
+        ))
+        .buttons([
+            "Cancel",
+            if conflict.saving {
+                "Save anyway"
+            } else {
+                "Open anyway"
+            },
+        ])
+        .cancel_button(0)
+        .default_button(0)
+        .build();
+
+    dialog.choose(
+        Some(view),
+        None::<gtk::gio::Cancellable>.as_ref(),
+        clone!(@strong model_ptr, @strong view => move |result| {
+            match result {
+                Ok(1) => update(model_ptr.clone(), &view, AppMessage::SavefileLockTakeoverAccepted),
+                _ => update(model_ptr.clone(), &view, AppMessage::SavefileLockTakeoverDeclined),
+            }
+        }),
+    );
+
+    update(model_ptr, view, AppMessage::SavefileLockedDialogOpened);
+}
+
+const REVIEW_CHANGES_LISTED_MAX: usize = 20;
+
+/// List what has changed since the workspace was last saved, before saving over it.
+pub fn savefile_review(model_ptr: AppModelPtr, view: &AsampoView, review: &SavefileReview) {
+    let mut detail = review
+        .changes
+        .iter()
+        .take(REVIEW_CHANGES_LISTED_MAX)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if review.changes.len() > REVIEW_CHANGES_LISTED_MAX {
+        detail.push_str(&format!(
+            "\n... and {} more",
+            review.changes.len() - REVIEW_CHANGES_LISTED_MAX
+        ));
+    }
+
+    let dialog = gtk::AlertDialog::builder()
+        .modal(true)
+        .message(format!("Save changes to {}?", review.filename))
+        .detail(detail)
+        .buttons(["Cancel", "Save"])
+        .cancel_button(0)
+        .default_button(1)
+        .build();
+
+    dialog.choose(
+        Some(view),
+        None::<gtk::gio::Cancellable>.as_ref(),
+        clone!(@strong model_ptr, @strong view => move |result| {
+            match result {
+                Ok(1) => update(model_ptr.clone(), &view, AppMessage::SavefileReviewAccepted),
+                _ => update(model_ptr.clone(), &view, AppMessage::SavefileReviewDeclined),
+            }
+        }),
+    );
+
+    update(model_ptr, view, AppMessage::SavefileReviewDialogOpened);
+}
+
+const EXPORT_ERRORS_LISTED_MAX: usize = 20;
+
+/// Summarize the errors from a finished export, offering to run the export again.
+pub fn export_errors(model_ptr: AppModelPtr, view: &AsampoView, errors: &[String]) {
+    let mut detail = errors
+        .iter()
+        .take(EXPORT_ERRORS_LISTED_MAX)
+        .cloned()
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    if errors.len() > EXPORT_ERRORS_LISTED_MAX {
+        detail.push_str(&format!(
+            "\n... and {} more",
+            errors.len() - EXPORT_ERRORS_LISTED_MAX
+        ));
+    }
+
+    let dialog = gtk::AlertDialog::builder()
+        .modal(true)
+        .message(match errors.len() {
+            1 => "1 file could not be exported".to_string(),
+            n => format!("{n} files could not be exported"),
+        })
+        .detail(detail)
+        .buttons(["Close", "Retry export"])
+        .cancel_button(0)
+        .default_button(0)
+        .build();
+
+    dialog.choose(
+        Some(view),
+        None::<gtk::gio::Cancellable>.as_ref(),
+        clone!(@strong model_ptr, @strong view => move |result| {
+            if let Ok(1) = result {
+                update(model_ptr.clone(), &view, AppMessage::QuickExportRequested);
+            }
+        }),
+    );
+
+    update(model_ptr, view, AppMessage::ExportErrorsDialogOpened);
+}
+
 pub fn input(
     model_ptr: AppModelPtr,
     view: &AsampoView,
@@ -83,20 +343,43 @@ pub fn input(
 
     let input = objects.object::<gtk::Entry>("input").unwrap();
     input.set_placeholder_text(Some(placeholder));
+    input.set_visibility(!context.is_secret());
 
     let okbutton = objects.object::<gtk::Button>("ok-button").unwrap();
     okbutton.set_label(ok);
 
+    let confirm_input = objects.object::<gtk::Entry>("confirm-input").unwrap();
+
+    if context.needs_confirmation() {
+        objects
+            .object::<gtk::Box>("confirm-box")
+            .unwrap()
+            .set_visible(true);
+
+        confirm_input.set_visibility(!context.is_secret());
+        okbutton.set_sensitive(false);
+
+        let check_match = clone!(@strong input, @strong confirm_input, @strong okbutton => move |_: &gtk::Entry| {
+            okbutton.set_sensitive(input.text() == confirm_input.text());
+        });
+
+        input.connect_changed(check_match.clone());
+        confirm_input.connect_changed(check_match);
+    }
+
     let cancelbutton = objects.object::<gtk::Button>("cancel-button").unwrap();
 
     okbutton.connect_clicked(
         clone!(@strong model_ptr, @strong view, @strong dialogwin, @strong context => move |_: &gtk::Button| {
             update(model_ptr.clone(), &view, AppMessage::InputDialogSubmitted(
                 context.clone(),
-                util::gtk_find_child_by_builder_id::<gtk::Entry>(&dialogwin, "input")
-                    .unwrap()
-                    .text()
-                    .to_string()
+                DialogText::new(
+                    &context,
+                    util::gtk_find_child_by_builder_id::<gtk::Entry>(&dialogwin, "input")
+                        .unwrap()
+                        .text()
+                        .to_string()
+                )
             ));
 
             view.set_sensitive(true);
@@ -104,8 +387,20 @@ pub fn input(
         }),
     );
 
-    input.connect_activate(clone!(@strong okbutton => move |_| {
-        okbutton.emit_clicked();
+    input.connect_activate(
+        clone!(@strong okbutton, @strong confirm_input, @strong context => move |_| {
+            if context.needs_confirmation() && confirm_input.text().is_empty() {
+                confirm_input.grab_focus();
+            } else if okbutton.is_sensitive() {
+                okbutton.emit_clicked();
+            }
+        }),
+    );
+
+    confirm_input.connect_activate(clone!(@strong okbutton => move |_| {
+        if okbutton.is_sensitive() {
+            okbutton.emit_clicked();
+        }
     }));
 
     cancelbutton.connect_clicked(
@@ -138,10 +433,208 @@ pub fn input(
     input.grab_focus();
 }
 
+pub fn source_preview(model_ptr: AppModelPtr, view: &AsampoView, model: &AppModel) {
+    let objects = gtk::Builder::from_resource("/source-preview-dialog.ui");
+
+    let dialogwin = objects
+        .object::<gtk::Window>("source-preview-dialog-window")
+        .unwrap();
+
+    let path = &model.viewvalues.sources_add_fs_path_entry;
+    let files = &model.viewvalues.sources_add_fs_preview_files;
+
+    objects
+        .object::<gtk::Label>("title")
+        .unwrap()
+        .set_text(path);
+
+    objects
+        .object::<gtk::Label>("summary-label")
+        .unwrap()
+        .set_text(&match files.len() {
+            0 => "No audio files directly in this folder".to_string(),
+            1 => "1 audio file, click to listen".to_string(),
+            n => format!("{n} audio files, click to listen"),
+        });
+
+    let file_list = objects.object::<gtk::ListBox>("file-list").unwrap();
+
+    for file in files {
+        let name = std::path::Path::new(file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(file.clone());
+
+        let label = gtk::Label::new(Some(&name));
+        label.set_xalign(0.0);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        label.set_tooltip_text(Some(file));
+
+        file_list.append(&label);
+    }
+
+    // row-activated is emitted for clicks, whereas a row's own activate is keyboard-only
+    let files = files.clone();
+
+    file_list.connect_row_activated(
+        clone!(@strong model_ptr, @strong view, @strong files => move |_, row| {
+            if let Some(file) = usize::try_from(row.index()).ok().and_then(|i| files.get(i)) {
+                update(model_ptr.clone(), &view, AppMessage::SourcePreviewFileClicked(file.clone()));
+            }
+        }),
+    );
+
+    objects
+        .object::<gtk::Button>("ok-button")
+        .unwrap()
+        .connect_clicked(clone!(@strong dialogwin => move |_: &gtk::Button| {
+            dialogwin.close();
+        }));
+
+    objects
+        .object::<gtk::Button>("choose-another-button")
+        .unwrap()
+        .connect_clicked(
+            clone!(@strong model_ptr, @strong view, @strong dialogwin => move |_: &gtk::Button| {
+                dialogwin.close();
+                update(model_ptr.clone(), &view, AppMessage::AddFilesystemSourcePathBrowseClicked);
+            }),
+        );
+
+    dialogwin.set_modal(true);
+    dialogwin.set_transient_for(Some(view));
+    dialogwin.present();
+
+    update(
+        model_ptr.clone(),
+        view,
+        AppMessage::SourcePreviewDialogOpened,
+    );
+}
+
+pub fn duplicates(model_ptr: AppModelPtr, view: &AsampoView, model: &AppModel) {
+    let objects = gtk::Builder::from_resource("/duplicates-dialog.ui");
+
+    let dialogwin = objects
+        .object::<gtk::Window>("duplicates-dialog-window")
+        .unwrap();
+
+    let groups = &model.samples_duplicates;
+
+    objects
+        .object::<gtk::Label>("summary-label")
+        .unwrap()
+        .set_text(&match groups.len() {
+            0 => "No duplicate samples found".to_string(),
+            1 => "1 sample has identical copies".to_string(),
+            n => format!("{n} samples have identical copies"),
+        });
+
+    let duplicates_list = objects.object::<gtk::ListBox>("duplicates-list").unwrap();
+
+    for group in groups {
+        let group_box = gtk::Box::new(gtk::Orientation::Vertical, 2);
+
+        for sample in group {
+            let label = gtk::Label::new(Some(sample.uri().as_str()));
+            label.set_xalign(0.0);
+            label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+            label.set_tooltip_text(Some(sample.uri().as_str()));
+            group_box.append(&label);
+        }
+
+        duplicates_list.append(&group_box);
+    }
+
+    let hide_check = objects
+        .object::<gtk::CheckButton>("hide-duplicates-check")
+        .unwrap();
+
+    hide_check.set_active(model.samples_hide_duplicates);
+    hide_check.set_sensitive(!groups.is_empty());
+
+    hide_check.connect_toggled(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::CheckButton| {
+            update(model_ptr.clone(), &view, AppMessage::DuplicatesHideToggled(e.is_active()));
+        }),
+    );
+
+    objects
+        .object::<gtk::Button>("ok-button")
+        .unwrap()
+        .connect_clicked(clone!(@strong dialogwin => move |_: &gtk::Button| {
+            dialogwin.close();
+        }));
+
+    dialogwin.set_modal(true);
+    dialogwin.set_transient_for(Some(view));
+    dialogwin.present();
+
+    update(model_ptr.clone(), view, AppMessage::DuplicatesDialogOpened);
+}
+
+/// Let the user pick which groups of the settings read from a file to import.
+pub fn settings_import(model_ptr: AppModelPtr, view: &AsampoView) {
+    let objects = gtk::Builder::from_resource("/settings-import-dialog.ui");
+
+    let dialogwin = objects
+        .object::<gtk::Window>("settings-import-dialog-window")
+        .unwrap();
+
+    let groups_box = objects.object::<gtk::Box>("groups-box").unwrap();
+
+    let checks = SETTINGS_GROUP_OPTIONS
+        .iter()
+        .map(|(label, group)| {
+            let check = gtk::CheckButton::with_label(label);
+            check.set_active(true);
+            groups_box.append(&check);
+            (check, *group)
+        })
+        .collect::<Vec<_>>();
+
+    objects
+        .object::<gtk::Button>("import-button")
+        .unwrap()
+        .connect_clicked(
+            clone!(@strong model_ptr, @strong view, @strong dialogwin => move |_: &gtk::Button| {
+                let groups = checks
+                    .iter()
+                    .filter(|(check, _)| check.is_active())
+                    .map(|(_, group)| *group)
+                    .collect();
+
+                // destroy rather than close, which would decline the import
+                dialogwin.destroy();
+                update(model_ptr.clone(), &view, AppMessage::SettingsImportAccepted(groups));
+            }),
+        );
+
+    objects
+        .object::<gtk::Button>("cancel-button")
+        .unwrap()
+        .connect_clicked(clone!(@strong dialogwin => move |_: &gtk::Button| {
+            dialogwin.close();
+        }));
+
+    dialogwin.connect_close_request(clone!(@strong model_ptr, @strong view => move |_| {
+        update(model_ptr.clone(), &view, AppMessage::SettingsImportDeclined);
+        Propagation::Proceed
+    }));
+
+    dialogwin.set_modal(true);
+    dialogwin.set_transient_for(Some(view));
+    dialogwin.present();
+
+    update(model_ptr, view, AppMessage::SettingsImportDialogOpened);
+}
+
 #[derive(Debug, Clone)]
 pub struct ExportDialogView {
     pub window: gtk::Window,
     pub target_dir_entry: gtk::Entry,
+    pub filename_template_entry: gtk::Entry,
+    pub filename_template_error_label: gtk::Label,
     pub export_button: gtk::Button,
 }
 
@@ -156,6 +649,14 @@ pub fn sampleset_export(model_ptr: AppModelPtr, view: &AsampoView, model: AppMod
         .object::<gtk::Entry>("target-directory-entry")
         .unwrap();
 
+    let filename_template_entry = objects
+        .object::<gtk::Entry>("filename-template-entry")
+        .unwrap();
+
+    let filename_template_error_label = objects
+        .object::<gtk::Label>("filename-template-error-label")
+        .unwrap();
+
     let browse_button = objects.object::<gtk::Button>("browse-button").unwrap();
     let export_button = objects.object::<gtk::Button>("export-button").unwrap();
     let cancel_button = objects.object::<gtk::Button>("cancel-button").unwrap();
@@ -168,18 +669,36 @@ pub fn sampleset_export(model_ptr: AppModelPtr, view: &AsampoView, model: AppMod
         .object::<gtk::CheckButton>("convert-radio-button")
         .unwrap();
 
+    let sfz_radio = objects
+        .object::<gtk::CheckButton>("sfz-radio-button")
+        .unwrap();
+
     target_dir_entry.set_text(&model.viewvalues.sets_export_target_dir_entry);
-    export_button.set_sensitive(target_dir_entry.text_length() > 0);
+    filename_template_entry.set_text(&model.viewvalues.sets_export_filename_template_entry);
+
+    let template_error = &model.viewvalues.sets_export_filename_template_error;
+    filename_template_error_label.set_text(template_error.as_deref().unwrap_or_default());
+    filename_template_error_label.set_visible(template_error.is_some());
+
+    export_button.set_sensitive(target_dir_entry.text_length() > 0 && template_error.is_none());
 
     match model.viewvalues.sets_export_kind {
         Some(crate::model::ExportKind::PlainCopy) => {
             plain_copy_radio.set_active(true);
             convert_radio.set_active(false);
+            sfz_radio.set_active(false);
         }
 
         Some(crate::model::ExportKind::Conversion) => {
             plain_copy_radio.set_active(false);
             convert_radio.set_active(true);
+            sfz_radio.set_active(false);
+        }
+
+        Some(crate::model::ExportKind::SfzInstrument) => {
+            plain_copy_radio.set_active(false);
+            convert_radio.set_active(false);
+            sfz_radio.set_active(true);
         }
 
         None => (),
@@ -195,6 +714,16 @@ pub fn sampleset_export(model_ptr: AppModelPtr, view: &AsampoView, model: AppMod
         }),
     );
 
+    filename_template_entry.connect_changed(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::Entry| {
+            update(
+                model_ptr.clone(),
+                &view,
+                AppMessage::ExportFilenameTemplateChanged(e.text().to_string())
+            );
+        }),
+    );
+
     browse_button.connect_clicked(
         clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
             update(model_ptr.clone(), &view, AppMessage::ExportTargetDirectoryBrowseClicked);
@@ -229,6 +758,14 @@ pub fn sampleset_export(model_ptr: AppModelPtr, view: &AsampoView, model: AppMod
         }),
     );
 
+    sfz_radio.connect_toggled(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::CheckButton| {
+            if e.is_active() {
+                update(model_ptr.clone(), &view, AppMessage::SfzExportSelected);
+            }
+        }),
+    );
+
     dialogwin.connect_close_request(
         clone!(@strong model_ptr, @strong view => move |_: &gtk::Window| {
             update(model_ptr.clone(), &view, AppMessage::ExportDialogClosed);
@@ -246,6 +783,8 @@ pub fn sampleset_export(model_ptr: AppModelPtr, view: &AsampoView, model: AppMod
         AppMessage::ExportDialogOpened(ExportDialogView {
             window: dialogwin.clone(),
             target_dir_entry: target_dir_entry.clone(),
+            filename_template_entry: filename_template_entry.clone(),
+            filename_template_error_label: filename_template_error_label.clone(),
             export_button: export_button.clone(),
         }),
     );
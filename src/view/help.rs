@@ -0,0 +1,212 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::{cell::OnceCell, rc::Rc};
+
+use gtk::{glib::clone, prelude::*};
+use serde::Deserialize;
+
+use crate::view::AsampoView;
+
+#[derive(Debug, Deserialize)]
+struct TourStep {
+    /// The page to switch to, or none to stay on the current page.
+    #[serde(default)]
+    page: Option<String>,
+    anchor: String,
+    title: String,
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelpTopic {
+    title: String,
+    text: String,
+}
+
+/// The guided tour and help topics, kept in the `help.json` resource.
+#[derive(Debug, Deserialize)]
+struct HelpContent {
+    tour: Vec<TourStep>,
+    topics: Vec<HelpTopic>,
+}
+
+thread_local! {
+    static HELP_CONTENT: OnceCell<Rc<HelpContent>> = const { OnceCell::new() };
+}
+
+fn help_content() -> Rc<HelpContent> {
+    HELP_CONTENT.with(|content| {
+        content
+            .get_or_init(|| {
+                let data = gtk::gio::resources_lookup_data(
+                    "/help.json",
+                    gtk::gio::ResourceLookupFlags::NONE,
+                )
+                .expect("Help content should be a compiled resource");
+
+                Rc::new(serde_json::from_slice(&data).expect("Help content should be valid JSON"))
+            })
+            .clone()
+    })
+}
+
+/// Show the tour popover for step `index`, switching to the page the step is about.
+pub fn show_tour_step(view: &AsampoView, index: usize) {
+    let help = help_content();
+
+    let Some(step) = help.tour.get(index) else {
+        return;
+    };
+
+    if let Some(page) = &step.page {
+        view.stack.set_visible_child_name(page);
+    }
+
+    let title = gtk::Label::new(Some(&step.title));
+    title.add_css_class("heading");
+    title.set_xalign(0.0);
+
+    let text = gtk::Label::new(Some(&step.text));
+    text.set_wrap(true);
+    text.set_max_width_chars(40);
+    text.set_xalign(0.0);
+
+    let progress = gtk::Label::new(Some(&format!("{}/{}", index + 1, help.tour.len())));
+    progress.set_hexpand(true);
+    progress.set_xalign(0.0);
+
+    let is_last = index + 1 == help.tour.len();
+    let close_button = gtk::Button::with_label("Close");
+    let next_button = gtk::Button::with_label(if is_last { "Done" } else { "Next" });
+
+    let button_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    button_box.add_css_class("button-box");
+    button_box.append(&progress);
+
+    if !is_last {
+        button_box.append(&close_button);
+    }
+
+    button_box.append(&next_button);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    content.append(&title);
+    content.append(&text);
+    content.append(&button_box);
+
+    let popover = gtk::Popover::new();
+    popover.add_css_class("tour-popover");
+    popover.set_child(Some(&content));
+    popover.set_parent(&tour_anchor(view, &step.anchor));
+
+    popover.connect_closed(|popover| {
+        gtk::glib::idle_add_local_once(clone!(@strong popover => move || {
+            popover.unparent();
+        }));
+    });
+
+    close_button.connect_clicked(clone!(@strong popover => move |_| {
+        popover.popdown();
+    }));
+
+    next_button.connect_clicked(clone!(@strong view, @strong popover => move |_| {
+        popover.popdown();
+        show_tour_step(&view, index + 1);
+    }));
+
+    popover.popup();
+}
+
+/// The widget a tour step named by `anchor` points at, or the page stack if none matches.
+fn tour_anchor(view: &AsampoView, anchor: &str) -> gtk::Widget {
+    match anchor {
+        "sources-add" => view.sources_add_frame.get().upcast(),
+        "samples-filter" => view.samples_list_filter_entry.get().upcast(),
+        "samples-group-by-source" => view.samples_group_by_source_button.get().upcast(),
+        "samples-compare" => view.samples_sidebar_compare_switch_button.get().upcast(),
+        "samples-add-to-set" => view.samples_sidebar_add_to_set_button.get().upcast(),
+        "sets-list" => view.sets_list_frame.get().upcast(),
+        "sequences-list" => view.sequences_list_frame.get().upcast(),
+        "main-menu" => view.main_menu_button.get().upcast(),
+        _ => view.stack.get().upcast(),
+    }
+}
+
+pub fn help(view: &AsampoView) {
+    let objects = gtk::Builder::from_resource("/help-dialog.ui");
+
+    let dialogwin = objects.object::<gtk::Window>("help-dialog-window").unwrap();
+
+    let search_entry = objects.object::<gtk::SearchEntry>("search-entry").unwrap();
+    let topics_list = objects.object::<gtk::ListBox>("topics-list").unwrap();
+    let topic_text = objects.object::<gtk::Label>("topic-text").unwrap();
+
+    let content = help_content();
+
+    for topic in content.topics.iter() {
+        let label = gtk::Label::new(Some(&topic.title));
+        label.set_xalign(0.0);
+        topics_list.append(&label);
+    }
+
+    topics_list.connect_row_selected(
+        clone!(@strong topic_text, @strong content => move |_, row| {
+            if let Some(topic) = row.and_then(|row| content.topics.get(row.index() as usize)) {
+                topic_text.set_text(&topic.text);
+            }
+        }),
+    );
+
+    topics_list.set_filter_func(clone!(@strong search_entry, @strong content => move |row| {
+        let query = search_entry.text().to_lowercase();
+
+        content.topics.get(row.index() as usize).is_some_and(|topic| {
+            query.split_whitespace().all(|word| {
+                topic.title.to_lowercase().contains(word)
+                    || topic.text.to_lowercase().contains(word)
+            })
+        })
+    }));
+
+    search_entry.connect_search_changed(clone!(@strong topics_list => move |_| {
+        topics_list.invalidate_filter();
+    }));
+
+    topics_list.select_row(topics_list.row_at_index(0).as_ref());
+
+    objects
+        .object::<gtk::Button>("tour-button")
+        .unwrap()
+        .connect_clicked(
+            clone!(@strong view, @strong dialogwin => move |_: &gtk::Button| {
+                dialogwin.close();
+                show_tour_step(&view, 0);
+            }),
+        );
+
+    objects
+        .object::<gtk::Button>("ok-button")
+        .unwrap()
+        .connect_clicked(clone!(@strong dialogwin => move |_: &gtk::Button| {
+            dialogwin.close();
+        }));
+
+    dialogwin.set_transient_for(Some(view));
+    dialogwin.present();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_help_content_is_valid() {
+        let content =
+            serde_json::from_str::<HelpContent>(include_str!("../../resources/help.json")).unwrap();
+
+        assert!(!content.tour.is_empty());
+        assert!(!content.topics.is_empty());
+    }
+}
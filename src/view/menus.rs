@@ -2,14 +2,23 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
-use gtk::{gio::ActionEntry, glib::clone, prelude::*, Application};
+use std::path::Path;
 
-use crate::{model::AppModelPtr, update, view::AsampoView, AppMessage};
+use gtk::{gio::ActionEntry, glib::clone, prelude::*, Application, GestureClick};
+use uuid::Uuid;
+
+use crate::{
+    config::AppConfig,
+    model::AppModelPtr,
+    update,
+    view::{help, AsampoView},
+    AppMessage,
+};
 
 pub fn build_actions(app: &Application, model_ptr: AppModelPtr, view: &AsampoView) {
     let action_open_savefile = ActionEntry::builder("open_savefile")
         .activate(
-            clone!(@strong model_ptr, @strong view => move |_app: &Application, _, _| {
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, _| {
                 let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
                 let filter_json = gtk::FileFilter::new();
 
@@ -46,7 +55,7 @@ pub fn build_actions(app: &Application, model_ptr: AppModelPtr, view: &AsampoVie
 
     let action_save = ActionEntry::builder("save")
         .activate(
-            clone!(@strong model_ptr, @strong view  => move |_app: &Application, _, _| {
+            clone!(@strong model_ptr, @strong view  => move |_: &AsampoView, _, _| {
                 let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
                 let filter_json = gtk::FileFilter::new();
 
@@ -90,5 +99,374 @@ pub fn build_actions(app: &Application, model_ptr: AppModelPtr, view: &AsampoVie
         )
         .build();
 
-    app.add_action_entries([action_open_savefile, action_save]);
+    let action_open_recent = ActionEntry::builder("open_recent")
+        .parameter_type(Some(&String::static_variant_type()))
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, param| {
+                if let Some(filename) = param.and_then(|p| p.get::<String>()) {
+                    update(model_ptr.clone(), &view, AppMessage::LoadFromSavefile(filename));
+                }
+            }),
+        )
+        .build();
+
+    let action_quick_export = ActionEntry::builder("quick_export")
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, _| {
+                update(model_ptr.clone(), &view, AppMessage::QuickExportRequested);
+            }),
+        )
+        .build();
+
+    let action_export_settings = ActionEntry::builder("export_settings")
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, _| {
+                json_file_dialog()
+                    .initial_name("asampo-settings.json")
+                    .build()
+                    .save(
+                        Some(&view),
+                        None::<gtk::gio::Cancellable>.as_ref(),
+                        clone!(@strong model_ptr, @strong view => move |result| {
+                            match result {
+                                Ok(gfile) => update(
+                                    model_ptr.clone(),
+                                    &view,
+                                    AppMessage::ExportSettings(
+                                        gfile
+                                            .path()
+                                            .unwrap()
+                                            .into_os_string()
+                                            .into_string()
+                                            .unwrap()
+                                    )
+                                ),
+
+                                Err(e) => update(model_ptr.clone(), &view, AppMessage::DialogError(e)),
+                            }
+                        })
+                    );
+            }),
+        )
+        .build();
+
+    let action_import_settings = ActionEntry::builder("import_settings")
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, _| {
+                json_file_dialog().build().open(
+                    Some(&view),
+                    None::<gtk::gio::Cancellable>.as_ref(),
+                    clone!(@strong model_ptr, @strong view => move |result| {
+                        match result {
+                            Ok(gfile) => update(
+                                model_ptr.clone(),
+                                &view,
+                                AppMessage::ImportSettings(
+                                    gfile
+                                        .path()
+                                        .unwrap()
+                                        .into_os_string()
+                                        .into_string()
+                                        .unwrap()
+                                )
+                            ),
+
+                            Err(e) => update(model_ptr.clone(), &view, AppMessage::DialogError(e)),
+                        }
+                    })
+                );
+            }),
+        )
+        .build();
+
+    let action_import_kit = ActionEntry::builder("import_kit")
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, _| {
+                kit_file_dialog().build().open(
+                    Some(&view),
+                    None::<gtk::gio::Cancellable>.as_ref(),
+                    clone!(@strong model_ptr, @strong view => move |result| {
+                        match result {
+                            Ok(gfile) => update(
+                                model_ptr.clone(),
+                                &view,
+                                AppMessage::ImportKit(
+                                    gfile
+                                        .path()
+                                        .unwrap()
+                                        .into_os_string()
+                                        .into_string()
+                                        .unwrap()
+                                )
+                            ),
+
+                            Err(e) => update(model_ptr.clone(), &view, AppMessage::DialogError(e)),
+                        }
+                    })
+                );
+            }),
+        )
+        .build();
+
+    let action_find_duplicates = ActionEntry::builder("find_duplicates")
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, _| {
+                update(model_ptr.clone(), &view, AppMessage::FindDuplicatesRequested);
+            }),
+        )
+        .build();
+
+    let action_help = ActionEntry::builder("help")
+        .activate(clone!(@strong view => move |_: &AsampoView, _, _| {
+            help::help(&view);
+        }))
+        .build();
+
+    let action_tour = ActionEntry::builder("tour")
+        .activate(clone!(@strong view => move |_: &AsampoView, _, _| {
+            help::show_tour_step(&view, 0);
+        }))
+        .build();
+
+    view.add_action_entries([
+        action_open_savefile,
+        action_open_recent,
+        action_save,
+        action_quick_export,
+        action_export_settings,
+        action_import_settings,
+        action_import_kit,
+        action_find_duplicates,
+        action_help,
+        action_tour,
+    ]);
+
+    // actions of the context menus of list rows, the uuid ones taking the uuid of the row
+    view.add_action_entries([
+        uuid_action(
+            "source_rescan",
+            &model_ptr,
+            view,
+            AppMessage::SourceRescanClicked,
+        ),
+        uuid_action(
+            "source_delete",
+            &model_ptr,
+            view,
+            AppMessage::SourceDeleteClicked,
+        ),
+        uuid_action(
+            "set_rename",
+            &model_ptr,
+            view,
+            AppMessage::SampleSetRenameClicked,
+        ),
+        uuid_action(
+            "set_duplicate",
+            &model_ptr,
+            view,
+            AppMessage::SampleSetDuplicateClicked,
+        ),
+        uuid_action(
+            "set_export",
+            &model_ptr,
+            view,
+            AppMessage::SampleSetExportClicked,
+        ),
+        plain_action("sample_rename", &model_ptr, view, || {
+            AppMessage::SampleSidebarRenameClicked
+        }),
+        plain_action("sample_add_to_set", &model_ptr, view, || {
+            AppMessage::SampleSidebarAddToSetClicked
+        }),
+        plain_action("sample_open_folder", &model_ptr, view, || {
+            AppMessage::SampleOpenFolderClicked
+        }),
+    ]);
+
+    // the state of audio_profile is the name of the active profile, for the radio items
+    view.add_action_entries([
+        ActionEntry::builder("audio_profile")
+            .parameter_type(Some(&String::static_variant_type()))
+            .state(String::new().to_variant())
+            .activate(
+                clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, param| {
+                    if let Some(name) = param.and_then(|p| p.get::<String>()) {
+                        update(model_ptr.clone(), &view, AppMessage::AudioProfileSelected(name));
+                    }
+                }),
+            )
+            .build(),
+        plain_action("audio_profile_save", &model_ptr, view, || {
+            AppMessage::AudioProfileSaveClicked
+        }),
+        plain_action("audio_profile_delete", &model_ptr, view, || {
+            AppMessage::AudioProfileDeleteClicked
+        }),
+    ]);
+
+    app.set_accels_for_action("win.quick_export", &["<Control>e"]);
+    app.set_accels_for_action("win.help", &["F1"]);
+}
+
+fn uuid_action(
+    name: &str,
+    model_ptr: &AppModelPtr,
+    view: &AsampoView,
+    message: fn(Uuid) -> AppMessage,
+) -> ActionEntry<AsampoView> {
+    ActionEntry::builder(name)
+        .parameter_type(Some(&String::static_variant_type()))
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, param| {
+                if let Some(uuid) = param
+                    .and_then(|p| p.get::<String>())
+                    .and_then(|s| Uuid::parse_str(&s).ok())
+                {
+                    update(model_ptr.clone(), &view, message(uuid));
+                }
+            }),
+        )
+        .build()
+}
+
+fn plain_action(
+    name: &str,
+    model_ptr: &AppModelPtr,
+    view: &AsampoView,
+    message: fn() -> AppMessage,
+) -> ActionEntry<AsampoView> {
+    ActionEntry::builder(name)
+        .activate(
+            clone!(@strong model_ptr, @strong view => move |_: &AsampoView, _, _| {
+                update(model_ptr.clone(), &view, message());
+            }),
+        )
+        .build()
+}
+
+/// A menu of `(label, action)` items, each action taking `uuid` as its parameter.
+pub fn uuid_menu(items: &[(&str, &str)], uuid: &Uuid) -> gtk::gio::Menu {
+    let menu = gtk::gio::Menu::new();
+
+    for (label, action) in items {
+        let item = gtk::gio::MenuItem::new(Some(label), None);
+        item.set_action_and_target_value(Some(action), Some(&uuid.to_string().to_variant()));
+        menu.append_item(&item);
+    }
+
+    menu
+}
+
+/// Show `menu` when `widget` is right-clicked.
+pub fn add_context_menu(widget: &impl IsA<gtk::Widget>, menu: gtk::gio::Menu) {
+    let clicked = GestureClick::new();
+    clicked.set_button(gtk::gdk::BUTTON_SECONDARY);
+
+    clicked.connect_pressed(move |e: &GestureClick, _, x, y| {
+        if let Some(widget) = e.widget() {
+            popup_context_menu(&widget, &menu, x, y);
+        }
+    });
+
+    widget.add_controller(clicked);
+}
+
+/// Show `menu` at position `(x, y)` of `widget`.
+pub fn popup_context_menu(widget: &gtk::Widget, menu: &gtk::gio::Menu, x: f64, y: f64) {
+    let popover = gtk::PopoverMenu::from_model(Some(menu));
+    popover.set_parent(widget);
+    popover.set_has_arrow(false);
+    popover.set_halign(gtk::Align::Start);
+    popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+
+    // unparent only once the chosen item's action has been activated
+    popover.connect_closed(|popover: &gtk::PopoverMenu| {
+        gtk::glib::idle_add_local_once(clone!(@strong popover => move || {
+            popover.unparent();
+        }));
+    });
+
+    popover.popup();
+}
+
+fn json_file_dialog() -> gtk::builders::FileDialogBuilder {
+    let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+    let filter_json = gtk::FileFilter::new();
+
+    filter_json.add_suffix("json");
+    filters.append(&filter_json);
+
+    gtk::FileDialog::builder().modal(true).filters(&filters)
+}
+
+/// A file dialog for SFZ instruments and Hydrogen drumkits.
+fn kit_file_dialog() -> gtk::builders::FileDialogBuilder {
+    let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+    let filter_kits = gtk::FileFilter::new();
+
+    filter_kits.set_name(Some("Drum kits (SFZ, Hydrogen)"));
+    filter_kits.add_suffix("sfz");
+    filter_kits.add_pattern("drumkit.xml");
+    filters.append(&filter_kits);
+
+    gtk::FileDialog::builder().modal(true).filters(&filters)
+}
+
+pub fn update_recent_savefiles_menu(view: &AsampoView, config: &AppConfig) {
+    view.main_menu_recent.remove_all();
+
+    for filename in config.recent_savefiles.iter() {
+        let label = Path::new(filename)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(filename.clone());
+
+        let item = gtk::gio::MenuItem::new(Some(&label), None);
+        item.set_action_and_target_value(Some("win.open_recent"), Some(&filename.to_variant()));
+
+        view.main_menu_recent.append_item(&item);
+    }
+}
+
+/// List the audio profiles in the audio profile menus, marking the active one.
+pub fn update_audio_profiles_menu(view: &AsampoView, config: &AppConfig) {
+    view.audio_profiles_menu_profiles.remove_all();
+
+    for profile in config.audio_profiles.iter() {
+        let item = gtk::gio::MenuItem::new(Some(&profile.name), None);
+        item.set_action_and_target_value(
+            Some("win.audio_profile"),
+            Some(&profile.name.to_variant()),
+        );
+
+        view.audio_profiles_menu_profiles.append_item(&item);
+    }
+
+    let active = config
+        .active_audio_profile()
+        .map(|profile| profile.name.clone());
+
+    if let Some(action) = view
+        .lookup_action("audio_profile")
+        .and_downcast::<gtk::gio::SimpleAction>()
+    {
+        action.set_state(&active.clone().unwrap_or_default().to_variant());
+    }
+
+    if let Some(action) = view
+        .lookup_action("audio_profile_delete")
+        .and_downcast::<gtk::gio::SimpleAction>()
+    {
+        action.set_enabled(active.is_some());
+    }
+
+    view.settings_audio_profile_button
+        .set_label(active.as_deref().unwrap_or("None"));
+
+    view.titlebar_audio_profile_button
+        .set_tooltip_text(Some(&match &active {
+            Some(name) => format!("Audio profile: {name}"),
+            None => "Audio profile".to_string(),
+        }));
 }
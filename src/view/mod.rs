@@ -3,7 +3,9 @@
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
 pub mod dialogs;
+pub mod help;
 pub mod menus;
+pub mod profiler;
 pub mod samples;
 pub mod sequences;
 pub mod sets;
@@ -22,9 +24,39 @@ pub struct AsampoViewState {
     #[template_child(id = "titlebar-stop-button")]
     pub titlebar_stop_button: gtk::TemplateChild<gtk::Button>,
 
+    #[template_child(id = "titlebar-mute-button")]
+    pub titlebar_mute_button: gtk::TemplateChild<gtk::ToggleButton>,
+
+    #[template_child(id = "titlebar-preview-volume-scale")]
+    pub titlebar_preview_volume_scale: gtk::TemplateChild<gtk::Scale>,
+
+    #[template_child(id = "titlebar-audio-profile-button")]
+    pub titlebar_audio_profile_button: gtk::TemplateChild<gtk::MenuButton>,
+
+    #[template_child(id = "profiler-overlay")]
+    pub profiler_overlay: gtk::TemplateChild<gtk::Frame>,
+
+    #[template_child(id = "profiler-overlay-label")]
+    pub profiler_overlay_label: gtk::TemplateChild<gtk::Label>,
+
     #[template_child(id = "main-menu-button")]
     pub main_menu_button: gtk::TemplateChild<gtk::MenuButton>,
 
+    #[template_child(id = "main-menu-recent")]
+    pub main_menu_recent: gtk::TemplateChild<gtk::gio::Menu>,
+
+    #[template_child(id = "audio-profiles-menu-profiles")]
+    pub audio_profiles_menu_profiles: gtk::TemplateChild<gtk::gio::Menu>,
+
+    #[template_child(id = "settings-audio-profile-button")]
+    pub settings_audio_profile_button: gtk::TemplateChild<gtk::MenuButton>,
+
+    #[template_child(id = "toast")]
+    pub toast: gtk::TemplateChild<gtk::Frame>,
+
+    #[template_child(id = "toast-label")]
+    pub toast_label: gtk::TemplateChild<gtk::Label>,
+
     #[template_child(id = "progress-popup")]
     pub progress_popup: gtk::TemplateChild<gtk::Frame>,
 
@@ -43,12 +75,69 @@ pub struct AsampoViewState {
     #[template_child(id = "settings-latency-approx-label")]
     pub settings_latency_approx_label: gtk::TemplateChild<gtk::Label>,
 
+    #[template_child(id = "settings-test-output-button")]
+    pub settings_test_output_button: gtk::TemplateChild<gtk::Button>,
+
     #[template_child(id = "settings-sample-rate-conversion-quality-entry")]
     pub settings_sample_rate_conversion_quality_entry: gtk::TemplateChild<gtk::DropDown>,
 
     #[template_child(id = "settings-sample-playback-behavior-entry")]
     pub settings_sample_playback_behavior_entry: gtk::TemplateChild<gtk::DropDown>,
 
+    #[template_child(id = "settings-workspace-sample-playback-behavior-entry")]
+    pub settings_workspace_sample_playback_behavior_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-sample-filter-mode-entry")]
+    pub settings_sample_filter_mode_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-watch-sources-entry")]
+    pub settings_watch_sources_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-source-loading-entry")]
+    pub settings_source_loading_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-samples-list-keep-place-entry")]
+    pub settings_samples_list_keep_place_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-stop-preview-on-page-change-entry")]
+    pub settings_stop_preview_on_page_change_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-velocity-curve-entry")]
+    pub settings_velocity_curve_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-velocity-curve-preview")]
+    pub settings_velocity_curve_preview: gtk::TemplateChild<gtk::DrawingArea>,
+
+    #[template_child(id = "settings-pad-labels-entry")]
+    pub settings_pad_labels_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-pad-labels-edit-button")]
+    pub settings_pad_labels_edit_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "settings-color-scheme-entry")]
+    pub settings_color_scheme_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-ui-scale-entry")]
+    pub settings_ui_scale_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-troubleshooting-log-entry")]
+    pub settings_troubleshooting_log_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-profiler-overlay-entry")]
+    pub settings_profiler_overlay_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-savefile-encryption-entry")]
+    pub settings_savefile_encryption_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-savefile-password-memory-entry")]
+    pub settings_savefile_password_memory_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-reopen-last-workspace-entry")]
+    pub settings_reopen_last_workspace_entry: gtk::TemplateChild<gtk::DropDown>,
+
+    #[template_child(id = "settings-review-changes-entry")]
+    pub settings_review_changes_entry: gtk::TemplateChild<gtk::DropDown>,
+
     #[template_child(id = "settings-config-save-path-entry")]
     pub settings_config_save_path_entry: gtk::TemplateChild<gtk::Entry>,
 
@@ -94,18 +183,66 @@ pub struct AsampoViewState {
     #[template_child(id = "sources-list")]
     pub sources_list: gtk::TemplateChild<gtk::ListBox>,
 
+    #[template_child(id = "sources-overlap-bar")]
+    pub sources_overlap_bar: gtk::TemplateChild<gtk::Box>,
+
+    #[template_child(id = "sources-dedup-checkbutton")]
+    pub sources_dedup_checkbutton: gtk::TemplateChild<gtk::CheckButton>,
+
     #[template_child(id = "samples-list-filter-entry")]
     pub samples_list_filter_entry: gtk::TemplateChild<gtk::Entry>,
 
+    #[template_child(id = "samples-list-searches-button")]
+    pub samples_list_searches_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "samples-list-history-button")]
+    pub samples_list_history_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "samples-favorites-only-button")]
+    pub samples_favorites_only_button: gtk::TemplateChild<gtk::ToggleButton>,
+
     #[template_child(id = "samples-listview")]
     pub samples_listview: gtk::TemplateChild<gtk::ListView>,
 
+    #[template_child(id = "samples-group-by-source-button")]
+    pub samples_group_by_source_button: gtk::TemplateChild<gtk::ToggleButton>,
+
+    #[template_child(id = "samples-split-view-button")]
+    pub samples_split_view_button: gtk::TemplateChild<gtk::ToggleButton>,
+
+    #[template_child(id = "samples-split-list-box")]
+    pub samples_split_list_box: gtk::TemplateChild<gtk::Box>,
+
+    #[template_child(id = "samples-split-list-filter-entry")]
+    pub samples_split_list_filter_entry: gtk::TemplateChild<gtk::Entry>,
+
+    #[template_child(id = "samples-split-listview")]
+    pub samples_split_listview: gtk::TemplateChild<gtk::ListView>,
+
     #[template_child(id = "samples-sidebar-waveform")]
     pub samples_sidebar_waveform: gtk::TemplateChild<gtk::DrawingArea>,
 
     #[template_child(id = "samples-sidebar-name-label")]
     pub samples_sidebar_name_label: gtk::TemplateChild<gtk::Label>,
 
+    #[template_child(id = "samples-sidebar-favorite-button")]
+    pub samples_sidebar_favorite_button: gtk::TemplateChild<gtk::ToggleButton>,
+
+    #[template_child(id = "samples-sidebar-rename-button")]
+    pub samples_sidebar_rename_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "samples-sidebar-compare-a-button")]
+    pub samples_sidebar_compare_a_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "samples-sidebar-compare-b-button")]
+    pub samples_sidebar_compare_b_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "samples-sidebar-compare-switch-button")]
+    pub samples_sidebar_compare_switch_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "samples-sidebar-pitch-entry")]
+    pub samples_sidebar_pitch_entry: gtk::TemplateChild<gtk::SpinButton>,
+
     #[template_child(id = "samples-sidebar-format-label")]
     pub samples_sidebar_format_label: gtk::TemplateChild<gtk::Label>,
 
@@ -139,9 +276,18 @@ pub struct AsampoViewState {
     #[template_child(id = "sets-add-set-button")]
     pub sets_add_set_button: gtk::TemplateChild<gtk::Button>,
 
+    #[template_child(id = "sets-details-cover-picture")]
+    pub sets_details_cover_picture: gtk::TemplateChild<gtk::Picture>,
+
     #[template_child(id = "sets-details-name-label")]
     pub sets_details_name_label: gtk::TemplateChild<gtk::Label>,
 
+    #[template_child(id = "sets-details-cover-button")]
+    pub sets_details_cover_button: gtk::TemplateChild<gtk::Button>,
+
+    #[template_child(id = "sets-details-cover-clear-button")]
+    pub sets_details_cover_clear_button: gtk::TemplateChild<gtk::Button>,
+
     #[template_child(id = "sets-details-sample-list-frame")]
     pub sets_details_sample_list_frame: gtk::TemplateChild<gtk::Frame>,
 
@@ -154,6 +300,9 @@ pub struct AsampoViewState {
     #[template_child(id = "sets-details-export-button")]
     pub sets_details_export_button: gtk::TemplateChild<gtk::Button>,
 
+    #[template_child(id = "sets-details-stop-button")]
+    pub sets_details_stop_button: gtk::TemplateChild<gtk::Button>,
+
     #[template_child(id = "sequences-list-frame")]
     pub sequences_list_frame: gtk::TemplateChild<gtk::Frame>,
 
@@ -0,0 +1,60 @@
+// MIT License
+//
+// Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
+
+use std::{cell::RefCell, collections::VecDeque, time::Duration};
+
+use gtk::glib;
+
+use crate::{model::AppModel, view::AsampoView};
+
+const PROFILER_ENTRIES: usize = 12;
+const SLOW_THRESHOLD: Duration = Duration::from_millis(16);
+
+thread_local! {
+    static RECENT: RefCell<VecDeque<(String, Duration, Duration)>> =
+        const { RefCell::new(VecDeque::new()) };
+}
+
+pub fn is_enabled(model: &AppModel) -> bool {
+    model
+        .config
+        .as_ref()
+        .is_some_and(|config| config.profiler_overlay)
+}
+
+/// Add the timings of a handled message to the overlay, showing the most recent messages
+/// with the slow ones highlighted.
+pub fn record(view: &AsampoView, kind: &str, model_time: Duration, view_time: Duration) {
+    let markup = RECENT.with_borrow_mut(|recent| {
+        recent.push_back((kind.to_string(), model_time, view_time));
+
+        while recent.len() > PROFILER_ENTRIES {
+            recent.pop_front();
+        }
+
+        recent
+            .iter()
+            .map(|(kind, model_time, view_time)| {
+                let line = glib::markup_escape_text(&format!(
+                    "{kind:<40} model {:>8.2} ms  view {:>8.2} ms",
+                    model_time.as_secs_f64() * 1000.0,
+                    view_time.as_secs_f64() * 1000.0,
+                ));
+
+                if *model_time + *view_time >= SLOW_THRESHOLD {
+                    format!("<span foreground=\"#c00\" weight=\"bold\">{line}</span>")
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+
+    view.profiler_overlay_label.set_markup(&markup);
+}
+
+pub fn update_profiler_overlay(view: &AsampoView, model: &AppModel) {
+    view.profiler_overlay.set_visible(is_enabled(model));
+}
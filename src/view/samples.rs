@@ -2,7 +2,11 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use gtk::{
     glib::{
@@ -21,16 +25,19 @@ use libasampo::{prelude::*, samples::Sample, samplesets::SampleSet};
 use uuid::Uuid;
 
 use crate::{
-    model::AppModel,
+    model::{AppModel, CompareSlot, SampleListId},
     update,
     util::{self, resource_as_string, uuidize_builder_template},
-    view::AsampoView,
+    view::{menus, AsampoView},
     AppMessage, AppModelPtr, WithModel,
 };
 
 #[derive(Default, Debug)]
 pub struct SampleListEntryState {
     pub value: RefCell<Sample>,
+    pub highlight: RefCell<Vec<usize>>,
+    pub display_name: RefCell<Option<String>>,
+    pub favorite: RefCell<bool>,
 }
 
 #[glib::object_subclass]
@@ -52,6 +59,13 @@ impl SampleListEntry {
         x.value.replace(value);
         obj
     }
+
+    /// Create an entry with the given char positions of the uri highlighted in the list.
+    pub fn new_highlighted(value: Sample, highlight: Vec<usize>) -> Self {
+        let obj = Self::new(value);
+        obj.highlight.replace(highlight);
+        obj
+    }
 }
 
 impl std::ops::Deref for SampleListEntry {
@@ -62,127 +76,844 @@ impl std::ops::Deref for SampleListEntry {
     }
 }
 
+#[derive(Default, Debug)]
+pub struct SampleListGroupState {
+    pub uuid: RefCell<Uuid>,
+    pub name: RefCell<String>,
+    pub children: RefCell<Option<gtk::gio::ListModel>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for SampleListGroupState {
+    const NAME: &'static str = "SampleListGroup";
+    type Type = SampleListGroup;
+}
+
+impl ObjectImpl for SampleListGroupState {}
+
+glib::wrapper! {
+    /// A source header in the samples list when grouped by source, with the samples of the
+    /// source as children.
+    pub struct SampleListGroup(ObjectSubclass<SampleListGroupState>);
+}
+
+impl SampleListGroup {
+    pub fn new(uuid: Uuid, name: &str, children: gtk::gio::ListModel) -> Self {
+        let obj: Self = Object::builder().build();
+        obj.uuid.replace(uuid);
+        obj.name.replace(name.to_string());
+        obj.children.replace(Some(children));
+        obj
+    }
+}
+
+impl std::ops::Deref for SampleListGroup {
+    type Target = SampleListGroupState;
+
+    fn deref(&self) -> &Self::Target {
+        SampleListGroupState::from_obj(self)
+    }
+}
+
+const FILTER_DEBOUNCE: Duration = Duration::from_millis(250);
+
 pub fn setup_samples_page(model_ptr: AppModelPtr, view: &AsampoView) {
-    let factory = gtk::SignalListItemFactory::new();
+    setup_sample_list(model_ptr.clone(), view, SampleListId::Main);
+    setup_sample_list(model_ptr.clone(), view, SampleListId::Split);
 
-    factory.connect_setup(move |_, list_item| {
-        // TODO: move to builder xml template
-        let label = gtk::Label::new(None);
-        label.set_xalign(0.0);
+    view.samples_split_view_button.connect_toggled(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::ToggleButton| {
+            view.samples_split_list_box.set_visible(e.is_active());
+            update(model_ptr.clone(), &view, AppMessage::SamplesSplitViewToggled(e.is_active()));
+        }),
+    );
 
-        list_item
-            .downcast_ref::<gtk::ListItem>()
-            .expect("ListItem")
-            .set_child(Some(&label));
+    model_ptr.with_model(|model| {
+        view.samples_group_by_source_button
+            .set_active(model.samples_list_grouped());
+        model
     });
 
+    view.samples_group_by_source_button.connect_toggled(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::ToggleButton| {
+            update(model_ptr.clone(), &view, AppMessage::SamplesGroupBySourceToggled(e.is_active()));
+        }),
+    );
+
+    view.samples_favorites_only_button.connect_toggled(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::ToggleButton| {
+            update(model_ptr.clone(), &view, AppMessage::SamplesFavoritesOnlyToggled(e.is_active()));
+        }),
+    );
+
+    view.samples_sidebar_favorite_button.connect_toggled(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::ToggleButton| {
+            update(model_ptr.clone(), &view, AppMessage::SampleSidebarFavoriteToggled(e.is_active()));
+        }),
+    );
+
+    view.samples_sidebar_compare_a_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SampleCompareAssignClicked(CompareSlot::A));
+        }),
+    );
+
+    view.samples_sidebar_compare_b_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SampleCompareAssignClicked(CompareSlot::B));
+        }),
+    );
+
+    view.samples_sidebar_compare_switch_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SampleCompareSwitchClicked);
+        }),
+    );
+
+    view.samples_sidebar_pitch_entry.connect_value_changed(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::SpinButton| {
+            update(model_ptr.clone(), &view, AppMessage::SamplesPreviewPitchChanged(e.value_as_int()));
+        }),
+    );
+
+    view.samples_list_searches_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SamplesSearchesClicked);
+        }),
+    );
+
+    view.samples_list_history_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SamplesHistoryClicked);
+        }),
+    );
+
+    view.samples_sidebar_rename_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SampleSidebarRenameClicked);
+        }),
+    );
+
+    view.samples_sidebar_add_to_set_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SampleSidebarAddToSetClicked);
+        }),
+    );
+
+    view.samples_sidebar_add_to_prev_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(
+                model_ptr.clone(),
+                &view,
+                AppMessage::SampleSidebarAddToMostRecentlyUsedSetClicked
+            );
+        }),
+    );
+}
+
+/// Hook up one of the sample lists on the samples page, along with its filter entry.
+fn setup_sample_list(model_ptr: AppModelPtr, view: &AsampoView, id: SampleListId) {
+    let (listview, filter_entry) = match id {
+        SampleListId::Main => (
+            view.samples_listview.get(),
+            view.samples_list_filter_entry.get(),
+        ),
+        SampleListId::Split => (
+            view.samples_split_listview.get(),
+            view.samples_split_list_filter_entry.get(),
+        ),
+    };
+
+    let factory = gtk::SignalListItemFactory::new();
+
+    let context_menu = gtk::gio::Menu::new();
+    context_menu.append(Some("Rename…"), Some("win.sample_rename"));
+    context_menu.append(Some("Add to set…"), Some("win.sample_add_to_set"));
+    context_menu.append(
+        Some("Open containing folder"),
+        Some("win.sample_open_folder"),
+    );
+
+    factory.connect_setup(
+        clone!(@strong model_ptr, @strong view, @strong listview => move |_, list_item| {
+            let list_item = list_item
+                .downcast_ref::<gtk::ListItem>()
+                .expect("ListItem")
+                .clone();
+
+            // TODO: move to builder xml template
+            let label = gtk::Label::new(None);
+            label.set_xalign(0.0);
+            label.set_hexpand(true);
+            label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+
+            let star_button = gtk::Button::from_icon_name("non-starred-symbolic");
+            star_button.set_has_frame(false);
+            star_button.add_css_class("sample-star-button");
+
+            star_button.connect_clicked(
+                clone!(@strong model_ptr, @strong view, @weak list_item => move |_: &gtk::Button| {
+                    if let Some(entry) = list_item_entry(&list_item) {
+                        let favorite = *entry.favorite.borrow();
+                        let sample = entry.value.borrow().clone();
+
+                        update(
+                            model_ptr.clone(),
+                            &view,
+                            AppMessage::SampleFavoriteToggled(sample, !favorite)
+                        );
+                    }
+                }),
+            );
+
+            let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            row_box.append(&label);
+            row_box.append(&star_button);
+
+            // the expander is only active when the list is grouped by source
+            let expander = gtk::TreeExpander::new();
+            expander.set_child(Some(&row_box));
+
+            // the context menu acts on the selected sample, so select the row right-clicked on
+            let context_clicked = GestureClick::new();
+            context_clicked.set_button(gtk::gdk::BUTTON_SECONDARY);
+
+            context_clicked.connect_pressed(
+                clone!(@strong model_ptr, @strong view, @strong listview, @strong context_menu,
+                    @weak list_item => move |e: &GestureClick, _, x, y| {
+                        if !list_item.is_selectable() {
+                            return;
+                        }
+
+                        let selection = listview.model().unwrap();
+
+                        if !selection.is_selected(list_item.position()) {
+                            selection.select_item(list_item.position(), true);
+                            update(model_ptr.clone(), &view, selection_message(&listview, id));
+                        }
+
+                        if let Some(widget) = e.widget() {
+                            menus::popup_context_menu(&widget, &context_menu, x, y);
+                        }
+                    }
+                ),
+            );
+
+            expander.add_controller(context_clicked);
+            list_item.set_child(Some(&expander));
+        }),
+    );
+
     factory.connect_bind(move |_, list_item| {
-        let entry = list_item
-            .downcast_ref::<gtk::ListItem>()
-            .expect("ListItem")
-            .item()
-            .and_downcast::<SampleListEntry>()
-            .expect("Entry");
-        let label = list_item
-            .downcast_ref::<gtk::ListItem>()
-            .expect("ListItem")
+        let list_item = list_item.downcast_ref::<gtk::ListItem>().expect("ListItem");
+
+        let expander = list_item
             .child()
+            .and_downcast::<gtk::TreeExpander>()
+            .expect("TreeExpander");
+
+        let row_box = expander.child().and_downcast::<gtk::Box>().expect("Box");
+
+        let label = row_box
+            .first_child()
             .and_downcast::<gtk::Label>()
             .expect("Label");
 
-        label.set_label(
-            SampleListEntryState::from_obj(&entry)
-                .value
-                .borrow()
-                .uri()
-                .as_str(),
-        );
+        let star_button = row_box
+            .last_child()
+            .and_downcast::<gtk::Button>()
+            .expect("Button");
+
+        let (row, item) = match list_item
+            .item()
+            .expect("Item")
+            .downcast::<gtk::TreeListRow>()
+        {
+            Ok(row) => (Some(row.clone()), row.item().expect("Row item")),
+            Err(item) => (None, item),
+        };
+
+        expander.set_list_row(row.as_ref());
+
+        if let Some(group) = item.downcast_ref::<SampleListGroup>() {
+            list_item.set_selectable(false);
+            list_item.set_activatable(false);
+            label.set_markup(&format!(
+                "<b>{}</b>",
+                glib::markup_escape_text(&group.name.borrow())
+            ));
+            label.set_tooltip_text(None);
+            star_button.set_visible(false);
+            return;
+        }
+
+        list_item.set_selectable(true);
+        list_item.set_activatable(true);
+
+        let entry = item.downcast::<SampleListEntry>().expect("Entry");
+
+        let uri = SampleListEntryState::from_obj(&entry)
+            .value
+            .borrow()
+            .uri()
+            .as_str()
+            .to_string();
+
+        let highlight = entry.highlight.borrow();
+
+        match entry.display_name.borrow().as_ref() {
+            Some(name) => label.set_markup(&format!(
+                "<b>{}</b>  {}",
+                glib::markup_escape_text(name),
+                highlighted_markup(&uri, &highlight)
+            )),
+
+            // set_markup leaves use-markup on for recycled rows, set_text turns it off
+            None if highlight.is_empty() => label.set_text(&uri),
+            None => label.set_markup(&highlighted_markup(&uri, &highlight)),
+        }
+
+        label.set_tooltip_text(Some(&uri));
+
+        let favorite = *entry.favorite.borrow();
+
+        star_button.set_visible(true);
+        star_button.set_icon_name(match favorite {
+            true => "starred-symbolic",
+            false => "non-starred-symbolic",
+        });
+        star_button.set_tooltip_text(Some(match favorite {
+            true => "Unstar",
+            false => "Star",
+        }));
     });
 
-    let selectmodel = gtk::SingleSelection::new(None::<gtk::gio::ListStore>);
+    let selectmodel = gtk::MultiSelection::new(None::<gtk::gio::ListStore>);
 
     model_ptr.with_model(|model| {
-        selectmodel.set_model(Some(&model.viewvalues.samples_listview_model.clone()));
+        selectmodel.set_model(Some(
+            &model
+                .viewvalues
+                .samples_list(id)
+                .display_model(model.samples_list_grouped()),
+        ));
         model
     });
 
-    view.samples_listview.set_model(Some(&selectmodel));
-    view.samples_listview.set_factory(Some(&factory));
+    listview.set_model(Some(&selectmodel));
+    listview.set_factory(Some(&factory));
 
-    view.samples_listview
-        .connect_activate(clone!(@strong model_ptr, @strong view => move |_, _| {
-            update(
-                model_ptr.clone(),
-                &view,
-                AppMessage::SampleListSampleSelected(
-                    view.samples_listview.model().unwrap().selection().minimum()
-                )
-            );
-        }));
+    listview.connect_activate(clone!(@strong model_ptr, @strong view => move |listview, _| {
+        update(
+            model_ptr.clone(),
+            &view,
+            AppMessage::SampleListSampleSelected(id, listview.model().unwrap().selection().minimum())
+        );
+    }));
 
     let clicked = GestureClick::new();
 
     clicked.connect_released(
-        clone!(@strong model_ptr, @strong view => move |_, _, _, _| {
-            update(
-                model_ptr.clone(),
-                &view,
-                AppMessage::SampleListSampleSelected(
-                    view.samples_listview.model().unwrap().selection().minimum()
-                )
-            );
+        clone!(@strong model_ptr, @strong view, @strong listview => move |_, _, _, _| {
+            update(model_ptr.clone(), &view, selection_message(&listview, id));
         }),
     );
 
-    view.samples_listview.add_controller(clicked);
+    listview.add_controller(clicked);
 
     let keyed = EventControllerKey::new();
 
-    keyed.connect_key_released(
-        clone!(@strong model_ptr, @strong view => move |_, key: gtk::gdk::Key, _, _| {
+    keyed.connect_key_released(clone!(@strong model_ptr, @strong view, @strong listview =>
+        move |_, key: gtk::gdk::Key, _, _| {
             if key == gtk::gdk::Key::Return {
                 return;
             }
 
-            update(
-                model_ptr.clone(),
-                &view,
-                AppMessage::SampleListSampleSelected(
-                    view.samples_listview.model().unwrap().selection().minimum()
-                )
-            );
+            update(model_ptr.clone(), &view, selection_message(&listview, id));
+        }
+    ));
+
+    listview.add_controller(keyed);
+
+    // wait for a pause in typing before refiltering, since refiltering a large library on
+    // every keystroke makes the filter entry lag behind
+    let filter_timeout = Rc::new(RefCell::new(None::<glib::SourceId>));
+
+    filter_entry.connect_changed(
+        clone!(@strong model_ptr, @strong view, @strong filter_timeout => move |e: &gtk::Entry| {
+            if let Some(source_id) = filter_timeout.take() {
+                source_id.remove();
+            }
+
+            filter_timeout.replace(Some(glib::timeout_add_local_once(
+                FILTER_DEBOUNCE,
+                clone!(@strong model_ptr, @strong view, @strong filter_timeout, @strong e => move || {
+                    filter_timeout.take();
+                    update(model_ptr.clone(), &view, AppMessage::SamplesFilterChanged(id, e.text().to_string()));
+                }),
+            )));
         }),
     );
 
-    view.samples_listview.add_controller(keyed);
+    filter_entry.connect_activate(
+        clone!(@strong model_ptr, @strong view, @strong filter_timeout => move |e: &gtk::Entry| {
+            // apply a pending filter change right away, so that it's the one remembered
+            if let Some(source_id) = filter_timeout.take() {
+                source_id.remove();
+                update(model_ptr.clone(), &view, AppMessage::SamplesFilterChanged(id, e.text().to_string()));
+            }
 
-    view.samples_list_filter_entry.connect_changed(
-        clone!(@strong model_ptr, @strong view => move |e: &gtk::Entry| {
-            update(model_ptr.clone(), &view, AppMessage::SamplesFilterChanged(e.text().to_string()));
+            update(model_ptr.clone(), &view, AppMessage::SamplesFilterActivated(id));
         }),
     );
+}
 
-    view.samples_sidebar_add_to_set_button.connect_clicked(
-        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
-            update(model_ptr.clone(), &view, AppMessage::SampleSidebarAddToSetClicked);
+fn highlighted_markup(text: &str, highlight: &[usize]) -> String {
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let escaped = glib::markup_escape_text(&c.to_string());
+
+            if highlight.contains(&i) {
+                format!("<b>{escaped}</b>")
+            } else {
+                escaped.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Switch to the samples page and select the sample being revealed, which should be listed
+/// by the current filter.
+pub fn reveal_sample(model_ptr: AppModelPtr, view: &AsampoView, model: &AppModel) {
+    view.stack.set_visible_child_name("samples");
+
+    if view.samples_list_filter_entry.text() != model.viewvalues.samples_list.filter {
+        view.samples_list_filter_entry
+            .set_text(&model.viewvalues.samples_list.filter);
+    }
+
+    if let Some(sample) = &model.viewvalues.samples_reveal {
+        let uri = sample.uri().as_str();
+
+        let position = model
+            .viewvalues
+            .samples_list
+            .position_of(uri, model.samples_list_grouped());
+
+        match position {
+            Some(position) => view.samples_listview.scroll_to(
+                position,
+                gtk::ListScrollFlags::SELECT | gtk::ListScrollFlags::FOCUS,
+                None,
+            ),
+
+            None => log::log!(log::Level::Warn, "Sample to reveal is not listed"),
+        }
+    }
+
+    update(model_ptr.clone(), view, AppMessage::SampleRevealed);
+}
+
+/// Open the folder containing the selected sample in the file manager.
+pub fn open_sample_folder(model_ptr: AppModelPtr, view: &AsampoView, model: &AppModel) {
+    if let Some(sample) = &model.samplelist_selected_sample {
+        gtk::FileLauncher::new(Some(&gtk::gio::File::for_path(sample.uri().as_str())))
+            .open_containing_folder(
+                Some(view),
+                None::<gtk::gio::Cancellable>.as_ref(),
+                |result| {
+                    if let Err(e) = result {
+                        log::log!(log::Level::Error, "Failed to open sample folder: {e}");
+                    }
+                },
+            );
+    }
+
+    update(model_ptr.clone(), view, AppMessage::SampleFolderOpened);
+}
+
+/// Show the sample lists grouped by source or not, following the config.
+pub fn update_samples_list_grouping(view: &AsampoView, model: &AppModel) {
+    let grouped = model.samples_list_grouped();
+
+    for (id, listview) in [
+        (SampleListId::Main, &view.samples_listview),
+        (SampleListId::Split, &view.samples_split_listview),
+    ] {
+        if let Some(selectmodel) = listview.model().and_downcast::<gtk::MultiSelection>() {
+            selectmodel.set_model(Some(
+                &model.viewvalues.samples_list(id).display_model(grouped),
+            ));
+        }
+    }
+
+    if view.samples_group_by_source_button.is_active() != grouped {
+        view.samples_group_by_source_button.set_active(grouped);
+    }
+}
+
+/// The sample entry shown by a list item, unless it shows a source group header.
+fn list_item_entry(list_item: &gtk::ListItem) -> Option<SampleListEntry> {
+    let item = list_item.item()?;
+
+    match item.downcast::<gtk::TreeListRow>() {
+        Ok(row) => row.item()?.downcast::<SampleListEntry>().ok(),
+        Err(item) => item.downcast::<SampleListEntry>().ok(),
+    }
+}
+
+/// Selecting a single sample plays it, while a multiple selection only marks the samples for
+/// bulk operations such as adding them to a set.
+fn selection_message(listview: &gtk::ListView, id: SampleListId) -> AppMessage {
+    let selection = listview.model().unwrap().selection();
+
+    if selection.size() == 1 {
+        AppMessage::SampleListSampleSelected(id, selection.minimum())
+    } else {
+        AppMessage::SampleListMultipleSelected(
+            id,
+            (0..selection.size() as u32)
+                .map(|n| selection.nth(n))
+                .collect(),
+        )
+    }
+}
+
+pub fn add_to_set_popover(model_ptr: AppModelPtr, view: &AsampoView, model: &AppModel) {
+    let sets = model.sets_by_recent_use();
+
+    let popover = gtk::Popover::new();
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 4);
+
+    let filter_entry = gtk::SearchEntry::new();
+    filter_entry.set_placeholder_text(Some("Filter sets"));
+
+    let sets_list = gtk::ListBox::new();
+    sets_list.set_selection_mode(gtk::SelectionMode::None);
+    sets_list.set_activate_on_single_click(true);
+
+    for (_, set) in sets.iter() {
+        let label = gtk::Label::new(Some(set.name()));
+        label.set_xalign(0.0);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        label.set_max_width_chars(24);
+        sets_list.append(&label);
+    }
+
+    let names = sets
+        .iter()
+        .map(|(_, set)| set.name().to_lowercase())
+        .collect::<Vec<String>>();
+
+    let uuids = sets.iter().map(|(uuid, _)| *uuid).collect::<Vec<Uuid>>();
+
+    sets_list.set_filter_func(
+        clone!(@strong filter_entry => move |row: &gtk::ListBoxRow| {
+            let filter = filter_entry.text().to_lowercase();
+
+            usize::try_from(row.index())
+                .ok()
+                .and_then(|n| names.get(n))
+                .is_some_and(|name| name.contains(&filter))
         }),
     );
 
-    view.samples_sidebar_add_to_prev_button.connect_clicked(
-        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
-            update(
-                model_ptr.clone(),
-                &view,
-                AppMessage::SampleSidebarAddToMostRecentlyUsedSetClicked
-            );
+    filter_entry.connect_search_changed(clone!(@strong sets_list => move |_| {
+        sets_list.invalidate_filter();
+    }));
+
+    sets_list.connect_row_activated(
+        clone!(@strong model_ptr, @strong view, @strong popover, @strong uuids =>
+            move |_, row: &gtk::ListBoxRow| {
+                if let Some(uuid) = usize::try_from(row.index()).ok().and_then(|n| uuids.get(n)) {
+                    popover.popdown();
+                    update(model_ptr.clone(), &view, AppMessage::SampleSidebarAddToSetChosen(*uuid));
+                }
+            }
+        ),
+    );
+
+    filter_entry.connect_activate(clone!(@strong sets_list => move |_| {
+        let mut n = 0;
+
+        while let Some(row) = sets_list.row_at_index(n) {
+            if row.is_child_visible() {
+                row.activate();
+                break;
+            }
+
+            n += 1;
+        }
+    }));
+
+    let scroller = gtk::ScrolledWindow::new();
+    scroller.set_child(Some(&sets_list));
+    scroller.set_propagate_natural_height(true);
+    scroller.set_max_content_height(320);
+
+    let new_set_button = gtk::Button::with_label("New set…");
+
+    new_set_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view, @strong popover => move |_: &gtk::Button| {
+            popover.popdown();
+            update(model_ptr.clone(), &view, AppMessage::SampleSidebarAddToNewSetClicked);
         }),
     );
+
+    content.append(&filter_entry);
+    content.append(&scroller);
+    content.append(&new_set_button);
+
+    popover.set_child(Some(&content));
+    popover.set_parent(&*view.samples_sidebar_add_to_set_button);
+
+    popover.connect_closed(|popover: &gtk::Popover| {
+        popover.unparent();
+    });
+
+    popover.popup();
+    filter_entry.grab_focus();
+
+    update(
+        model_ptr.clone(),
+        view,
+        AppMessage::SampleSidebarAddToSetPopoverOpened,
+    );
+}
+
+/// Popover listing the saved searches and recently used filters, choosing one of which puts
+/// it in the filter entry of the main samples list.
+pub fn searches_popover(model_ptr: AppModelPtr, view: &AsampoView, model: &AppModel) {
+    let (saved_searches, filter_history) = match &model.config {
+        Some(config) => (config.saved_searches.clone(), config.filter_history.clone()),
+        None => (Vec::new(), Vec::new()),
+    };
+
+    let popover = gtk::Popover::new();
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 4);
+
+    let heading = |text: &str| {
+        let label = gtk::Label::new(Some(text));
+        label.set_xalign(0.0);
+        label.add_css_class("dim-label");
+        label
+    };
+
+    let query_button = |label: &str, query: &str| {
+        let button = gtk::Button::with_label(label);
+        button.set_has_frame(false);
+        button.set_hexpand(true);
+        button.set_tooltip_text(Some(query));
+
+        let query = query.to_string();
+
+        if let Some(label) = button.child().and_downcast::<gtk::Label>() {
+            label.set_xalign(0.0);
+            label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+            label.set_max_width_chars(32);
+        }
+
+        button.connect_clicked(
+            clone!(@strong view, @strong popover, @strong query => move |_: &gtk::Button| {
+                popover.popdown();
+                view.samples_list_filter_entry.set_text(&query);
+            }),
+        );
+
+        button
+    };
+
+    if !saved_searches.is_empty() {
+        content.append(&heading("Saved searches"));
+    }
+
+    for (name, query) in saved_searches.iter() {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let remove_button = gtk::Button::from_icon_name("user-trash-symbolic");
+        remove_button.set_has_frame(false);
+        remove_button.set_tooltip_text(Some("Remove saved search"));
+
+        remove_button.connect_clicked(
+            clone!(@strong model_ptr, @strong view, @strong popover, @strong name =>
+                move |_: &gtk::Button| {
+                    popover.popdown();
+                    update(model_ptr.clone(), &view, AppMessage::SavedSearchRemoved(name.clone()));
+                }
+            ),
+        );
+
+        row.append(&query_button(name, query));
+        row.append(&remove_button);
+        content.append(&row);
+    }
+
+    content.append(&heading("Recent"));
+
+    if filter_history.is_empty() {
+        content.append(&gtk::Label::new(Some("No recent searches")));
+    }
+
+    for query in filter_history.iter() {
+        content.append(&query_button(query, query));
+    }
+
+    let save_button = gtk::Button::with_label("Save current search…");
+    save_button.set_sensitive(!model.viewvalues.samples_list.filter.is_empty());
+
+    save_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view, @strong popover => move |_: &gtk::Button| {
+            popover.popdown();
+            update(model_ptr.clone(), &view, AppMessage::SamplesSaveSearchClicked);
+        }),
+    );
+
+    content.append(&save_button);
+
+    popover.set_child(Some(&content));
+    popover.set_parent(&*view.samples_list_searches_button);
+
+    popover.connect_closed(|popover: &gtk::Popover| {
+        popover.unparent();
+    });
+
+    popover.popup();
+
+    update(
+        model_ptr.clone(),
+        view,
+        AppMessage::SamplesSearchesPopoverOpened,
+    );
+}
+
+/// How long ago something happened, for listing recently played samples.
+fn time_ago(instant: Instant) -> String {
+    match instant.elapsed().as_secs() {
+        0..=9 => "just now".to_string(),
+        secs @ 10..=59 => format!("{secs} s ago"),
+        secs @ 60..=3599 => format!("{} min ago", secs / 60),
+        secs => format!("{} h ago", secs / 3600),
+    }
+}
+
+/// List the most recently played samples, most recent first. Choosing one reveals it in
+/// the samples list.
+pub fn history_popover(model_ptr: AppModelPtr, view: &AsampoView, model: &AppModel) {
+    let popover = gtk::Popover::new();
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 4);
+
+    if model.samples_audition_history.is_empty() {
+        content.append(&gtk::Label::new(Some("No recently played samples")));
+    }
+
+    for (sample, played) in model.samples_audition_history.iter() {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        let name = gtk::Label::new(Some(&model.sample_display_name(sample)));
+        let time = gtk::Label::new(Some(&time_ago(*played)));
+
+        name.set_xalign(0.0);
+        name.set_hexpand(true);
+        name.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        name.set_max_width_chars(32);
+        time.add_css_class("dim-label");
+
+        row.append(&name);
+        row.append(&time);
+
+        let button = gtk::Button::new();
+        button.set_has_frame(false);
+        button.set_child(Some(&row));
+        button.set_tooltip_text(Some(sample.uri().as_str()));
+
+        button.connect_clicked(
+            clone!(@strong model_ptr, @strong view, @strong popover, @strong sample =>
+                move |_: &gtk::Button| {
+                    popover.popdown();
+
+                    update(
+                        model_ptr.clone(),
+                        &view,
+                        AppMessage::SamplesHistoryEntryClicked(sample.clone()),
+                    );
+                }
+            ),
+        );
+
+        content.append(&button);
+    }
+
+    popover.set_child(Some(&content));
+    popover.set_parent(&*view.samples_list_history_button);
+
+    popover.connect_closed(|popover: &gtk::Popover| {
+        popover.unparent();
+    });
+
+    popover.popup();
+
+    update(
+        model_ptr.clone(),
+        view,
+        AppMessage::SamplesHistoryPopoverOpened,
+    );
+}
+
+/// Show which samples are being compared, and which of them was played last.
+pub fn update_samples_compare(view: &AsampoView, model: &AppModel) {
+    let selected = model.samplelist_selected_sample.is_some();
+
+    view.samples_sidebar_compare_a_button
+        .set_sensitive(selected);
+    view.samples_sidebar_compare_b_button
+        .set_sensitive(selected);
+
+    let names = [&model.samples_compare_a, &model.samples_compare_b]
+        .map(|sample| sample.as_ref().map(|s| model.sample_display_name(s)));
+
+    view.samples_sidebar_compare_a_button
+        .set_tooltip_text(Some(&match &names[0] {
+            Some(name) => format!("Compare the selected sample as A (now {name})"),
+            None => "Compare the selected sample as A".to_string(),
+        }));
+
+    view.samples_sidebar_compare_b_button
+        .set_tooltip_text(Some(&match &names[1] {
+            Some(name) => format!("Compare the selected sample as B (now {name})"),
+            None => "Compare the selected sample as B".to_string(),
+        }));
+
+    view.samples_sidebar_compare_switch_button
+        .set_sensitive(names.iter().all(Option::is_some));
+
+    view.samples_sidebar_compare_switch_button
+        .set_label(match model.samples_compare_playing {
+            Some(CompareSlot::A) => "Playing A",
+            Some(CompareSlot::B) => "Playing B",
+            None => "A/B",
+        });
 }
 
 pub fn update_samples_sidebar(_model_ptr: AppModelPtr, model: AppModel, view: &AsampoView) {
+    view.samples_sidebar_rename_button
+        .set_sensitive(model.samplelist_selected_sample.is_some());
+    view.samples_sidebar_favorite_button
+        .set_sensitive(model.samplelist_selected_sample.is_some());
+
+    let favorite = model
+        .samplelist_selected_sample
+        .as_ref()
+        .is_some_and(|sample| model.is_favorite(sample));
+
+    if view.samples_sidebar_favorite_button.is_active() != favorite {
+        view.samples_sidebar_favorite_button.set_active(favorite);
+    }
+
     match &model.samplelist_selected_sample {
         Some(sample) => {
-            view.samples_sidebar_name_label.set_text(sample.name());
+            view.samples_sidebar_name_label
+                .set_text(&model.sample_display_name(sample));
+            view.samples_sidebar_name_label
+                .set_tooltip_text(Some(sample.uri().as_str()));
 
             view.samples_sidebar_rate_label
                 .set_text(&format!("{} Hz", sample.metadata().rate));
@@ -196,16 +927,18 @@ pub fn update_samples_sidebar(_model_ptr: AppModelPtr, model: AppModel, view: &A
             view.samples_sidebar_length_label
                 .set_text(&util::readable_length(sample.metadata().length_millis));
 
-            match sample.source_uuid() {
-                Some(uuid) => view.samples_sidebar_source_label.set_text(
-                    model
-                        .sources
-                        .get(uuid)
-                        .map_or("???", |src| src.name().unwrap_or("Unnamed")),
-                ),
-                None => view.samples_sidebar_source_label.set_text("-"),
+            let source_name = match sample.source_uuid() {
+                Some(uuid) => model
+                    .sources
+                    .get(uuid)
+                    .map_or("???", |src| src.name().unwrap_or("Unnamed")),
+                None => "-",
             };
 
+            view.samples_sidebar_source_label.set_text(source_name);
+            view.samples_sidebar_source_label
+                .set_tooltip_text(Some(source_name));
+
             view.samples_sidebar_sets_list.remove_all();
 
             let mut containing_sets = model
@@ -228,10 +961,17 @@ pub fn update_samples_sidebar(_model_ptr: AppModelPtr, model: AppModel, view: &A
                     *uuid,
                 ));
 
-                stuff
+                let set_button = stuff
                     .object::<gtk::Button>(format!("{uuid}-combo-button-label"))
-                    .unwrap()
-                    .set_label(set.name());
+                    .unwrap();
+
+                set_button.set_label(set.name());
+                set_button.set_tooltip_text(Some(set.name()));
+
+                if let Some(label) = set_button.child().and_downcast::<gtk::Label>() {
+                    label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+                    label.set_max_width_chars(24);
+                }
 
                 view.samples_sidebar_sets_list.append(
                     &stuff
@@ -242,12 +982,21 @@ pub fn update_samples_sidebar(_model_ptr: AppModelPtr, model: AppModel, view: &A
         }
 
         None => {
-            view.samples_sidebar_name_label.set_text("-");
+            match model.samplelist_selected_samples.len() {
+                0 | 1 => view.samples_sidebar_name_label.set_text("-"),
+                n => view
+                    .samples_sidebar_name_label
+                    .set_text(&format!("{n} samples selected")),
+            }
+
+            view.samples_sidebar_name_label.set_tooltip_text(None);
             view.samples_sidebar_rate_label.set_text("-");
             view.samples_sidebar_format_label.set_text("-");
             view.samples_sidebar_size_label.set_text("-");
             view.samples_sidebar_length_label.set_text("-");
             view.samples_sidebar_source_label.set_text("-");
+            view.samples_sidebar_source_label.set_tooltip_text(None);
+            view.samples_sidebar_sets_list.remove_all();
         }
     }
 }
@@ -4,13 +4,18 @@
 
 use gtk::{
     glib::clone,
-    prelude::{ButtonExt, FrameExt, WidgetExt},
+    prelude::{ButtonExt, FrameExt, GestureSingleExt, WidgetExt},
+    GestureClick,
 };
 use libasampo::samplesets::DrumkitLabel;
 
-use crate::{model::AppModel, update, AppMessage, AppModelPtr, AsampoView};
+use crate::{
+    config::{AppConfig, NUM_PADS},
+    model::AppModel,
+    update, AppMessage, AppModelPtr, AsampoView,
+};
 
-pub const LABELS: [DrumkitLabel; 16] = [
+pub const LABELS: [DrumkitLabel; NUM_PADS] = [
     DrumkitLabel::RimShot,
     DrumkitLabel::Clap,
     DrumkitLabel::ClosedHihat,
@@ -35,7 +40,7 @@ pub fn setup_sequences_page(model_ptr: AppModelPtr, view: &AsampoView) {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DrumMachineView {
-    pad_buttons: [gtk::Button; 16],
+    pad_buttons: [gtk::Button; NUM_PADS],
     part_buttons: [gtk::Button; 4],
     step_buttons: [gtk::Button; 16],
 }
@@ -52,6 +57,14 @@ fn setup_drum_machine_view(model_ptr: AppModelPtr, view: &AsampoView) {
             );
         };
 
+        (toggle $name:expr, $x:ident => $message:expr) => {
+            objects.object::<gtk::ToggleButton>($name).unwrap().connect_toggled(
+                clone!(@strong model_ptr, @strong view => move |$x: &gtk::ToggleButton| {
+                    update(model_ptr.clone(), &view, $message);
+                })
+            );
+        };
+
         (button $name:expr, $message:expr) => {
             objects.object::<gtk::Button>($name).unwrap().connect_clicked(
                 clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
@@ -67,9 +80,14 @@ fn setup_drum_machine_view(model_ptr: AppModelPtr, view: &AsampoView) {
     connect!(spinner "sequences-editor-swing-entry",
         x => AppMessage::DrumMachineSwingChanged(x.value_as_int() as u32));
 
+    connect!(spinner "sequences-editor-volume-entry",
+        x => AppMessage::DrumMachineVolumeChanged(x.value_as_int() as u32));
+
     connect!(button "sequences-editor-play-button", AppMessage::DrumMachinePlayClicked);
     connect!(button "sequences-editor-stop-button", AppMessage::DrumMachineStopClicked);
     connect!(button "sequences-editor-back-button", AppMessage::DrumMachineBackClicked);
+    connect!(toggle "sequences-editor-metronome-button",
+        x => AppMessage::DrumMachineMetronomeToggled(x.is_active()));
     connect!(button "sequences-editor-save-seq-button", AppMessage::DrumMachineSaveSequenceClicked);
     connect!(button "sequences-editor-save-seq-as-button",
         AppMessage::DrumMachineSaveSequenceAsClicked);
@@ -108,23 +126,36 @@ fn setup_drum_machine_view(model_ptr: AppModelPtr, view: &AsampoView) {
         connect!(button format!("sequences-editor-step-{}", index),
             AppMessage::DrumMachineStepClicked(index));
 
-        step_buttons.push(
-            objects
-                .object::<gtk::Button>(format!("sequences-editor-step-{}", index))
-                .unwrap(),
+        let step_button = objects
+            .object::<gtk::Button>(format!("sequences-editor-step-{}", index))
+            .unwrap();
+
+        let right_clicked = GestureClick::new();
+        right_clicked.set_button(gtk::gdk::BUTTON_SECONDARY);
+
+        right_clicked.connect_released(
+            clone!(@strong model_ptr, @strong view => move |_, _, _, _| {
+                update(model_ptr.clone(), &view, AppMessage::DrumMachineStepVelocityCycled(index));
+            }),
         );
+
+        step_button.add_controller(right_clicked);
+        step_buttons.push(step_button);
     }
 
-    let pad_buttons: [gtk::Button; 16] = pad_buttons.try_into().unwrap();
+    let pad_buttons: [gtk::Button; NUM_PADS] = pad_buttons.try_into().unwrap();
     let part_buttons: [gtk::Button; 4] = part_buttons.try_into().unwrap();
     let step_buttons: [gtk::Button; 16] = step_buttons.try_into().unwrap();
 
-    let mut model = model_ptr.take().unwrap();
-    model.viewvalues.drum_machine = Some(DrumMachineView {
+    let drum_machine_view = DrumMachineView {
         pad_buttons,
         part_buttons,
         step_buttons,
-    });
+    };
+
+    let mut model = model_ptr.take().unwrap();
+    update_pad_labels(&drum_machine_view, &model);
+    model.viewvalues.drum_machine = Some(drum_machine_view);
     model_ptr.replace(Some(model));
 
     let root = objects.object::<gtk::Box>("drum-machine-root").unwrap();
@@ -133,11 +164,28 @@ fn setup_drum_machine_view(model_ptr: AppModelPtr, view: &AsampoView) {
         .set_child(Some(&root));
 }
 
+/// Name the pads using the label set chosen in the settings.
+fn update_pad_labels(drum_machine_view: &DrumMachineView, model: &AppModel) {
+    let labels = model
+        .config
+        .as_ref()
+        .map(AppConfig::pad_labels)
+        .unwrap_or_else(|| AppConfig::default().pad_labels());
+
+    for (button, label) in drum_machine_view.pad_buttons.iter().zip(labels.iter()) {
+        if button.label().as_deref() != Some(label.as_str()) {
+            button.set_label(label);
+        }
+    }
+}
+
 pub fn update_drum_machine_view(model: AppModel) {
     let drum_machine_model = &model.drum_machine;
     let drum_machine_view = &model.viewvalues.drum_machine.as_ref().unwrap();
 
-    assert!(drum_machine_model.activated_pad < 16);
+    update_pad_labels(drum_machine_view, &model);
+
+    assert!(drum_machine_model.activated_pad < NUM_PADS);
 
     if let Some(event) = &drum_machine_model.event_latest {
         for (i, label) in LABELS.iter().enumerate() {
@@ -155,7 +203,7 @@ pub fn update_drum_machine_view(model: AppModel) {
         }
     }
 
-    for i in 0..16 {
+    for i in 0..NUM_PADS {
         if i == drum_machine_model.activated_pad {
             drum_machine_view.pad_buttons[i].add_css_class("activated");
         } else {
@@ -165,10 +213,21 @@ pub fn update_drum_machine_view(model: AppModel) {
 
     for i in 0..16 {
         if let Some(labels) = model.drum_machine.sequence.labels_at_step(i) {
+            let step_button = &drum_machine_view.step_buttons[i];
+
+            step_button.remove_css_class("velocity-low");
+            step_button.remove_css_class("velocity-high");
+
             if labels.contains(&LABELS[drum_machine_model.activated_pad]) {
-                drum_machine_view.step_buttons[i].add_css_class("activated");
+                step_button.add_css_class("activated");
+
+                match drum_machine_model.velocity_level(i, drum_machine_model.activated_pad) {
+                    0 => step_button.add_css_class("velocity-low"),
+                    2 => step_button.add_css_class("velocity-high"),
+                    _ => (),
+                }
             } else {
-                drum_machine_view.step_buttons[i].remove_css_class("activated");
+                step_button.remove_css_class("activated");
             }
         }
     }
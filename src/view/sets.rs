@@ -16,7 +16,7 @@ use crate::{
         idize_builder_template, resource_as_string, set_dropdown_choice,
         strs_dropdown_get_selected, uuidize_builder_template,
     },
-    view::AsampoView,
+    view::{menus, AsampoView},
     AppMessage,
 };
 
@@ -62,6 +62,24 @@ pub fn setup_sets_page(model_ptr: AppModelPtr, view: &AsampoView) {
             update(model_ptr.clone(), &view, AppMessage::SampleSetDetailsExportClicked);
         }),
     );
+
+    view.sets_details_stop_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::StopAllSoundButtonClicked);
+        }),
+    );
+
+    view.sets_details_cover_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SampleSetCoverBrowseClicked);
+        }),
+    );
+
+    view.sets_details_cover_clear_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SampleSetCoverClearClicked);
+        }),
+    );
 }
 
 pub fn update_samplesets_list(model_ptr: AppModelPtr, model: AppModel, view: &AsampoView) {
@@ -84,7 +102,30 @@ pub fn update_samplesets_list(model_ptr: AppModelPtr, model: AppModel, view: &As
             .object::<gtk::Label>(format!("{uuid}-name-label"))
             .unwrap();
 
-        name_label.set_text(model.sets.get(uuid).unwrap().name());
+        let name = model.sets.get(uuid).unwrap().name();
+        name_label.set_text(name);
+        name_label.set_tooltip_text(Some(name));
+
+        let name_clicked = GestureClick::new();
+
+        name_clicked.connect_pressed(clone!(@strong model_ptr, @strong view, @strong uuid =>
+            move |_: &GestureClick, n_press, _, _| {
+                if n_press == 2 {
+                    update(model_ptr.clone(), &view, AppMessage::SampleSetRenameClicked(uuid));
+                }
+            }
+        ));
+
+        name_label.add_controller(name_clicked);
+
+        objects
+            .object::<gtk::Button>(format!("{uuid}-duplicate-button"))
+            .unwrap()
+            .connect_clicked(
+                clone!(@strong model_ptr, @strong view, @strong uuid => move |_: &gtk::Button| {
+                    update(model_ptr.clone(), &view, AppMessage::SampleSetDuplicateClicked(uuid));
+                }),
+            );
 
         let clicked = GestureClick::new();
 
@@ -104,6 +145,18 @@ pub fn update_samplesets_list(model_ptr: AppModelPtr, model: AppModel, view: &As
 
         row.add_controller(keyup);
 
+        menus::add_context_menu(
+            &row,
+            menus::uuid_menu(
+                &[
+                    ("Rename…", "win.set_rename"),
+                    ("Duplicate", "win.set_duplicate"),
+                    ("Export…", "win.set_export"),
+                ],
+                uuid,
+            ),
+        );
+
         row.connect_activate(
             clone!(@strong model_ptr, @strong view, @strong uuid => move |_: &gtk::ListBoxRow| {
                 update(model_ptr.clone(), &view, AppMessage::SampleSetSelected(uuid));
@@ -123,6 +176,17 @@ pub fn update_samplesets_detail(model_ptr: AppModelPtr, model: AppModel, view: &
     {
         Some(set) => {
             view.sets_details_name_label.set_text(set.name());
+            view.sets_details_name_label
+                .set_tooltip_text(Some(set.name()));
+
+            let cover = model.sets_cover_images.get(set.uuid());
+
+            view.sets_details_cover_picture
+                .set_filename(cover.map(String::as_str));
+            view.sets_details_cover_picture.set_visible(cover.is_some());
+            view.sets_details_cover_button.set_sensitive(true);
+            view.sets_details_cover_clear_button
+                .set_sensitive(cover.is_some());
 
             set_dropdown_choice(
                 &view.sets_details_labelling_kind_entry,
@@ -133,10 +197,23 @@ pub fn update_samplesets_detail(model_ptr: AppModelPtr, model: AppModel, view: &
                 },
             );
 
+            let num_excluded = model
+                .sets_export_excluded
+                .get(set.uuid())
+                .map(|excluded| excluded.len())
+                .unwrap_or(0);
+
             view.sets_details_sample_list_frame
-                .set_label(Some(&format!("Samples ({})", set.len())));
+                .set_label(Some(&match num_excluded {
+                    0 => format!("Samples ({})", set.len()),
+                    n => format!("Samples ({}, {n} excluded from export)", set.len()),
+                }));
 
-            for (row_index, sample) in set.list().iter().enumerate() {
+            let members = model
+                .ordered_set_members(set.uuid())
+                .expect("Selected sample set should exist");
+
+            for (row_index, sample) in members.iter().enumerate() {
                 let objects = gtk::Builder::from_string(&idize_builder_template(
                     &resource_as_string("/sets-details-sample-list-row.ui").unwrap(),
                     row_index,
@@ -149,7 +226,51 @@ pub fn update_samplesets_detail(model_ptr: AppModelPtr, model: AppModel, view: &
                 let name_label = objects
                     .object::<gtk::Label>(format!("{row_index}-name-label"))
                     .unwrap();
-                name_label.set_label(sample.name());
+                name_label.set_label(&model.sample_display_name(sample));
+                name_label.set_tooltip_text(Some(sample.uri().as_str()));
+
+                let excluded = model.is_excluded_from_export(set.uuid(), sample);
+
+                if excluded {
+                    name_label.add_css_class("dim-label");
+                }
+
+                let export_checkbutton = objects
+                    .object::<gtk::CheckButton>(format!("{row_index}-export-checkbutton"))
+                    .unwrap();
+
+                export_checkbutton.set_active(!excluded);
+
+                export_checkbutton.connect_toggled(
+                    clone!(@strong model_ptr, @strong view, @strong sample => move |e: &gtk::CheckButton| {
+                        update(
+                            model_ptr.clone(),
+                            &view,
+                            AppMessage::SampleSetMemberExportToggled(sample.clone(), e.is_active())
+                        );
+                    }),
+                );
+
+                for (suffix, offset, enabled) in [
+                    ("move-up-button", -1, row_index > 0),
+                    ("move-down-button", 1, row_index + 1 < members.len()),
+                ] {
+                    let button = objects
+                        .object::<gtk::Button>(format!("{row_index}-{suffix}"))
+                        .unwrap();
+
+                    button.set_sensitive(enabled);
+
+                    button.connect_clicked(
+                        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+                            update(
+                                model_ptr.clone(),
+                                &view,
+                                AppMessage::SampleSetMemberMoved(row_index, offset)
+                            );
+                        }),
+                    );
+                }
 
                 let clicked = GestureClick::new();
 
@@ -162,7 +283,33 @@ pub fn update_samplesets_detail(model_ptr: AppModelPtr, model: AppModel, view: &
 
                 row.add_controller(clicked);
 
-                let bound_sample = (*sample).clone();
+                objects
+                    .object::<gtk::Button>(format!("{row_index}-find-button"))
+                    .unwrap()
+                    .connect_clicked(
+                        clone!(@strong model_ptr, @strong view, @strong sample => move |_: &gtk::Button| {
+                            update(
+                                model_ptr.clone(),
+                                &view,
+                                AppMessage::SampleSetMemberFindClicked(sample.clone())
+                            );
+                        }),
+                    );
+
+                objects
+                    .object::<gtk::Button>(format!("{row_index}-play-button"))
+                    .unwrap()
+                    .connect_clicked(
+                        clone!(@strong model_ptr, @strong view, @strong sample => move |_: &gtk::Button| {
+                            update(
+                                model_ptr.clone(),
+                                &view,
+                                AppMessage::SampleSetMemberPlayClicked(sample.clone())
+                            );
+                        }),
+                    );
+
+                let bound_sample = sample.clone();
 
                 row.connect_activate(
                     clone!(@strong model_ptr, @strong view => move |_: &gtk::ListBoxRow| {
@@ -179,6 +326,11 @@ pub fn update_samplesets_detail(model_ptr: AppModelPtr, model: AppModel, view: &
         }
         None => {
             view.sets_details_name_label.set_text("");
+            view.sets_details_name_label.set_tooltip_text(None);
+            view.sets_details_cover_picture.set_filename(None::<&str>);
+            view.sets_details_cover_picture.set_visible(false);
+            view.sets_details_cover_button.set_sensitive(false);
+            view.sets_details_cover_clear_button.set_sensitive(false);
         }
     }
 }
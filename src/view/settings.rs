@@ -2,12 +2,14 @@
 //
 // Copyright (c) 2024 Mikael Forsberg (github.com/mkforsb)
 
-use gtk::{glib::clone, prelude::*, StringList};
+use std::cell::{Cell, RefCell};
+
+use gtk::{gdk::Display, glib::clone, prelude::*, StringList};
 
 use crate::{
-    config,
+    config::{self, ColorScheme, VelocityCurve},
     ext::{OptionMapExt, WithModel},
-    model::AppModelPtr,
+    model::{AppModel, AppModelPtr},
     update,
     util::{set_dropdown_choice, strs_dropdown_get_selected},
     AppMessage,
@@ -15,6 +17,25 @@ use crate::{
 
 use super::AsampoView;
 
+thread_local! {
+    /// Set while the settings widgets are brought in line with the model, so that their
+    /// change handlers don't send the model its own settings back.
+    static REFRESHING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn refreshing(f: impl FnOnce()) {
+    let was_refreshing = REFRESHING.replace(true);
+    f();
+    REFRESHING.set(was_refreshing);
+}
+
+/// Send a message for a setting changed by the user, ignoring changes made by refreshing.
+fn update_setting(model_ptr: AppModelPtr, view: &AsampoView, message: AppMessage) {
+    if !REFRESHING.get() {
+        update(model_ptr, view, message);
+    }
+}
+
 pub fn setup_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
     view.settings_output_sample_rate_entry
         .set_model(Some(&StringList::new(
@@ -31,6 +52,82 @@ pub fn setup_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
             &config::SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS.keys(),
         )));
 
+    view.settings_workspace_sample_playback_behavior_entry
+        .set_model(Some(&StringList::new(
+            &config::WORKSPACE_SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS.keys(),
+        )));
+
+    view.settings_sample_filter_mode_entry
+        .set_model(Some(&StringList::new(
+            &config::SAMPLE_FILTER_MODE_OPTIONS.keys(),
+        )));
+
+    view.settings_watch_sources_entry
+        .set_model(Some(&StringList::new(
+            &config::WATCH_SOURCES_OPTIONS.keys(),
+        )));
+
+    view.settings_source_loading_entry
+        .set_model(Some(&StringList::new(
+            &config::SOURCE_LOADING_OPTIONS.keys(),
+        )));
+
+    view.settings_samples_list_keep_place_entry
+        .set_model(Some(&StringList::new(
+            &config::SAMPLES_LIST_KEEP_PLACE_OPTIONS.keys(),
+        )));
+
+    view.settings_stop_preview_on_page_change_entry
+        .set_model(Some(&StringList::new(
+            &config::STOP_PREVIEW_ON_PAGE_CHANGE_OPTIONS.keys(),
+        )));
+
+    view.settings_velocity_curve_entry
+        .set_model(Some(&StringList::new(
+            &config::VELOCITY_CURVE_OPTIONS.keys(),
+        )));
+
+    view.settings_pad_labels_entry
+        .set_model(Some(&StringList::new(
+            &config::PAD_LABEL_SET_OPTIONS.keys(),
+        )));
+
+    view.settings_color_scheme_entry
+        .set_model(Some(&StringList::new(&config::COLOR_SCHEME_OPTIONS.keys())));
+
+    view.settings_ui_scale_entry
+        .set_model(Some(&StringList::new(&config::UI_SCALE_OPTIONS.keys())));
+
+    view.settings_troubleshooting_log_entry
+        .set_model(Some(&StringList::new(
+            &config::TROUBLESHOOTING_LOG_OPTIONS.keys(),
+        )));
+
+    view.settings_profiler_overlay_entry
+        .set_model(Some(&StringList::new(
+            &config::PROFILER_OVERLAY_OPTIONS.keys(),
+        )));
+
+    view.settings_savefile_encryption_entry
+        .set_model(Some(&StringList::new(
+            &config::SAVEFILE_ENCRYPTION_OPTIONS.keys(),
+        )));
+
+    view.settings_savefile_password_memory_entry
+        .set_model(Some(&StringList::new(
+            &config::SAVEFILE_PASSWORD_MEMORY_OPTIONS.keys(),
+        )));
+
+    view.settings_reopen_last_workspace_entry
+        .set_model(Some(&StringList::new(
+            &config::REOPEN_LAST_WORKSPACE_OPTIONS.keys(),
+        )));
+
+    view.settings_review_changes_entry
+        .set_model(Some(&StringList::new(
+            &config::REVIEW_CHANGES_OPTIONS.keys(),
+        )));
+
     // we don't want to trigger signals in setup_settings_page(), so update the settings
     // view before hooking up the signals.
     update_settings_page(model_ptr.clone(), view);
@@ -38,15 +135,21 @@ pub fn setup_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
     view.settings_output_sample_rate_entry
         .connect_selected_item_notify(
             clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
-                update(model_ptr.clone(), &view, AppMessage::SettingsOutputSampleRateChanged(
+                update_setting(model_ptr.clone(), &view, AppMessage::SettingsOutputSampleRateChanged(
                     strs_dropdown_get_selected(e)
                 ))
             }),
         );
 
+    view.settings_test_output_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SettingsTestOutputClicked);
+        }),
+    );
+
     view.settings_buffer_size_entry.connect_value_changed(
         clone!(@strong model_ptr, @strong view => move |e: &gtk::SpinButton| {
-            update(
+            update_setting(
                 model_ptr.clone(),
                 &view,
                 AppMessage::SettingsBufferSizeChanged(e.value() as u16)
@@ -57,7 +160,7 @@ pub fn setup_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
     view.settings_sample_rate_conversion_quality_entry
         .connect_selected_item_notify(
             clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
-                update(
+                update_setting(
                     model_ptr.clone(),
                     &view,
                     AppMessage::SettingsSampleRateConversionQualityChanged(
@@ -70,7 +173,7 @@ pub fn setup_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
     view.settings_sample_playback_behavior_entry
         .connect_selected_item_notify(
             clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
-                update(
+                update_setting(
                     model_ptr.clone(),
                     &view,
                     AppMessage::SettingsSamplePlaybackBehaviorChanged(
@@ -79,41 +182,442 @@ pub fn setup_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
                 )
             }),
         );
-}
 
-pub fn update_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
-    model_ptr.with_model(|model| {
-        let config = model.config.as_ref().expect("A config should be present");
+    view.settings_workspace_sample_playback_behavior_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsWorkspaceSamplePlaybackBehaviorChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
 
-        set_dropdown_choice(
-            &view.settings_output_sample_rate_entry,
-            &config::OUTPUT_SAMPLE_RATE_OPTIONS,
-            &config.output_samplerate_hz,
+    view.settings_sample_filter_mode_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsSampleFilterModeChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
         );
 
-        view.settings_buffer_size_entry
-            .set_value(config.buffer_size_frames.into());
+    view.settings_watch_sources_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsWatchSourcesChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
 
-        view.settings_latency_approx_label
-            .set_text(&model.viewvalues.settings_latency_approx_label);
+    view.settings_source_loading_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsSourceLoadingChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
 
-        set_dropdown_choice(
-            &view.settings_sample_rate_conversion_quality_entry,
-            &config::SAMPLE_RATE_CONVERSION_QUALITY_OPTIONS,
-            &config.sample_rate_conversion_quality,
+    view.settings_samples_list_keep_place_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsSamplesListKeepPlaceChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
         );
 
-        set_dropdown_choice(
-            &view.settings_sample_playback_behavior_entry,
-            &config::SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS,
-            &config.sample_playback_behavior,
+    view.settings_stop_preview_on_page_change_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsStopPreviewOnPageChangeChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
         );
 
-        if view.settings_config_save_path_entry.text() != config.config_save_path {
-            view.settings_config_save_path_entry
-                .set_text(&config.config_save_path);
-        }
+    view.settings_pad_labels_entry.connect_selected_item_notify(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+            update_setting(
+                model_ptr.clone(),
+                &view,
+                AppMessage::SettingsPadLabelsChanged(strs_dropdown_get_selected(e))
+            )
+        }),
+    );
+
+    view.settings_pad_labels_edit_button.connect_clicked(
+        clone!(@strong model_ptr, @strong view => move |_: &gtk::Button| {
+            update(model_ptr.clone(), &view, AppMessage::SettingsPadLabelsEditClicked);
+        }),
+    );
+
+    view.settings_velocity_curve_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsVelocityCurveChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
+
+    view.settings_color_scheme_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsColorSchemeChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
+
+    view.settings_ui_scale_entry.connect_selected_item_notify(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+            update_setting(
+                model_ptr.clone(),
+                &view,
+                AppMessage::SettingsUiScaleChanged(
+                    strs_dropdown_get_selected(e)
+                )
+            )
+        }),
+    );
+
+    view.settings_troubleshooting_log_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsTroubleshootingLogChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
+
+    view.settings_profiler_overlay_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsProfilerOverlayChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
+
+    view.settings_savefile_encryption_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsSavefileEncryptionChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
+
+    view.settings_savefile_password_memory_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsSavefilePasswordMemoryChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
 
+    view.settings_reopen_last_workspace_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsReopenLastWorkspaceChanged(
+                        strs_dropdown_get_selected(e)
+                    )
+                )
+            }),
+        );
+
+    view.settings_review_changes_entry
+        .connect_selected_item_notify(
+            clone!(@strong model_ptr, @strong view => move |e: &gtk::DropDown| {
+                update_setting(
+                    model_ptr.clone(),
+                    &view,
+                    AppMessage::SettingsReviewChangesChanged(strs_dropdown_get_selected(e))
+                )
+            }),
+        );
+}
+
+pub fn update_settings_page(model_ptr: AppModelPtr, view: &AsampoView) {
+    model_ptr.with_model(|model| {
+        update_settings_view(view, &model);
         model
     })
 }
+
+pub fn update_settings_view(view: &AsampoView, model: &AppModel) {
+    refreshing(|| refresh_settings_view(view, model));
+}
+
+fn refresh_settings_view(view: &AsampoView, model: &AppModel) {
+    let config = model.config.as_ref().expect("A config should be present");
+
+    set_dropdown_choice(
+        &view.settings_output_sample_rate_entry,
+        &config::OUTPUT_SAMPLE_RATE_OPTIONS,
+        &config.output_samplerate_hz,
+    );
+
+    view.settings_buffer_size_entry
+        .set_value(config.buffer_size_frames.into());
+
+    view.settings_latency_approx_label
+        .set_text(&model.viewvalues.settings_latency_approx_label);
+
+    set_dropdown_choice(
+        &view.settings_sample_rate_conversion_quality_entry,
+        &config::SAMPLE_RATE_CONVERSION_QUALITY_OPTIONS,
+        &config.sample_rate_conversion_quality,
+    );
+
+    set_dropdown_choice(
+        &view.settings_sample_playback_behavior_entry,
+        &config::SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS,
+        &config.sample_playback_behavior,
+    );
+
+    update_workspace_settings(view, model);
+
+    set_dropdown_choice(
+        &view.settings_sample_filter_mode_entry,
+        &config::SAMPLE_FILTER_MODE_OPTIONS,
+        &config.sample_filter_mode,
+    );
+
+    set_dropdown_choice(
+        &view.settings_watch_sources_entry,
+        &config::WATCH_SOURCES_OPTIONS,
+        &config.watch_sources,
+    );
+
+    set_dropdown_choice(
+        &view.settings_source_loading_entry,
+        &config::SOURCE_LOADING_OPTIONS,
+        &config.source_loading_samples_per_tick,
+    );
+
+    set_dropdown_choice(
+        &view.settings_samples_list_keep_place_entry,
+        &config::SAMPLES_LIST_KEEP_PLACE_OPTIONS,
+        &config.samples_list_keep_place,
+    );
+
+    set_dropdown_choice(
+        &view.settings_stop_preview_on_page_change_entry,
+        &config::STOP_PREVIEW_ON_PAGE_CHANGE_OPTIONS,
+        &config.stop_preview_on_page_change,
+    );
+
+    set_dropdown_choice(
+        &view.settings_velocity_curve_entry,
+        &config::VELOCITY_CURVE_OPTIONS,
+        &config.velocity_curve,
+    );
+
+    update_velocity_curve_preview(view, &config.velocity_curve);
+
+    set_dropdown_choice(
+        &view.settings_pad_labels_entry,
+        &config::PAD_LABEL_SET_OPTIONS,
+        &config.pad_label_set,
+    );
+
+    set_dropdown_choice(
+        &view.settings_color_scheme_entry,
+        &config::COLOR_SCHEME_OPTIONS,
+        &config.color_scheme,
+    );
+
+    set_dropdown_choice(
+        &view.settings_ui_scale_entry,
+        &config::UI_SCALE_OPTIONS,
+        &config.ui_scale_percent,
+    );
+
+    set_dropdown_choice(
+        &view.settings_troubleshooting_log_entry,
+        &config::TROUBLESHOOTING_LOG_OPTIONS,
+        &config.troubleshooting_log,
+    );
+
+    set_dropdown_choice(
+        &view.settings_profiler_overlay_entry,
+        &config::PROFILER_OVERLAY_OPTIONS,
+        &config.profiler_overlay,
+    );
+
+    set_dropdown_choice(
+        &view.settings_savefile_encryption_entry,
+        &config::SAVEFILE_ENCRYPTION_OPTIONS,
+        &config.encrypt_savefiles,
+    );
+
+    set_dropdown_choice(
+        &view.settings_savefile_password_memory_entry,
+        &config::SAVEFILE_PASSWORD_MEMORY_OPTIONS,
+        &config.remember_savefile_password,
+    );
+
+    set_dropdown_choice(
+        &view.settings_reopen_last_workspace_entry,
+        &config::REOPEN_LAST_WORKSPACE_OPTIONS,
+        &config.reopen_last_workspace,
+    );
+
+    set_dropdown_choice(
+        &view.settings_review_changes_entry,
+        &config::REVIEW_CHANGES_OPTIONS,
+        &config.review_changes_before_saving,
+    );
+
+    if view.settings_config_save_path_entry.text() != config.config_save_path {
+        view.settings_config_save_path_entry
+            .set_text(&config.config_save_path);
+    }
+}
+
+pub fn update_workspace_settings(view: &AsampoView, model: &AppModel) {
+    refreshing(|| {
+        set_dropdown_choice(
+            &view.settings_workspace_sample_playback_behavior_entry,
+            &config::WORKSPACE_SAMPLE_PLAYBACK_BEHAVIOR_OPTIONS,
+            &model.sample_playback_behavior_override,
+        )
+    });
+}
+
+thread_local! {
+    static SCALE_CSS_PROVIDER: RefCell<Option<gtk::CssProvider>> = const { RefCell::new(None) };
+}
+
+/// Whether the color scheme or interface scale settings differ between `old` and `new`.
+pub fn appearance_changed(old: &AppModel, new: &AppModel) -> bool {
+    let appearance = |model: &AppModel| {
+        model
+            .config
+            .as_ref()
+            .map(|config| (config.color_scheme.clone(), config.ui_scale_percent))
+    };
+
+    appearance(old) != appearance(new)
+}
+
+/// Apply the color scheme and interface scale settings to the running application.
+pub fn apply_appearance(model: &AppModel) {
+    let Some(config) = model.config.as_ref() else {
+        return;
+    };
+
+    if let Some(settings) = gtk::Settings::default() {
+        match config.color_scheme {
+            ColorScheme::FollowSystem => {
+                settings.reset_property("gtk-application-prefer-dark-theme")
+            }
+            ColorScheme::Dark => settings.set_gtk_application_prefer_dark_theme(true),
+            ColorScheme::Light => settings.set_gtk_application_prefer_dark_theme(false),
+        }
+    }
+
+    SCALE_CSS_PROVIDER.with_borrow_mut(|provider| {
+        let provider = provider.get_or_insert_with(|| {
+            let provider = gtk::CssProvider::new();
+
+            gtk::style_context_add_provider_for_display(
+                &Display::default().expect("There should be an available display"),
+                &provider,
+                gtk::STYLE_PROVIDER_PRIORITY_USER,
+            );
+
+            provider
+        });
+
+        provider.load_from_string(&format!(
+            "window {{ font-size: {}%; }}",
+            config.ui_scale_percent
+        ));
+    });
+}
+
+fn update_velocity_curve_preview(view: &AsampoView, curve: &VelocityCurve) {
+    let curve = curve.clone();
+
+    view.settings_velocity_curve_preview
+        .set_draw_func(move |area, cr, width, height| {
+            let (w, h) = (width as f64, height as f64);
+            let color = area.color();
+
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                color.alpha() as f64,
+            );
+            cr.set_line_width(1.5);
+            cr.move_to(0.0, h);
+
+            for x in 1..=width {
+                let velocity = x as f32 / w as f32;
+                cr.line_to(x as f64, h - curve.apply(velocity) as f64 * h);
+            }
+
+            if let Err(e) = cr.stroke() {
+                log::log!(log::Level::Error, "Failed to draw velocity curve: {e}");
+            }
+        });
+
+    view.settings_velocity_curve_preview.queue_draw();
+}
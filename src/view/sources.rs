@@ -7,8 +7,8 @@ use libasampo::prelude::*;
 
 use crate::{
     update,
-    util::{resource_as_string, uuidize_builder_template},
-    view::AsampoView,
+    util::{gtk_find_child_by_builder_id, resource_as_string, uuidize_builder_template},
+    view::{menus, AsampoView},
     AppMessage, AppModel, AppModelPtr,
 };
 
@@ -54,6 +54,60 @@ pub fn setup_sources_page(model_ptr: AppModelPtr, view: &AsampoView) {
             update(model_ptr.clone(), &view, AppMessage::AddFilesystemSourceClicked);
         }),
     );
+
+    view.sources_dedup_checkbutton.connect_toggled(
+        clone!(@strong model_ptr, @strong view => move |e: &gtk::CheckButton| {
+            update(model_ptr.clone(), &view, AppMessage::SourcesDedupToggled(e.is_active()));
+        }),
+    );
+}
+
+pub fn update_sources_overlap(model: &AppModel, view: &AsampoView) {
+    let overlaps = model.overlapping_sources();
+
+    for uuid in model.sources_order.iter() {
+        if let Some(icon) = gtk_find_child_by_builder_id::<gtk::Image>(
+            &view.sources_list.get(),
+            &format!("{uuid}-overlap-icon"),
+        ) {
+            match overlaps.get(uuid) {
+                Some(others) => {
+                    let names = others
+                        .iter()
+                        .filter_map(|other| model.sources.get(other))
+                        .map(|source| source.name().unwrap_or("Unnamed"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    icon.set_tooltip_text(Some(&format!("Lists the same files as: {names}")));
+                    icon.set_visible(true);
+                }
+
+                None => icon.set_visible(false),
+            }
+        }
+    }
+
+    view.sources_overlap_bar.set_visible(!overlaps.is_empty());
+}
+
+/// Show a pulsing progress bar next to each source that is still being loaded, advanced
+/// whenever more of its samples have been taken in.
+pub fn update_sources_loading_progress(model: &AppModel, view: &AsampoView) {
+    for uuid in model.sources_order.iter() {
+        if let Some(progress_bar) = gtk_find_child_by_builder_id::<gtk::ProgressBar>(
+            &view.sources_list.get(),
+            &format!("{uuid}-loading-progress-bar"),
+        ) {
+            let loading = model.sources_loading.contains_key(uuid);
+
+            progress_bar.set_visible(loading);
+
+            if loading {
+                progress_bar.pulse();
+            }
+        }
+    }
 }
 
 pub fn update_sources_list(model_ptr: AppModelPtr, model: AppModel, view: &AsampoView) {
@@ -77,6 +131,10 @@ pub fn update_sources_list(model_ptr: AppModelPtr, model: AppModel, view: &Asamp
             .object::<gtk::Label>(&format!("{uuid}-name-label"))
             .unwrap();
 
+        let rescan_button = objects
+            .object::<gtk::Button>(&format!("{uuid}-rescan-button"))
+            .unwrap();
+
         let delete_button = objects
             .object::<gtk::Button>(&format!("{uuid}-delete-button"))
             .unwrap();
@@ -95,7 +153,15 @@ pub fn update_sources_list(model_ptr: AppModelPtr, model: AppModel, view: &Asamp
             }),
         );
 
-        name_label.set_label(model.sources.get(uuid).unwrap().name().unwrap_or("Unnamed"));
+        let name = model.sources.get(uuid).unwrap().name().unwrap_or("Unnamed");
+        name_label.set_label(name);
+        name_label.set_tooltip_text(Some(name));
+
+        rescan_button.connect_clicked(
+            clone!(@strong model_ptr, @strong view, @strong uuid => move |_: &gtk::Button| {
+                update(model_ptr.clone(), &view, AppMessage::SourceRescanClicked(uuid));
+            }),
+        );
 
         delete_button.connect_clicked(
             clone!(@strong model_ptr, @strong view, @strong uuid => move |_: &gtk::Button| {
@@ -111,6 +177,19 @@ pub fn update_sources_list(model_ptr: AppModelPtr, model: AppModel, view: &Asamp
 
         row.add_controller(clicked);
 
+        menus::add_context_menu(
+            &row,
+            menus::uuid_menu(
+                &[
+                    ("Rescan", "win.source_rescan"),
+                    ("Delete", "win.source_delete"),
+                ],
+                uuid,
+            ),
+        );
+
         view.sources_list.append(&row);
     }
+
+    update_sources_overlap(&model, view);
 }